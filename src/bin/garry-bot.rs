@@ -1,16 +1,27 @@
 // Garry Bot - Automation service for merge queue management
 
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 use tokio::sync::Mutex;
+use tokio::task::JoinSet;
 use tokio::time::{interval, Duration};
-use tracing::{info, error, warn};
+use tracing::{info, info_span, error, warn, Instrument};
 
 // Import from main crate
 use garry::bot::VcsAdapter;
-use garry::bot::queue::QueueManager;
+use garry::bot::backport::Backporter;
+use garry::bot::chatops::ChatOps;
+use garry::bot::digest::DigestScheduler;
+use garry::bot::metrics;
+use garry::bot::notifier::Notifier;
+use garry::bot::queue::{QueueManager, QueueSnapshot};
+use garry::bot::server::{self, Health, ServerState};
+use garry::bot::stale::StaleReviewSweeper;
+use garry::review::owners::{self, CodeOwners};
+use garry::review::policy::ApprovalPolicy;
+use garry::review::roles::Roles;
 use garry::bot::adapters::GithubAdapter;
 use garry::utils::config::Config;
-use garry::utils::error::Result;
+use garry::utils::error::{GarryError, Result};
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -26,54 +37,266 @@ async fn main() -> Result<()> {
     
     // Load configuration
     let config = Config::load_with_env()?;
-    info!("Loaded configuration for repository: {}", config.vcs.repository);
+    let configs = config.repository_configs();
+    for repository in &configs {
+        info!("Loaded configuration for repository: {}", repository.vcs.repository);
+    }
     
-    // Create VCS adapter
-    let adapter = match config.vcs.platform.as_str() {
-        "github" => Box::new(GithubAdapter::new(
-            config.vcs.host.clone(),
-            config.vcs.token.clone(),
-            config.vcs.repository.clone(),
-        )?),
-        _ => {
-            error!("Unsupported VCS platform: {}", config.vcs.platform);
-            return Err(garry::utils::error::GarryError::ConfigError(
-                format!("Unsupported platform: {}", config.vcs.platform)
-            ));
-        }
-    };
+    // Bind early, so a port conflict stops the bot before it touches anything
+    let listener = server::bind(&config)?;
     
-    // Set up repository protection
-    info!("Setting up repository protection...");
-    if let Err(e) = adapter.setup_repository_protection(&config.bot.main_branch, "garry-bot").await {
-        warn!("Could not set up repository protection: {}", e);
-        warn!("You may need to configure branch protection manually");
+    // Each repository gets its own adapter, policies and queue; one that
+    // fails to start doesn't keep the others from running
+    let mut bots = Vec::new();
+    for repository in &configs {
+        match RepositoryBot::start(repository).await {
+            Ok(bot) => bots.push(bot),
+            Err(e) if configs.len() > 1 => {
+                error!("Could not start the bot for {}: {}", repository.vcs.repository, e);
+            },
+            Err(e) => return Err(e),
+        }
+    }
+    if bots.is_empty() {
+        return Err(GarryError::ConfigError("No repository could be started".to_string()));
     }
     
-    // Create queue manager
-    let queue_manager = Arc::new(Mutex::new(QueueManager::new(adapter)));
-    
-    info!("Garry Bot started successfully");
-    info!("Monitoring repository: {}", config.vcs.repository);
-    info!("Queue check interval: {}s", config.bot.queue_check_interval);
-    info!("⚠️  Only Garry Bot can push to {} - all merges go through the queue", config.bot.main_branch);
+    // Health checks, metrics, the dashboard and the admin API
+    if let Some(listener) = listener {
+        let mut state = ServerState::new(bots[0].queue.clone(), bots[0].snapshot.clone(), bots[0].health.clone());
+        if configs.len() > 1 {
+            for bot in &bots {
+                state = state.with_repository(bot.repository.clone(), bot.queue.clone(), bot.snapshot.clone(), bot.health.clone());
+            }
+        }
+        if let Some(token) = config.api.token.clone() {
+            state = state.with_token(token);
+        }
+        if config.api.dashboard {
+            state = state.with_dashboard(config.api.dashboard_refresh_seconds);
+        }
+        tokio::spawn(async move {
+            if let Err(e) = server::serve(listener, state).await {
+                error!("{}", e);
+            }
+        });
+    }
     
-    // Start queue processing loop
-    let mut check_interval = interval(Duration::from_secs(config.bot.queue_check_interval));
+    info!("Garry Bot started successfully for {} repositories", bots.len());
     
-    loop {
-        check_interval.tick().await;
+    // Each repository's queue runs in its own task, so one that errors or
+    // stalls doesn't hold up the rest
+    let mut tasks = JoinSet::new();
+    for bot in bots {
+        let span = info_span!("repository", name = %bot.repository);
+        tasks.spawn(bot.run().instrument(span));
+    }
+    while let Some(result) = tasks.join_next().await {
+        if let Err(e) = result {
+            error!("A repository's bot loop stopped: {}", e);
+        }
+    }
+    Err(GarryError::Other("Every repository's bot loop stopped".to_string()))
+}
+
+/// A repository's merge queue and the services that run alongside it
+struct RepositoryBot {
+    /// "owner/repo"
+    repository: String,
+    main_branch: String,
+    queue_check_interval: u64,
+    queue: Arc<Mutex<QueueManager>>,
+    /// The queue as of the last pass, for the dashboard
+    snapshot: Arc<RwLock<QueueSnapshot>>,
+    /// For work that doesn't need the queue locked
+    adapter: Box<dyn VcsAdapter>,
+    health: Arc<Health>,
+    sweeper: Option<StaleReviewSweeper>,
+    chatops: Option<(ChatOps, Notifier)>,
+    digests: Option<(DigestScheduler, Notifier)>,
+    backporter: Option<Backporter>,
+}
+
+impl RepositoryBot {
+    /// Set up the queue for the repository in `config`
+    async fn start(config: &Config) -> Result<Self> {
+        // Create VCS adapter
+        let adapter = create_adapter(config)?;
         
-        let mut qm = queue_manager.lock().await;
+        // Set up repository protection
+        info!("Setting up repository protection for {}...", config.vcs.repository);
+        if let Err(e) = adapter.setup_repository_protection(&config.bot.main_branch, "garry-bot").await {
+            warn!("Could not set up repository protection for {}: {}", config.vcs.repository, e);
+            warn!("You may need to configure branch protection manually");
+        }
+        
+        // Create queue manager
+        // Load code owners from the main branch (picked up again on restart)
+        let code_owners = match CodeOwners::from_adapter(adapter.as_ref(), &config.bot.main_branch).await {
+            Ok(code_owners) => code_owners,
+            Err(e) => {
+                warn!("Could not load code owners for {}: {}", config.vcs.repository, e);
+                CodeOwners::default()
+            }
+        };
+        let team_members = owners::resolve_team_members(adapter.as_ref(), &code_owners).await;
+        let policy = ApprovalPolicy::new(config.policy.clone()).with_owners(code_owners.clone(), team_members.clone());
+        // Only maintainers' approvals count, and only maintainers and bots control the queue
+        let roles = Roles::load(config.roles.clone(), adapter.as_ref()).await;
+        let notifier = Notifier::from_config(create_adapter(config)?, config)?;
+        let mut queue = QueueManager::new(adapter)
+            .with_repository(config.vcs.repository.clone())
+            .with_main_branch(config.bot.main_branch.clone())
+            .with_policy(policy)
+            .with_roles(roles)
+            .with_notifier(notifier);
+        // Release branches and the like get their own queue, under their own policy
+        for target in &config.targets {
+            let policy = target.policy.clone().unwrap_or_else(|| config.policy.clone());
+            let policy = ApprovalPolicy::new(policy).with_owners(code_owners.clone(), team_members.clone());
+            queue = queue.with_target(target.branch.clone(), policy);
+            info!("Also merging reviews into {}", target.branch);
+        }
+        
+        // Commands in review comments are replied to through the notifier
+        let chatops = if config.bot.chat_commands {
+            let notifier = Notifier::from_config(create_adapter(config)?, config)?;
+            let bot_user = create_adapter(config)?.current_user().await?;
+            Some((ChatOps::new(bot_user, chrono::Utc::now()), notifier))
+        } else {
+            None
+        };
+        
+        // Digests go to the notification channels on the configured schedule
+        let digests = match DigestScheduler::new(&config.digest, chrono::Utc::now()) {
+            Some(scheduler) => Some((scheduler, Notifier::from_config(create_adapter(config)?, config)?)),
+            None => None,
+        };
+        
+        let stall_after = chrono::Duration::seconds((config.bot.queue_check_interval * 10).max(300) as i64);
+        let bot = Self {
+            repository: config.vcs.repository.clone(),
+            main_branch: config.bot.main_branch.clone(),
+            queue_check_interval: config.bot.queue_check_interval,
+            snapshot: Arc::new(RwLock::new(queue.snapshot())),
+            queue: Arc::new(Mutex::new(queue)),
+            adapter: create_adapter(config)?,
+            health: Arc::new(Health::new(stall_after, chrono::Utc::now())),
+            sweeper: StaleReviewSweeper::new(&config.bot),
+            chatops,
+            digests,
+            backporter: Backporter::new(&config.backport, &config.git.default_remote, chrono::Utc::now()),
+        };
         
-        // Discover and queue approved PRs
-        if let Err(e) = qm.discover_and_queue_reviews().await {
-            error!("Error discovering reviews: {}", e);
+        info!("Monitoring repository: {}", config.vcs.repository);
+        info!("Queue check interval: {}s", config.bot.queue_check_interval);
+        if config.bot.abandon_after_days > 0 {
+            info!("Abandoning reviews after {} days without activity", config.bot.abandon_after_days);
         }
+        if let Some(checkout) = &config.backport.checkout {
+            info!("Backporting reviews labelled {}<branch>, from the clone in {}", config.backport.label_prefix, checkout);
+        }
+        if bot.digests.is_some() {
+            info!("Sending {:?} digests at {:02}:00 UTC", config.digest.schedule, config.digest.hour);
+        }
+        Ok(bot)
+    }
+    
+    /// Process the queue forever; errors are logged and retried on the next pass
+    ///
+    /// Discovery and queue passes talk to the forge without holding the
+    /// queue, so the API isn't kept waiting on it. Chat commands still lock
+    /// the queue while each one runs.
+    async fn run(mut self) {
+        info!("⚠️  Only Garry Bot can push to {} - all merges go through the queue", self.main_branch);
+        
+        // Start queue processing loop
+        let mut check_interval = interval(Duration::from_secs(self.queue_check_interval));
         
-        // Process the merge queue
-        if let Err(e) = qm.process_queue().await {
-            error!("Error processing queue: {}", e);
+        loop {
+            check_interval.tick().await;
+            
+            // Abandon inactive reviews before they're walked for the queue
+            if let Some(sweeper) = self.sweeper.as_mut() {
+                let queued: Vec<_> = self.queue.lock().await.get_queue().iter().map(|e| e.review_id.clone()).collect();
+                match sweeper.sweep(self.adapter.as_ref(), &queued, chrono::Utc::now()).await {
+                    Ok(result) if !result.abandoned.is_empty() => {
+                        info!("Abandoned {} inactive review(s)", result.abandoned.len());
+                    },
+                    Ok(_) => {},
+                    Err(e) => error!("Error abandoning inactive reviews: {}", e),
+                }
+            }
+            
+            // Run commands before discovery, so holds apply straight away
+            if let Some((chatops, notifier)) = self.chatops.as_mut() {
+                match chatops.poll(self.adapter.as_ref(), &self.queue, notifier, chrono::Utc::now()).await {
+                    Ok(0) => {},
+                    Ok(handled) => info!("Handled {} review comment command(s)", handled),
+                    Err(e) => error!("Error handling review comment commands: {}", e),
+                }
+            }
+            
+            // Discover approved PRs unlocked, then queue them
+            let (rules, skip) = {
+                let qm = self.queue.lock().await;
+                (qm.rules().clone(), qm.skipped())
+            };
+            match rules.find_queueable(self.adapter.as_ref(), &skip).await {
+                Ok(found) => self.queue.lock().await.add_discovered(found),
+                Err(e) => error!("Error discovering reviews: {}", e),
+            }
+            
+            // Process the merge queue: plan and apply the pass under the
+            // lock, run it and send its notifications without
+            let pass = self.queue.lock().await.plan_pass();
+            let outcome = pass.run(self.adapter.as_ref()).await;
+            let (notices, snapshot) = {
+                let mut qm = self.queue.lock().await;
+                let notices = qm.finish_pass(outcome);
+                (notices, qm.snapshot())
+            };
+            if let Err(e) = notices.send().await {
+                error!("Error processing queue: {}", e);
+            }
+            
+            // Backport what just merged, as its labels ask
+            if let Some(backporter) = self.backporter.as_mut() {
+                match backporter.poll(self.adapter.as_ref(), &snapshot).await {
+                    Ok(0) => {},
+                    Ok(attempted) => info!("Attempted {} backport(s)", attempted),
+                    Err(e) => error!("Error backporting reviews: {}", e),
+                }
+            }
+            
+            // Send digests once the queue has been processed, so they're current
+            if let Some((scheduler, notifier)) = self.digests.as_mut() {
+                if let Err(e) = scheduler.poll(self.adapter.as_ref(), &snapshot, notifier, chrono::Utc::now()).await {
+                    error!("Error sending digests: {}", e);
+                }
+            }
+            
+            *self.snapshot.write().unwrap() = snapshot;
+            let now = chrono::Utc::now();
+            metrics::metrics().last_pass.with_label_values(&[&self.repository]).set(now.timestamp());
+            self.health.tick(now);
+        }
+    }
+}
+
+/// Create the VCS adapter for the configured platform
+fn create_adapter(config: &Config) -> Result<Box<dyn VcsAdapter>> {
+    match config.vcs.platform.as_str() {
+        "github" => Ok(Box::new(GithubAdapter::new(
+            config.vcs.host.clone(),
+            config.vcs.token.clone(),
+            config.vcs.repository.clone(),
+        )?.with_threads_required(config.blocks_on_unresolved_threads()))),
+        _ => {
+            error!("Unsupported VCS platform: {}", config.vcs.platform);
+            Err(GarryError::ConfigError(
+                format!("Unsupported platform: {}", config.vcs.platform)
+            ))
         }
     }
 }
@@ -1,13 +1,20 @@
 use async_trait::async_trait;
-use crate::review::{ReviewId, ReviewStatus, CiStatus};
+use chrono::{DateTime, Utc};
+use crate::review::{ReviewId, ReviewHead, ReviewStatus, ReviewSummary, ReviewDecision, CiStatus, CiCheck, CommentThread, InlineComment, RepoPermission, ReviewComment};
 use crate::utils::error::Result;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 /// VCS adapter trait - unified interface for all Git hosting platforms
 #[async_trait]
 pub trait VcsAdapter: Send + Sync {
-    /// Create a new review (PR/MR)
-    async fn create_review(&self, branch: &str, title: &str, description: &str) -> Result<(ReviewId, String)>;
+    /// Create a new review (PR/MR) of `branch`, to merge into `target`
+    async fn create_review(&self, branch: &str, target: &str, title: &str, description: &str) -> Result<(ReviewId, String)>;
+    
+    /// Create a draft review, which the merge queue never picks up
+    async fn create_draft_review(&self, branch: &str, target: &str, title: &str, description: &str) -> Result<(ReviewId, String)>;
+    
+    /// Mark a draft review as ready for review
+    async fn mark_ready_for_review(&self, review_id: &ReviewId) -> Result<()>;
     
     /// Get the status of a review
     async fn get_review_status(&self, review_id: &ReviewId) -> Result<ReviewStatus>;
@@ -15,21 +22,92 @@ pub trait VcsAdapter: Send + Sync {
     /// Merge a review
     async fn merge_review(&self, review_id: &ReviewId) -> Result<()>;
     
-    /// Post a comment on a review
-    async fn post_comment(&self, review_id: &ReviewId, message: &str) -> Result<()>;
+    /// Post a comment on a review, returning the new comment's ID
+    async fn post_comment(&self, review_id: &ReviewId, message: &str) -> Result<String>;
+    
+    /// List a review's top-level comments, oldest first
+    ///
+    /// With `since`, comments last touched before then may be left out.
+    async fn list_comments(&self, review_id: &ReviewId, since: Option<DateTime<Utc>>) -> Result<Vec<ReviewComment>>;
+    
+    /// Find the top-level comments the adapter's user posted that contain `marker`
+    async fn find_own_comments(&self, review_id: &ReviewId, marker: &str) -> Result<Vec<ReviewComment>>;
+    
+    /// Replace the body of a top-level comment
+    async fn update_comment(&self, review_id: &ReviewId, comment_id: &str, message: &str) -> Result<()>;
+    
+    /// Delete a top-level comment
+    async fn delete_comment(&self, review_id: &ReviewId, comment_id: &str) -> Result<()>;
+    
+    /// Post an inline comment on a line of a review's diff, starting a thread
+    async fn post_inline_comment(&self, review_id: &ReviewId, comment: &InlineComment) -> Result<()>;
+    
+    /// List the inline comment threads on a review
+    async fn list_comment_threads(&self, review_id: &ReviewId) -> Result<Vec<CommentThread>>;
+    
+    /// Request reviews from users or teams ("org/team")
+    async fn request_reviewers(&self, review_id: &ReviewId, reviewers: &[String]) -> Result<()>;
+    
+    /// Close a review without merging it
+    async fn close_review(&self, review_id: &ReviewId) -> Result<()>;
+    
+    /// Reopen a closed review
+    async fn reopen_review(&self, review_id: &ReviewId) -> Result<()>;
     
     /// Approve a review
     async fn approve_review(&self, review_id: &ReviewId, message: Option<&str>) -> Result<()>;
     
+    /// Submit a review with label votes
+    ///
+    /// `decision` is the platform-level outcome the votes map to; adapters
+    /// without native labels record the votes in the review body.
+    async fn submit_votes(
+        &self,
+        review_id: &ReviewId,
+        decision: ReviewDecision,
+        labels: &BTreeMap<String, i8>,
+        message: Option<&str>,
+    ) -> Result<()>;
+    
     /// List all pending reviews
     async fn list_pending_reviews(&self) -> Result<Vec<ReviewId>>;
     
+    /// List all open reviews with their summaries
+    async fn list_open_reviews(&self) -> Result<Vec<ReviewSummary>>;
+    
+    /// Get the username the adapter is authenticated as
+    async fn current_user(&self) -> Result<String>;
+    
     /// Get CI status for a review
     async fn get_ci_status(&self, review_id: &ReviewId) -> Result<CiStatus>;
     
+    /// Get the individual CI check results for a review
+    async fn get_ci_checks(&self, review_id: &ReviewId) -> Result<Vec<CiCheck>>;
+    
+    /// Find the open review for a branch, returning its ID and URL
+    async fn find_review_for_branch(&self, branch: &str) -> Result<Option<(ReviewId, String)>>;
+    
+    /// Get where a review's head commit can be fetched from
+    async fn get_review_head(&self, review_id: &ReviewId) -> Result<ReviewHead>;
+    
+    /// Get a file's contents on a branch, or `None` if it doesn't exist
+    async fn get_file_contents(&self, path: &str, branch: &str) -> Result<Option<String>>;
+    
+    /// Find the paths of every file called `name` on a branch
+    async fn find_files(&self, name: &str, branch: &str) -> Result<Vec<String>>;
+    
+    /// Get a user's access to the repository
+    async fn get_permission(&self, user: &str) -> Result<RepoPermission>;
+    
+    /// Get the usernames of a team's members (team given as "org/team")
+    async fn get_team_members(&self, team: &str) -> Result<Vec<String>>;
+    
     /// Setup repository protection rules (called by bot on startup)
     async fn setup_repository_protection(&self, main_branch: &str, bot_user: &str) -> Result<()>;
     
+    /// Get the web URL of a review
+    fn review_url(&self, review_id: &ReviewId) -> String;
+    
     /// Get the platform-specific name for reviews (PR, MR, etc.)
     fn review_name(&self) -> &str;
 }
@@ -53,8 +131,8 @@ impl AdapterRegistry {
     }
     
     /// Get an adapter by name
-    pub fn get_adapter(&self, name: &str) -> Option<&Box<dyn VcsAdapter>> {
-        self.adapters.get(name)
+    pub fn get_adapter(&self, name: &str) -> Option<&dyn VcsAdapter> {
+        self.adapters.get(name).map(|a| a.as_ref())
     }
 }
 
@@ -1,18 +1,26 @@
 use async_trait::async_trait;
 use reqwest::{Client, header};
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use crate::bot::adapter::VcsAdapter;
-use crate::review::{ReviewId, ReviewStatus, ReviewState, CiStatus, Approval};
+use crate::bot::adapters::http::TrackedSend;
+use crate::review::{ReviewId, ReviewHead, ReviewStatus, ReviewSummary, ReviewState, ReviewVote, ReviewDecision, CiStatus, CiCheck, Approval};
+use crate::review::{CommentThread, DiffSide, InlineComment, RepoPermission, ReviewComment};
+use crate::review::labels;
 use crate::utils::error::{GarryError, Result};
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, SecondsFormat, Utc};
+use std::collections::BTreeMap;
 use tracing::{info, debug, warn};
 
+/// Platform label for the adapter metrics
+const PLATFORM: &str = "github";
+
 /// GitHub adapter implementation
 pub struct GithubAdapter {
     client: Client,
     host: String,
-    token: String,
     repository: String,
+    /// Whether a review's status needs its comment threads, rather than doing without
+    threads_required: bool,
 }
 
 impl GithubAdapter {
@@ -36,29 +44,108 @@ impl GithubAdapter {
         let client = Client::builder()
             .default_headers(headers)
             .build()
-            .map_err(|e| GarryError::NetworkError(e))?;
+            .map_err(GarryError::NetworkError)?;
         
         Ok(Self {
             client,
             host,
-            token,
             repository,
+            threads_required: false,
         })
     }
     
+    /// Fail review status lookups when comment threads can't be listed
+    ///
+    /// Needed when unresolved threads block approval, so a failed lookup
+    /// doesn't look like a review with none.
+    pub fn with_threads_required(mut self, required: bool) -> Self {
+        self.threads_required = required;
+        self
+    }
+    
+    /// Base URL of a GitHub Enterprise host; a host with a scheme is used as-is
+    fn enterprise_url(&self) -> String {
+        if self.host.starts_with("http://") || self.host.starts_with("https://") {
+            self.host.trim_end_matches('/').to_string()
+        } else {
+            format!("https://{}", self.host)
+        }
+    }
+    
     fn api_url(&self, path: &str) -> String {
         if self.host == "github.com" {
             format!("https://api.github.com{}", path)
         } else {
-            format!("https://{}/api/v3{}", self.host, path)
+            format!("{}/api/v3{}", self.enterprise_url(), path)
         }
     }
-}
-
-#[async_trait]
-impl VcsAdapter for GithubAdapter {
-    async fn create_review(&self, branch: &str, title: &str, description: &str) -> Result<(ReviewId, String)> {
-        info!("Creating GitHub PR for branch: {}", branch);
+    
+    fn graphql_url(&self) -> String {
+        if self.host == "github.com" {
+            "https://api.github.com/graphql".to_string()
+        } else {
+            format!("{}/api/graphql", self.enterprise_url())
+        }
+    }
+    
+    /// GET every page of a list endpoint, following the `Link` header
+    async fn get_all<T: DeserializeOwned>(&self, url: String, endpoint: &str) -> Result<Vec<T>> {
+        let mut items = Vec::new();
+        let mut next = Some(url);
+        while let Some(url) = next {
+            let response = self.client.get(&url).send_tracked(PLATFORM, endpoint).await?;
+            if !response.status().is_success() {
+                let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+                return Err(GarryError::VcsError(format!("{} failed: {}", endpoint, error_text)));
+            }
+            next = next_page(response.headers());
+            items.extend(response.json::<Vec<T>>().await?);
+        }
+        Ok(items)
+    }
+    
+    /// Run a GraphQL query, returning its `data`
+    async fn graphql<T: DeserializeOwned>(&self, query: &str, variables: serde_json::Value) -> Result<T> {
+        #[derive(Serialize)]
+        struct GraphqlRequest<'a> {
+            query: &'a str,
+            variables: serde_json::Value,
+        }
+        
+        #[derive(Deserialize)]
+        struct GraphqlResponse<T> {
+            data: Option<T>,
+            #[serde(default)]
+            errors: Vec<GraphqlError>,
+        }
+        
+        #[derive(Deserialize)]
+        struct GraphqlError {
+            message: String,
+        }
+        
+        let response = self.client
+            .post(self.graphql_url())
+            .json(&GraphqlRequest { query, variables })
+            .send_tracked(PLATFORM, "POST /graphql")
+            .await?;
+        
+        if !response.status().is_success() {
+            let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(GarryError::VcsError(format!("GraphQL request failed: {}", error_text)));
+        }
+        
+        let result: GraphqlResponse<T> = response.json().await?;
+        if !result.errors.is_empty() {
+            let messages: Vec<String> = result.errors.into_iter().map(|e| e.message).collect();
+            return Err(GarryError::VcsError(format!("GraphQL request failed: {}", messages.join("; "))));
+        }
+        result.data.ok_or_else(|| GarryError::VcsError("GraphQL response had no data".to_string()))
+    }
+    
+    /// Open a pull request, optionally as a draft
+    async fn open_pull_request(&self, branch: &str, target: &str, title: &str, description: &str, draft: bool) -> Result<(ReviewId, String)> {
+        info!("Creating GitHub PR for branch: {} into {} (draft: {})", branch, target, draft);
         
         #[derive(Serialize)]
         struct CreatePrRequest {
@@ -66,6 +153,7 @@ impl VcsAdapter for GithubAdapter {
             body: String,
             head: String,
             base: String,
+            draft: bool,
         }
         
         #[derive(Deserialize)]
@@ -89,14 +177,15 @@ impl VcsAdapter for GithubAdapter {
             title: title.to_string(),
             body: description.to_string(),
             head,
-            base: "main".to_string(),
+            base: target.to_string(),
+            draft,
         };
         
         let url = self.api_url(&format!("/repos/{}/pulls", self.repository));
         let response = self.client
             .post(&url)
             .json(&request)
-            .send()
+            .send_tracked(PLATFORM, "POST /repos/:repo/pulls")
             .await?;
         
         if !response.status().is_success() {
@@ -110,13 +199,95 @@ impl VcsAdapter for GithubAdapter {
         Ok((ReviewId::new(pr.number.to_string()), pr.html_url))
     }
     
+    /// Open or close a pull request
+    async fn set_review_state(&self, review_id: &ReviewId, state: &str) -> Result<()> {
+        #[derive(Serialize)]
+        struct StateRequest<'a> {
+            state: &'a str,
+        }
+        
+        let url = self.api_url(&format!("/repos/{}/pulls/{}", self.repository, review_id.as_str()));
+        let response = self.client
+            .patch(&url)
+            .json(&StateRequest { state })
+            .send_tracked(PLATFORM, "PATCH /repos/:repo/pulls/:number")
+            .await?;
+        
+        if !response.status().is_success() {
+            let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(GarryError::VcsError(format!("Failed to set review state to {}: {}", state, error_text)));
+        }
+        
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl VcsAdapter for GithubAdapter {
+    async fn create_review(&self, branch: &str, target: &str, title: &str, description: &str) -> Result<(ReviewId, String)> {
+        self.open_pull_request(branch, target, title, description, false).await
+    }
+    
+    async fn create_draft_review(&self, branch: &str, target: &str, title: &str, description: &str) -> Result<(ReviewId, String)> {
+        self.open_pull_request(branch, target, title, description, true).await
+    }
+    
+    async fn mark_ready_for_review(&self, review_id: &ReviewId) -> Result<()> {
+        info!("Marking PR #{} ready for review", review_id);
+        
+        #[derive(Deserialize)]
+        struct PrNode {
+            node_id: String,
+        }
+        
+        // The REST API can't leave draft state, so this goes through GraphQL
+        let url = self.api_url(&format!("/repos/{}/pulls/{}", self.repository, review_id.as_str()));
+        let pr: PrNode = self.client.get(&url).send_tracked(PLATFORM, "GET /repos/:repo/pulls/:number").await?.json().await?;
+        
+        let _: serde_json::Value = self.graphql(
+            "mutation($id: ID!) { markPullRequestReadyForReview(input: { pullRequestId: $id }) { clientMutationId } }",
+            serde_json::json!({ "id": pr.node_id }),
+        ).await?;
+        
+        Ok(())
+    }
+    
     async fn get_review_status(&self, review_id: &ReviewId) -> Result<ReviewStatus> {
         debug!("Getting status for PR #{}", review_id);
         
         #[derive(Deserialize)]
         struct PrDetails {
             state: String,
+            #[serde(default)]
+            draft: bool,
             mergeable: Option<bool>,
+            user: User,
+            head: Head,
+            base: Base,
+            #[serde(default)]
+            merged: bool,
+            merge_commit_sha: Option<String>,
+            #[serde(default)]
+            labels: Vec<Label>,
+            changed_files: Option<usize>,
+        }
+        
+        #[derive(Deserialize)]
+        struct Label {
+            name: String,
+        }
+        
+        #[derive(Deserialize)]
+        struct Head {
+            sha: String,
+            #[serde(rename = "ref")]
+            branch: String,
+        }
+        
+        #[derive(Deserialize)]
+        struct Base {
+            #[serde(rename = "ref")]
+            branch: String,
         }
         
         #[derive(Deserialize)]
@@ -124,6 +295,9 @@ impl VcsAdapter for GithubAdapter {
             user: User,
             state: String,
             submitted_at: Option<String>,
+            commit_id: Option<String>,
+            #[serde(default)]
+            body: Option<String>,
         }
         
         #[derive(Deserialize)]
@@ -131,16 +305,48 @@ impl VcsAdapter for GithubAdapter {
             login: String,
         }
         
+        #[derive(Deserialize)]
+        struct PrFile {
+            filename: String,
+        }
+        
+        #[derive(Deserialize)]
+        struct CommitDetails {
+            commit: CommitInfo,
+        }
+        
+        #[derive(Deserialize)]
+        struct CommitInfo {
+            committer: Committer,
+        }
+        
+        #[derive(Deserialize)]
+        struct Committer {
+            date: DateTime<Utc>,
+        }
+        
         // Get PR details
         let url = self.api_url(&format!("/repos/{}/pulls/{}", self.repository, review_id.as_str()));
-        let pr: PrDetails = self.client.get(&url).send().await?.json().await?;
+        let pr: PrDetails = self.client.get(&url).send_tracked(PLATFORM, "GET /repos/:repo/pulls/:number").await?.json().await?;
         
         // Get reviews
-        let reviews_url = self.api_url(&format!("/repos/{}/pulls/{}/reviews", self.repository, review_id.as_str()));
-        let reviews: Vec<Review> = self.client.get(&reviews_url).send().await?.json().await?;
+        let reviews_url = self.api_url(&format!("/repos/{}/pulls/{}/reviews?per_page=100", self.repository, review_id.as_str()));
+        let reviews: Vec<Review> = self.get_all(reviews_url, "GET /repos/:repo/pulls/:number/reviews").await?;
+        
+        // The head commit's committer date approximates the latest push
+        // (squashing and amending both rewrite it)
+        let commit_url = self.api_url(&format!("/repos/{}/commits/{}", self.repository, pr.head.sha));
+        let pushed_at = match self.client.get(&commit_url).send_tracked(PLATFORM, "GET /repos/:repo/commits/:sha").await?.json::<CommitDetails>().await {
+            Ok(details) => Some(details.commit.committer.date),
+            Err(e) => {
+                warn!("Could not get head commit date for PR #{}: {}", review_id, e);
+                None
+            }
+        };
         
         // Parse state
         let state = match pr.state.as_str() {
+            "open" if pr.draft => ReviewState::Draft,
             "open" => {
                 let has_approval = reviews.iter().any(|r| r.state == "APPROVED");
                 let has_changes_requested = reviews.iter().any(|r| r.state == "CHANGES_REQUESTED");
@@ -153,6 +359,7 @@ impl VcsAdapter for GithubAdapter {
                     ReviewState::Open
                 }
             },
+            "closed" if pr.merged => ReviewState::Merged,
             "closed" => ReviewState::Closed,
             _ => ReviewState::Open,
         };
@@ -170,15 +377,76 @@ impl VcsAdapter for GithubAdapter {
             })
             .collect();
         
+        // Get changed files, for code owner checks
+        // (GitHub lists at most 3000, so a bigger PR can't pass an ownership check)
+        let files_url = self.api_url(&format!("/repos/{}/pulls/{}/files?per_page=100", self.repository, review_id.as_str()));
+        let files: Vec<PrFile> = self.get_all(files_url, "GET /repos/:repo/pulls/:number/files").await?;
+        let changed_files_truncated = pr.changed_files.is_some_and(|count| files.len() < count);
+        
+        // Keep every submitted review for the approval policy
+        let votes: Vec<ReviewVote> = reviews
+            .iter()
+            .filter_map(|r| {
+                let decision = match r.state.as_str() {
+                    "APPROVED" => ReviewDecision::Approved,
+                    "CHANGES_REQUESTED" => ReviewDecision::ChangesRequested,
+                    "COMMENTED" => ReviewDecision::Commented,
+                    "DISMISSED" => ReviewDecision::Dismissed,
+                    // Pending reviews haven't been submitted yet
+                    _ => return None,
+                };
+                Some(ReviewVote {
+                    reviewer: r.user.login.clone(),
+                    decision,
+                    submitted_at: r.submitted_at
+                        .as_ref()
+                        .and_then(|s| s.parse::<DateTime<Utc>>().ok())
+                        .unwrap_or_else(Utc::now),
+                    commit_id: r.commit_id.clone(),
+                    // Anyone can write trailers in a comment, so only reviews
+                    // that approve or request changes carry votes
+                    labels: match decision {
+                        ReviewDecision::Approved | ReviewDecision::ChangesRequested => {
+                            r.body.as_deref().map(labels::parse_trailers).unwrap_or_default()
+                        },
+                        _ => BTreeMap::new(),
+                    },
+                })
+            })
+            .collect();
+        
         // Get CI status
         let ci_status = self.get_ci_status(review_id).await?;
         
+        // Threads come from GraphQL, so don't fail the whole status over them
+        // unless they decide approval
+        let threads = match self.list_comment_threads(review_id).await {
+            Ok(threads) => threads,
+            Err(e) if self.threads_required => return Err(e),
+            Err(e) => {
+                warn!("Could not list comment threads for PR #{}: {}", review_id, e);
+                vec![]
+            }
+        };
+        
         Ok(ReviewStatus {
             id: review_id.clone(),
             state,
             approvals,
             ci_status,
             mergeable: pr.mergeable.unwrap_or(false),
+            author: pr.user.login,
+            votes,
+            head_sha: pr.head.sha,
+            pushed_at,
+            changed_files: files.into_iter().map(|f| f.filename).collect(),
+            changed_files_truncated,
+            threads,
+            head_branch: pr.head.branch,
+            target_branch: pr.base.branch,
+            labels: pr.labels.into_iter().map(|l| l.name).collect(),
+            // Open PRs have a test merge commit; only a merged PR's is on the base
+            merge_commit: pr.merge_commit_sha.filter(|_| pr.merged),
         })
     }
     
@@ -198,7 +466,7 @@ impl VcsAdapter for GithubAdapter {
         let response = self.client
             .put(&url)
             .json(&request)
-            .send()
+            .send_tracked(PLATFORM, "PUT /repos/:repo/pulls/:number/merge")
             .await?;
         
         if !response.status().is_success() {
@@ -210,7 +478,7 @@ impl VcsAdapter for GithubAdapter {
         Ok(())
     }
     
-    async fn post_comment(&self, review_id: &ReviewId, message: &str) -> Result<()> {
+    async fn post_comment(&self, review_id: &ReviewId, message: &str) -> Result<String> {
         debug!("Posting comment to PR #{}", review_id);
         
         #[derive(Serialize)]
@@ -218,20 +486,309 @@ impl VcsAdapter for GithubAdapter {
             body: String,
         }
         
+        #[derive(Deserialize)]
+        struct CommentResponse {
+            id: u64,
+        }
+        
         let request = CommentRequest {
             body: message.to_string(),
         };
         
         let url = self.api_url(&format!("/repos/{}/issues/{}/comments", self.repository, review_id.as_str()));
-        self.client
+        let response = self.client
+            .post(&url)
+            .json(&request)
+            .send_tracked(PLATFORM, "POST /repos/:repo/issues/:number/comments")
+            .await?;
+        
+        if !response.status().is_success() {
+            let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(GarryError::VcsError(format!("Failed to post comment: {}", error_text)));
+        }
+        
+        let comment: CommentResponse = response.json().await?;
+        Ok(comment.id.to_string())
+    }
+    
+    async fn list_comments(&self, review_id: &ReviewId, since: Option<DateTime<Utc>>) -> Result<Vec<ReviewComment>> {
+        debug!("Listing comments on PR #{}", review_id);
+        
+        #[derive(Deserialize)]
+        struct Comment {
+            id: u64,
+            user: User,
+            body: String,
+            created_at: DateTime<Utc>,
+        }
+        
+        #[derive(Deserialize)]
+        struct User {
+            login: String,
+        }
+        
+        // GitHub filters on when comments were last updated
+        let mut url = self.api_url(&format!("/repos/{}/issues/{}/comments?per_page=100", self.repository, review_id.as_str()));
+        if let Some(since) = since {
+            url.push_str(&format!("&since={}", since.to_rfc3339_opts(SecondsFormat::Secs, true)));
+        }
+        let comments: Vec<Comment> = self.get_all(url, "GET /repos/:repo/issues/:number/comments").await?;
+        Ok(comments.into_iter().map(|c| ReviewComment {
+            id: c.id.to_string(),
+            author: c.user.login,
+            body: c.body,
+            created_at: c.created_at,
+        }).collect())
+    }
+    
+    async fn find_own_comments(&self, review_id: &ReviewId, marker: &str) -> Result<Vec<ReviewComment>> {
+        let user = self.current_user().await?;
+        let comments = self.list_comments(review_id, None).await?;
+        Ok(comments.into_iter().filter(|c| c.author == user && c.body.contains(marker)).collect())
+    }
+    
+    async fn update_comment(&self, review_id: &ReviewId, comment_id: &str, message: &str) -> Result<()> {
+        debug!("Updating comment {} on PR #{}", comment_id, review_id);
+        
+        let url = self.api_url(&format!("/repos/{}/issues/comments/{}", self.repository, comment_id));
+        let response = self.client
+            .patch(&url)
+            .json(&serde_json::json!({ "body": message }))
+            .send_tracked(PLATFORM, "PATCH /repos/:repo/issues/comments/:id")
+            .await?;
+        
+        if !response.status().is_success() {
+            let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(GarryError::VcsError(format!("Failed to update comment: {}", error_text)));
+        }
+        
+        Ok(())
+    }
+    
+    async fn delete_comment(&self, review_id: &ReviewId, comment_id: &str) -> Result<()> {
+        debug!("Deleting comment {} on PR #{}", comment_id, review_id);
+        
+        let url = self.api_url(&format!("/repos/{}/issues/comments/{}", self.repository, comment_id));
+        let response = self.client.delete(&url).send_tracked(PLATFORM, "DELETE /repos/:repo/issues/comments/:id").await?;
+        
+        if !response.status().is_success() {
+            let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(GarryError::VcsError(format!("Failed to delete comment: {}", error_text)));
+        }
+        
+        Ok(())
+    }
+    
+    async fn post_inline_comment(&self, review_id: &ReviewId, comment: &InlineComment) -> Result<()> {
+        debug!("Commenting on {}:{} of PR #{}", comment.file, comment.line, review_id);
+        
+        #[derive(Serialize)]
+        struct CommentRequest<'a> {
+            body: &'a str,
+            commit_id: String,
+            path: &'a str,
+            line: u32,
+            side: &'a str,
+        }
+        
+        let head = self.get_review_head(review_id).await?;
+        let request = CommentRequest {
+            body: &comment.body,
+            commit_id: head.sha,
+            path: &comment.file,
+            line: comment.line,
+            side: match comment.side {
+                DiffSide::Old => "LEFT",
+                DiffSide::New => "RIGHT",
+            },
+        };
+        
+        let url = self.api_url(&format!("/repos/{}/pulls/{}/comments", self.repository, review_id.as_str()));
+        let response = self.client
             .post(&url)
             .json(&request)
-            .send()
+            .send_tracked(PLATFORM, "POST /repos/:repo/pulls/:number/comments")
             .await?;
         
+        if !response.status().is_success() {
+            let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(GarryError::VcsError(format!("Failed to post inline comment: {}", error_text)));
+        }
+        
         Ok(())
     }
     
+    async fn list_comment_threads(&self, review_id: &ReviewId) -> Result<Vec<CommentThread>> {
+        debug!("Listing comment threads on PR #{}", review_id);
+        
+        #[derive(Deserialize)]
+        struct Data {
+            repository: Repository,
+        }
+        
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Repository {
+            pull_request: PullRequest,
+        }
+        
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct PullRequest {
+            review_threads: Page<Thread>,
+        }
+        
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Page<T> {
+            nodes: Vec<T>,
+            page_info: PageInfo,
+        }
+        
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct PageInfo {
+            has_next_page: bool,
+            end_cursor: Option<String>,
+        }
+        
+        #[derive(Deserialize)]
+        struct Nodes<T> {
+            nodes: Vec<T>,
+        }
+        
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Thread {
+            id: String,
+            path: String,
+            original_line: Option<u32>,
+            diff_side: String,
+            is_resolved: bool,
+            is_outdated: bool,
+            comments: Nodes<Comment>,
+        }
+        
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Comment {
+            id: String,
+            author: Option<Author>,
+            body: String,
+            created_at: DateTime<Utc>,
+            original_commit: Option<Commit>,
+        }
+        
+        #[derive(Deserialize)]
+        struct Author {
+            login: String,
+        }
+        
+        #[derive(Deserialize)]
+        struct Commit {
+            oid: String,
+        }
+        
+        const QUERY: &str = "query($owner: String!, $name: String!, $number: Int!, $after: String) {
+            repository(owner: $owner, name: $name) {
+                pullRequest(number: $number) {
+                    reviewThreads(first: 100, after: $after) {
+                        pageInfo { hasNextPage endCursor }
+                        nodes {
+                            id path originalLine diffSide isResolved isOutdated
+                            comments(first: 100) {
+                                nodes { id author { login } body createdAt originalCommit { oid } }
+                            }
+                        }
+                    }
+                }
+            }
+        }";
+        
+        let (owner, name) = self.repository.split_once('/')
+            .ok_or_else(|| GarryError::VcsError("Invalid repository format".to_string()))?;
+        let number: u64 = review_id.as_str().parse()
+            .map_err(|_| GarryError::ReviewNotFound(review_id.to_string()))?;
+        let mut nodes = Vec::new();
+        let mut after: Option<String> = None;
+        loop {
+            let variables = serde_json::json!({ "owner": owner, "name": name, "number": number, "after": after });
+            let data: Data = self.graphql(QUERY, variables).await?;
+            let page = data.repository.pull_request.review_threads;
+            nodes.extend(page.nodes);
+            match page.page_info.end_cursor {
+                Some(cursor) if page.page_info.has_next_page => after = Some(cursor),
+                _ => break,
+            }
+        }
+        
+        // Line numbers are kept relative to the commit they were made on, so
+        // they can be mapped onto any later revision
+        Ok(nodes.into_iter().map(|thread| {
+            let commit_id = thread.comments.nodes.first()
+                .and_then(|c| c.original_commit.as_ref())
+                .map(|c| c.oid.clone());
+            CommentThread {
+                id: thread.id,
+                file: thread.path,
+                line: thread.original_line,
+                side: if thread.diff_side == "LEFT" { DiffSide::Old } else { DiffSide::New },
+                commit_id,
+                resolved: thread.is_resolved,
+                outdated: thread.is_outdated,
+                comments: thread.comments.nodes.into_iter().map(|c| ReviewComment {
+                    id: c.id,
+                    author: c.author.map(|a| a.login).unwrap_or_else(|| "ghost".to_string()),
+                    body: c.body,
+                    created_at: c.created_at,
+                }).collect(),
+            }
+        }).collect())
+    }
+    
+    async fn request_reviewers(&self, review_id: &ReviewId, reviewers: &[String]) -> Result<()> {
+        info!("Requesting reviews on PR #{} from {:?}", review_id, reviewers);
+        
+        #[derive(Serialize)]
+        struct ReviewersRequest {
+            reviewers: Vec<String>,
+            team_reviewers: Vec<String>,
+        }
+        
+        // Teams are given as "org/team" but GitHub only wants the slug
+        let mut request = ReviewersRequest { reviewers: vec![], team_reviewers: vec![] };
+        for reviewer in reviewers.iter().map(|r| r.trim_start_matches('@')) {
+            match reviewer.rsplit_once('/') {
+                Some((_, team)) => request.team_reviewers.push(team.to_string()),
+                None => request.reviewers.push(reviewer.to_string()),
+            }
+        }
+        
+        let url = self.api_url(&format!("/repos/{}/pulls/{}/requested_reviewers", self.repository, review_id.as_str()));
+        let response = self.client
+            .post(&url)
+            .json(&request)
+            .send_tracked(PLATFORM, "POST /repos/:repo/pulls/:number/requested_reviewers")
+            .await?;
+        
+        if !response.status().is_success() {
+            let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(GarryError::VcsError(format!("Failed to request reviewers: {}", error_text)));
+        }
+        
+        Ok(())
+    }
+    
+    async fn close_review(&self, review_id: &ReviewId) -> Result<()> {
+        info!("Closing PR #{}", review_id);
+        self.set_review_state(review_id, "closed").await
+    }
+    
+    async fn reopen_review(&self, review_id: &ReviewId) -> Result<()> {
+        info!("Reopening PR #{}", review_id);
+        self.set_review_state(review_id, "open").await
+    }
+    
     async fn approve_review(&self, review_id: &ReviewId, message: Option<&str>) -> Result<()> {
         info!("Approving PR #{}", review_id);
         
@@ -250,7 +807,7 @@ impl VcsAdapter for GithubAdapter {
         let response = self.client
             .post(&url)
             .json(&request)
-            .send()
+            .send_tracked(PLATFORM, "POST /repos/:repo/pulls/:number/reviews")
             .await?;
         
         if !response.status().is_success() {
@@ -262,6 +819,56 @@ impl VcsAdapter for GithubAdapter {
         Ok(())
     }
     
+    async fn submit_votes(
+        &self,
+        review_id: &ReviewId,
+        decision: ReviewDecision,
+        labels: &BTreeMap<String, i8>,
+        message: Option<&str>,
+    ) -> Result<()> {
+        info!("Voting {:?} on PR #{}", labels, review_id);
+        
+        #[derive(Serialize)]
+        struct ReviewRequest {
+            body: String,
+            event: String,
+        }
+        
+        // GitHub has no labels on reviews, so votes travel as body trailers
+        let event = match decision {
+            ReviewDecision::Approved => "APPROVE",
+            ReviewDecision::ChangesRequested => "REQUEST_CHANGES",
+            ReviewDecision::Commented | ReviewDecision::Dismissed => {
+                warn!("Votes that neither approve nor request changes are recorded on PR #{} but don't count", review_id);
+                "COMMENT"
+            },
+        };
+        let trailers = labels::format_trailers(labels);
+        let body = match message {
+            Some(message) => format!("{}\n\n{}", message, trailers),
+            None => trailers,
+        };
+        
+        let request = ReviewRequest {
+            body,
+            event: event.to_string(),
+        };
+        
+        let url = self.api_url(&format!("/repos/{}/pulls/{}/reviews", self.repository, review_id.as_str()));
+        let response = self.client
+            .post(&url)
+            .json(&request)
+            .send_tracked(PLATFORM, "POST /repos/:repo/pulls/:number/reviews")
+            .await?;
+        
+        if !response.status().is_success() {
+            let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(GarryError::VcsError(format!("Failed to submit review: {}", error_text)));
+        }
+        
+        Ok(())
+    }
+    
     async fn list_pending_reviews(&self) -> Result<Vec<ReviewId>> {
         debug!("Listing pending PRs");
         
@@ -270,15 +877,76 @@ impl VcsAdapter for GithubAdapter {
             number: u64,
         }
         
-        let url = self.api_url(&format!("/repos/{}/pulls?state=open", self.repository));
-        let prs: Vec<PrListItem> = self.client.get(&url).send().await?.json().await?;
+        let url = self.api_url(&format!("/repos/{}/pulls?state=open&per_page=100", self.repository));
+        let prs: Vec<PrListItem> = self.get_all(url, "GET /repos/:repo/pulls").await?;
         
         Ok(prs.into_iter().map(|pr| ReviewId::new(pr.number.to_string())).collect())
     }
     
+    async fn list_open_reviews(&self) -> Result<Vec<ReviewSummary>> {
+        debug!("Listing open PRs");
+        
+        #[derive(Deserialize)]
+        struct PrListItem {
+            number: u64,
+            title: String,
+            html_url: String,
+            created_at: DateTime<Utc>,
+            updated_at: DateTime<Utc>,
+            user: User,
+            head: Head,
+            #[serde(default)]
+            requested_reviewers: Vec<User>,
+        }
+        
+        #[derive(Deserialize)]
+        struct User {
+            login: String,
+        }
+        
+        #[derive(Deserialize)]
+        struct Head {
+            #[serde(rename = "ref")]
+            branch: String,
+        }
+        
+        let url = self.api_url(&format!("/repos/{}/pulls?state=open&per_page=100", self.repository));
+        let prs: Vec<PrListItem> = self.get_all(url, "GET /repos/:repo/pulls").await?;
+        
+        Ok(prs.into_iter()
+            .map(|pr| ReviewSummary {
+                id: ReviewId::new(pr.number.to_string()),
+                title: pr.title,
+                author: pr.user.login,
+                branch: pr.head.branch,
+                url: pr.html_url,
+                created_at: pr.created_at,
+                updated_at: pr.updated_at,
+                requested_reviewers: pr.requested_reviewers.into_iter().map(|u| u.login).collect(),
+            })
+            .collect())
+    }
+    
+    async fn current_user(&self) -> Result<String> {
+        #[derive(Deserialize)]
+        struct User {
+            login: String,
+        }
+        
+        let user: User = self.client.get(self.api_url("/user")).send_tracked(PLATFORM, "GET /user").await?.json().await?;
+        Ok(user.login)
+    }
+    
     async fn get_ci_status(&self, review_id: &ReviewId) -> Result<CiStatus> {
         debug!("Getting CI status for PR #{}", review_id);
         
+        let checks = self.get_ci_checks(review_id).await?;
+        Ok(CiStatus::from_checks(&checks))
+    }
+    
+    async fn get_ci_checks(&self, review_id: &ReviewId) -> Result<Vec<CiCheck>> {
+        debug!("Getting CI checks for PR #{}", review_id);
+        
         #[derive(Deserialize)]
         struct PrDetails {
             head: Head,
@@ -303,43 +971,205 @@ impl VcsAdapter for GithubAdapter {
         
         // Get PR to find commit SHA
         let pr_url = self.api_url(&format!("/repos/{}/pulls/{}", self.repository, review_id.as_str()));
-        let pr: PrDetails = self.client.get(&pr_url).send().await?.json().await?;
+        let pr: PrDetails = self.client.get(&pr_url).send_tracked(PLATFORM, "GET /repos/:repo/pulls/:number").await?.json().await?;
         
         // Get check runs for the commit
         let checks_url = self.api_url(&format!("/repos/{}/commits/{}/check-runs", self.repository, pr.head.sha));
-        let checks: CheckRunsResponse = self.client.get(&checks_url).send().await?.json().await?;
+        let checks: CheckRunsResponse = self.client.get(&checks_url).send_tracked(PLATFORM, "GET /repos/:repo/commits/:sha/check-runs").await?.json().await?;
+        
+        Ok(checks.check_runs
+            .into_iter()
+            .map(|check| {
+                let status = match (check.status.as_str(), check.conclusion.as_deref()) {
+                    ("completed", Some("failure")) | ("completed", Some("timed_out")) => {
+                        CiStatus::Failed(vec![check.name.clone()])
+                    },
+                    ("completed", Some("cancelled")) => CiStatus::Cancelled,
+                    // Success, neutral and skipped checks don't block
+                    ("completed", _) => CiStatus::Success,
+                    ("in_progress", _) | ("queued", _) => CiStatus::Running,
+                    _ => CiStatus::Pending,
+                };
+                CiCheck { name: check.name, status }
+            })
+            .collect())
+    }
+    
+    async fn find_review_for_branch(&self, branch: &str) -> Result<Option<(ReviewId, String)>> {
+        debug!("Finding PR for branch: {}", branch);
         
-        if checks.check_runs.is_empty() {
-            return Ok(CiStatus::Pending);
+        #[derive(Deserialize)]
+        struct PrListItem {
+            number: u64,
+            html_url: String,
         }
         
-        let mut has_running = false;
-        let mut failures = Vec::new();
+        let owner = self.repository.split('/').next()
+            .ok_or_else(|| GarryError::VcsError("Invalid repository format".to_string()))?;
+        let url = self.api_url(&format!("/repos/{}/pulls?state=open&head={}:{}", self.repository, owner, branch));
+        let prs: Vec<PrListItem> = self.client.get(&url).send_tracked(PLATFORM, "GET /repos/:repo/pulls").await?.json().await?;
         
-        for check in &checks.check_runs {
-            match check.status.as_str() {
-                "completed" => {
-                    if let Some(conclusion) = &check.conclusion {
-                        match conclusion.as_str() {
-                            "success" => continue,
-                            "failure" | "timed_out" => failures.push(check.name.clone()),
-                            "cancelled" => return Ok(CiStatus::Cancelled),
-                            _ => {}
-                        }
-                    }
-                },
-                "in_progress" | "queued" => has_running = true,
-                _ => {}
-            }
+        Ok(prs.into_iter()
+            .next()
+            .map(|pr| (ReviewId::new(pr.number.to_string()), pr.html_url)))
+    }
+    
+    async fn get_review_head(&self, review_id: &ReviewId) -> Result<ReviewHead> {
+        debug!("Getting head of PR #{}", review_id);
+        
+        #[derive(Deserialize)]
+        struct PrDetails {
+            head: Head,
         }
         
-        if !failures.is_empty() {
-            Ok(CiStatus::Failed(failures))
-        } else if has_running {
-            Ok(CiStatus::Running)
-        } else {
-            Ok(CiStatus::Success)
+        #[derive(Deserialize)]
+        struct Head {
+            #[serde(rename = "ref")]
+            branch: String,
+            sha: String,
+            repo: Option<Repo>,
+        }
+        
+        #[derive(Deserialize)]
+        struct Repo {
+            full_name: String,
+            clone_url: String,
+        }
+        
+        let url = self.api_url(&format!("/repos/{}/pulls/{}", self.repository, review_id.as_str()));
+        let response = self.client.get(&url).send_tracked(PLATFORM, "GET /repos/:repo/pulls/:number").await?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Err(GarryError::ReviewNotFound(review_id.to_string()));
         }
+        let pr: PrDetails = response.json().await?;
+        
+        // The head repo is null if the fork was deleted; refs/pull/N/head still works
+        let fork_url = pr.head.repo
+            .filter(|repo| !repo.full_name.eq_ignore_ascii_case(&self.repository))
+            .map(|repo| repo.clone_url);
+        
+        Ok(ReviewHead {
+            branch: pr.head.branch,
+            sha: pr.head.sha,
+            review_ref: Some(format!("refs/pull/{}/head", review_id.as_str())),
+            fork_url,
+        })
+    }
+    
+    async fn get_file_contents(&self, path: &str, branch: &str) -> Result<Option<String>> {
+        debug!("Getting {} on branch {}", path, branch);
+        
+        let url = self.api_url(&format!("/repos/{}/contents/{}?ref={}", self.repository, path, branch));
+        let response = self.client
+            .get(&url)
+            .header(header::ACCEPT, "application/vnd.github.raw")
+            .send_tracked(PLATFORM, "GET /repos/:repo/contents/:path")
+            .await?;
+        
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if !response.status().is_success() {
+            let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(GarryError::VcsError(format!("Failed to get {}: {}", path, error_text)));
+        }
+        
+        Ok(Some(response.text().await?))
+    }
+    
+    async fn find_files(&self, name: &str, branch: &str) -> Result<Vec<String>> {
+        debug!("Finding files named {} on branch {}", name, branch);
+        
+        #[derive(Deserialize)]
+        struct Tree {
+            tree: Vec<TreeEntry>,
+            #[serde(default)]
+            truncated: bool,
+        }
+        
+        #[derive(Deserialize)]
+        struct TreeEntry {
+            path: String,
+            #[serde(rename = "type")]
+            kind: String,
+        }
+        
+        let url = self.api_url(&format!("/repos/{}/git/trees/{}?recursive=1", self.repository, branch));
+        let response = self.client.get(&url).send_tracked(PLATFORM, "GET /repos/:repo/git/trees/:ref").await?;
+        if !response.status().is_success() {
+            let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(GarryError::VcsError(format!("Failed to list files on {}: {}", branch, error_text)));
+        }
+        
+        let tree: Tree = response.json().await?;
+        if tree.truncated {
+            return Err(GarryError::VcsError(format!("{} has too many files to list through the API", branch)));
+        }
+        Ok(tree.tree
+            .into_iter()
+            .filter(|e| e.kind == "blob" && e.path.rsplit('/').next() == Some(name))
+            .map(|e| e.path)
+            .collect())
+    }
+    
+    async fn get_permission(&self, user: &str) -> Result<RepoPermission> {
+        debug!("Getting {}'s permission on {}", user, self.repository);
+        
+        #[derive(Deserialize)]
+        struct PermissionResponse {
+            permission: String,
+            role_name: Option<String>,
+        }
+        
+        let url = self.api_url(&format!("/repos/{}/collaborators/{}/permission", self.repository, user));
+        let response = self.client.get(&url).send_tracked(PLATFORM, "GET /repos/:repo/collaborators/:user/permission").await?;
+        
+        // Non-collaborators get a 404 on private repositories
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(RepoPermission::None);
+        }
+        if !response.status().is_success() {
+            let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(GarryError::VcsError(format!("Failed to get permission for {}: {}", user, error_text)));
+        }
+        
+        let parse = |name: &str| match name {
+            "admin" => Some(RepoPermission::Admin),
+            "maintain" => Some(RepoPermission::Maintain),
+            "write" => Some(RepoPermission::Write),
+            "triage" => Some(RepoPermission::Triage),
+            "read" => Some(RepoPermission::Read),
+            _ => None,
+        };
+        
+        // `permission` folds maintain into write and triage into read;
+        // `role_name` keeps them apart unless it's a custom role
+        let result: PermissionResponse = response.json().await?;
+        Ok(result.role_name.as_deref().and_then(parse)
+            .or_else(|| parse(&result.permission))
+            .unwrap_or(RepoPermission::None))
+    }
+    
+    async fn get_team_members(&self, team: &str) -> Result<Vec<String>> {
+        debug!("Getting members of team {}", team);
+        
+        #[derive(Deserialize)]
+        struct Member {
+            login: String,
+        }
+        
+        let (org, slug) = team.split_once('/')
+            .ok_or_else(|| GarryError::VcsError(format!("Team must be in format 'org/team': {}", team)))?;
+        let url = self.api_url(&format!("/orgs/{}/teams/{}/members?per_page=100", org, slug));
+        let response = self.client.get(&url).send_tracked(PLATFORM, "GET /orgs/:org/teams/:team/members").await?;
+        
+        if !response.status().is_success() {
+            let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(GarryError::VcsError(format!("Failed to get members of {}: {}", team, error_text)));
+        }
+        
+        let members: Vec<Member> = response.json().await?;
+        Ok(members.into_iter().map(|m| m.login).collect())
     }
     
     async fn setup_repository_protection(&self, main_branch: &str, bot_user: &str) -> Result<()> {
@@ -403,7 +1233,7 @@ impl VcsAdapter for GithubAdapter {
         let response = self.client
             .put(&url)
             .json(&protection)
-            .send()
+            .send_tracked(PLATFORM, "PUT /repos/:repo/branches/:branch/protection")
             .await?;
         
         if !response.status().is_success() {
@@ -416,7 +1246,21 @@ impl VcsAdapter for GithubAdapter {
         Ok(())
     }
     
+    fn review_url(&self, review_id: &ReviewId) -> String {
+        format!("https://{}/{}/pull/{}", self.host, self.repository, review_id)
+    }
+    
     fn review_name(&self) -> &str {
         "Pull Request"
     }
 }
+
+/// URL of the next page named by a `Link` header, if there is one
+fn next_page(headers: &header::HeaderMap) -> Option<String> {
+    let link = headers.get(header::LINK)?.to_str().ok()?;
+    link.split(',').find_map(|part| {
+        let (url, params) = part.split_once(';')?;
+        params.split(';').any(|param| param.trim() == "rel=\"next\"")
+            .then(|| url.trim().trim_start_matches('<').trim_end_matches('>').to_string())
+    })
+}
@@ -0,0 +1,41 @@
+use std::time::Instant;
+use async_trait::async_trait;
+use reqwest::{RequestBuilder, Response};
+use crate::bot::metrics;
+
+/// Sends forge API requests, recording them in the adapter metrics
+#[async_trait]
+pub(crate) trait TrackedSend {
+    /// Send the request, counted and timed under `platform` and `endpoint`
+    ///
+    /// `endpoint` is the method and path template, e.g.
+    /// "GET /repos/:repo/pulls/:number", so requests for different reviews
+    /// share a series. Rate-limit headers update the remaining-requests gauge.
+    async fn send_tracked(self, platform: &str, endpoint: &str) -> reqwest::Result<Response>;
+}
+
+#[async_trait]
+impl TrackedSend for RequestBuilder {
+    async fn send_tracked(self, platform: &str, endpoint: &str) -> reqwest::Result<Response> {
+        let start = Instant::now();
+        let result = self.send().await;
+        
+        let metrics = metrics::metrics();
+        metrics.adapter_latency.with_label_values(&[platform, endpoint]).observe(start.elapsed().as_secs_f64());
+        let status = match &result {
+            Ok(response) => response.status().as_str().to_string(),
+            Err(_) => "error".to_string(),
+        };
+        metrics.adapter_requests.with_label_values(&[platform, endpoint, &status]).inc();
+        
+        let remaining = result.as_ref().ok()
+            .and_then(|response| response.headers().get("x-ratelimit-remaining"))
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<i64>().ok());
+        if let Some(remaining) = remaining {
+            metrics.rate_limit_remaining.with_label_values(&[platform]).set(remaining);
+        }
+        
+        result
+    }
+}
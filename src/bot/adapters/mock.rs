@@ -1,28 +1,193 @@
 use async_trait::async_trait;
 use std::sync::{Arc, Mutex};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use crate::bot::adapter::VcsAdapter;
-use crate::review::{ReviewId, ReviewStatus, ReviewState, CiStatus};
-use crate::utils::error::Result;
+use crate::review::{ReviewId, ReviewHead, ReviewStatus, ReviewSummary, ReviewState, ReviewVote, ReviewDecision, CiStatus, CiCheck, Approval};
+use crate::review::{CommentThread, InlineComment, RepoPermission, ReviewComment};
+use chrono::{DateTime, Utc};
+use crate::utils::error::{GarryError, Result};
+
+/// Username the mock adapter authors reviews as
+pub const MOCK_USER: &str = "mock-user";
+
+/// Username `approve_review` approves as in the mock adapter
+pub const MOCK_REVIEWER: &str = "mock-reviewer";
 
 /// Mock VCS adapter for testing
+///
+/// Clones share state, so a test can keep a handle on an adapter it hands off.
+#[derive(Clone)]
 pub struct MockVcsAdapter {
     reviews: Arc<Mutex<HashMap<ReviewId, ReviewStatus>>>,
+    summaries: Arc<Mutex<HashMap<ReviewId, ReviewSummary>>>,
+    files: Arc<Mutex<HashMap<String, String>>>,
+    teams: Arc<Mutex<HashMap<String, Vec<String>>>>,
+    comments: Arc<Mutex<HashMap<ReviewId, Vec<ReviewComment>>>>,
+    permissions: Arc<Mutex<HashMap<String, RepoPermission>>>,
+    threads: Arc<Mutex<HashMap<ReviewId, Vec<CommentThread>>>>,
     next_id: Arc<Mutex<u64>>,
+    next_comment_id: Arc<Mutex<u64>>,
 }
 
 impl MockVcsAdapter {
     pub fn new() -> Self {
         Self {
             reviews: Arc::new(Mutex::new(HashMap::new())),
+            summaries: Arc::new(Mutex::new(HashMap::new())),
+            files: Arc::new(Mutex::new(HashMap::new())),
+            teams: Arc::new(Mutex::new(HashMap::new())),
+            comments: Arc::new(Mutex::new(HashMap::new())),
+            permissions: Arc::new(Mutex::new(HashMap::new())),
+            threads: Arc::new(Mutex::new(HashMap::new())),
             next_id: Arc::new(Mutex::new(1)),
+            next_comment_id: Arc::new(Mutex::new(1)),
+        }
+    }
+    
+    /// Record a review vote, updating the summary state like a forge would
+    pub fn add_vote(&self, review_id: &ReviewId, reviewer: &str, decision: ReviewDecision) {
+        self.add_label_vote(review_id, reviewer, decision, BTreeMap::new());
+    }
+    
+    /// Record a review with label votes, as `submit_votes` would
+    pub fn add_label_vote(&self, review_id: &ReviewId, reviewer: &str, decision: ReviewDecision, labels: BTreeMap<String, i8>) {
+        if let Some(status) = self.reviews.lock().unwrap().get_mut(review_id) {
+            let now = Utc::now();
+            status.votes.push(ReviewVote {
+                reviewer: reviewer.to_string(),
+                decision,
+                submitted_at: now,
+                commit_id: None,
+                labels,
+            });
+            // Drafts stay drafts whatever the votes say
+            let draft = status.state == ReviewState::Draft;
+            match decision {
+                ReviewDecision::Approved => {
+                    if !draft {
+                        status.state = ReviewState::Approved;
+                    }
+                    status.approvals.push(Approval { reviewer: reviewer.to_string(), approved_at: now });
+                },
+                ReviewDecision::ChangesRequested if !draft => status.state = ReviewState::ChangesRequested,
+                _ => {},
+            }
+        }
+    }
+    
+    /// Simulate pushing new commits to a review
+    pub fn push(&self, review_id: &ReviewId) {
+        if let Some(status) = self.reviews.lock().unwrap().get_mut(review_id) {
+            status.pushed_at = Some(Utc::now());
+        }
+    }
+    
+    /// Set the paths a review changes
+    pub fn set_changed_files(&self, review_id: &ReviewId, files: Vec<String>) {
+        if let Some(status) = self.reviews.lock().unwrap().get_mut(review_id) {
+            status.changed_files = files;
+        }
+    }
+    
+    /// Set a file's contents on every branch
+    pub fn set_file(&self, path: &str, content: &str) {
+        self.files.lock().unwrap().insert(path.to_string(), content.to_string());
+    }
+    
+    /// Set the members of a team
+    pub fn set_team_members(&self, team: &str, members: Vec<String>) {
+        self.teams.lock().unwrap().insert(team.to_string(), members);
+    }
+    
+    /// Set when a review last saw activity
+    pub fn set_updated_at(&self, review_id: &ReviewId, updated_at: DateTime<Utc>) {
+        if let Some(summary) = self.summaries.lock().unwrap().get_mut(review_id) {
+            summary.updated_at = updated_at;
+        }
+    }
+    
+    /// Set when a review was opened
+    pub fn set_created_at(&self, review_id: &ReviewId, created_at: DateTime<Utc>) {
+        if let Some(summary) = self.summaries.lock().unwrap().get_mut(review_id) {
+            summary.created_at = created_at;
+        }
+    }
+    
+    /// Bodies of the comments posted on a review, oldest first
+    pub fn comments(&self, review_id: &ReviewId) -> Vec<String> {
+        self.comments.lock().unwrap().get(review_id)
+            .map(|comments| comments.iter().map(|c| c.body.clone()).collect())
+            .unwrap_or_default()
+    }
+    
+    /// Post a comment on a review as someone else, returning its ID
+    pub fn add_comment(&self, review_id: &ReviewId, author: &str, body: &str) -> String {
+        let id = {
+            let mut next_id = self.next_comment_id.lock().unwrap();
+            *next_id += 1;
+            format!("comment-{}", *next_id - 1)
+        };
+        self.comments.lock().unwrap().entry(review_id.clone()).or_default().push(ReviewComment {
+            id: id.clone(),
+            author: author.to_string(),
+            body: body.to_string(),
+            created_at: Utc::now(),
+        });
+        // Comments count as activity, like on a real forge
+        self.set_updated_at(review_id, Utc::now());
+        id
+    }
+    
+    /// Set a user's access to the repository (users default to read access)
+    pub fn set_permission(&self, user: &str, permission: RepoPermission) {
+        self.permissions.lock().unwrap().insert(user.to_string(), permission);
+    }
+    
+    /// Add an inline comment thread to a review
+    pub fn add_thread(&self, review_id: &ReviewId, thread: CommentThread) {
+        self.threads.lock().unwrap().entry(review_id.clone()).or_default().push(thread);
+    }
+    
+    /// Mark a review's thread resolved
+    pub fn resolve_thread(&self, review_id: &ReviewId, thread_id: &str) {
+        if let Some(threads) = self.threads.lock().unwrap().get_mut(review_id) {
+            for thread in threads.iter_mut().filter(|t| t.id == thread_id) {
+                thread.resolved = true;
+            }
+        }
+    }
+    
+    /// Set the labels on a review
+    pub fn set_labels(&self, review_id: &ReviewId, labels: &[&str]) {
+        if let Some(status) = self.reviews.lock().unwrap().get_mut(review_id) {
+            status.labels = labels.iter().map(|l| l.to_string()).collect();
+        }
+    }
+    
+    /// Set the commit a review merges as, e.g. a real squash commit in a test repository
+    pub fn set_merge_commit(&self, review_id: &ReviewId, sha: &str) {
+        if let Some(status) = self.reviews.lock().unwrap().get_mut(review_id) {
+            status.merge_commit = Some(sha.to_string());
+        }
+    }
+    
+    /// Set the CI status of a review
+    pub fn set_ci_status(&self, review_id: &ReviewId, ci_status: CiStatus) {
+        if let Some(status) = self.reviews.lock().unwrap().get_mut(review_id) {
+            status.ci_status = ci_status;
         }
     }
 }
 
+impl Default for MockVcsAdapter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[async_trait]
 impl VcsAdapter for MockVcsAdapter {
-    async fn create_review(&self, _branch: &str, _title: &str, _description: &str) -> Result<(ReviewId, String)> {
+    async fn create_review(&self, branch: &str, target: &str, title: &str, _description: &str) -> Result<(ReviewId, String)> {
         let mut next_id = self.next_id.lock().unwrap();
         let id = ReviewId::new(next_id.to_string());
         *next_id += 1;
@@ -33,18 +198,62 @@ impl VcsAdapter for MockVcsAdapter {
             approvals: vec![],
             ci_status: CiStatus::Pending,
             mergeable: true,
+            author: MOCK_USER.to_string(),
+            votes: vec![],
+            head_sha: String::new(),
+            pushed_at: None,
+            changed_files: vec![],
+            changed_files_truncated: false,
+            threads: vec![],
+            head_branch: branch.to_string(),
+            target_branch: target.to_string(),
+            labels: vec![],
+            merge_commit: None,
+        };
+        
+        let url = self.review_url(&id);
+        let summary = ReviewSummary {
+            id: id.clone(),
+            title: title.to_string(),
+            author: MOCK_USER.to_string(),
+            branch: branch.to_string(),
+            url: url.clone(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            requested_reviewers: vec![],
         };
         
         self.reviews.lock().unwrap().insert(id.clone(), status);
-        let url = format!("https://mock-vcs.example.com/review/{}", id.as_str());
+        self.summaries.lock().unwrap().insert(id.clone(), summary);
+        Ok((id, url))
+    }
+    
+    async fn create_draft_review(&self, branch: &str, target: &str, title: &str, description: &str) -> Result<(ReviewId, String)> {
+        let (id, url) = self.create_review(branch, target, title, description).await?;
+        if let Some(status) = self.reviews.lock().unwrap().get_mut(&id) {
+            status.state = ReviewState::Draft;
+        }
         Ok((id, url))
     }
     
+    async fn mark_ready_for_review(&self, review_id: &ReviewId) -> Result<()> {
+        let mut reviews = self.reviews.lock().unwrap();
+        let status = reviews.get_mut(review_id)
+            .ok_or_else(|| GarryError::ReviewNotFound(review_id.to_string()))?;
+        if status.state != ReviewState::Draft {
+            return Err(GarryError::VcsError(format!("Review {} is not a draft", review_id)));
+        }
+        status.state = ReviewState::Open;
+        Ok(())
+    }
+    
     async fn get_review_status(&self, review_id: &ReviewId) -> Result<ReviewStatus> {
         let reviews = self.reviews.lock().unwrap();
-        reviews.get(review_id)
+        let mut status = reviews.get(review_id)
             .cloned()
-            .ok_or_else(|| crate::utils::error::GarryError::ReviewNotFound(review_id.to_string()))
+            .ok_or_else(|| GarryError::ReviewNotFound(review_id.to_string()))?;
+        status.threads = self.threads.lock().unwrap().get(review_id).cloned().unwrap_or_default();
+        Ok(status)
     }
     
     async fn merge_review(&self, review_id: &ReviewId) -> Result<()> {
@@ -55,21 +264,143 @@ impl VcsAdapter for MockVcsAdapter {
         Ok(())
     }
     
-    async fn post_comment(&self, _review_id: &ReviewId, _message: &str) -> Result<()> {
+    async fn post_comment(&self, review_id: &ReviewId, message: &str) -> Result<String> {
+        Ok(self.add_comment(review_id, MOCK_USER, message))
+    }
+    
+    async fn list_comments(&self, review_id: &ReviewId, since: Option<DateTime<Utc>>) -> Result<Vec<ReviewComment>> {
+        let comments = self.comments.lock().unwrap().get(review_id).cloned().unwrap_or_default();
+        Ok(comments.into_iter().filter(|c| since.is_none_or(|since| c.created_at >= since)).collect())
+    }
+    
+    async fn find_own_comments(&self, review_id: &ReviewId, marker: &str) -> Result<Vec<ReviewComment>> {
+        let comments = self.list_comments(review_id, None).await?;
+        Ok(comments.into_iter().filter(|c| c.author == MOCK_USER && c.body.contains(marker)).collect())
+    }
+    
+    async fn update_comment(&self, review_id: &ReviewId, comment_id: &str, message: &str) -> Result<()> {
+        let mut comments = self.comments.lock().unwrap();
+        let comment = comments.get_mut(review_id)
+            .and_then(|comments| comments.iter_mut().find(|c| c.id == comment_id))
+            .ok_or_else(|| GarryError::ReviewNotFound(format!("comment {} on review {}", comment_id, review_id)))?;
+        comment.body = message.to_string();
         Ok(())
     }
     
-    async fn approve_review(&self, review_id: &ReviewId, _message: Option<&str>) -> Result<()> {
+    async fn delete_comment(&self, review_id: &ReviewId, comment_id: &str) -> Result<()> {
+        let mut comments = self.comments.lock().unwrap();
+        let comments = comments.get_mut(review_id)
+            .ok_or_else(|| GarryError::ReviewNotFound(format!("comment {} on review {}", comment_id, review_id)))?;
+        let before = comments.len();
+        comments.retain(|c| c.id != comment_id);
+        if comments.len() == before {
+            return Err(GarryError::ReviewNotFound(format!("comment {} on review {}", comment_id, review_id)));
+        }
+        Ok(())
+    }
+    
+    async fn post_inline_comment(&self, review_id: &ReviewId, comment: &InlineComment) -> Result<()> {
+        let head_sha = self.reviews.lock().unwrap().get(review_id)
+            .ok_or_else(|| GarryError::ReviewNotFound(review_id.to_string()))?
+            .head_sha.clone();
+        let mut threads = self.threads.lock().unwrap();
+        let threads = threads.entry(review_id.clone()).or_default();
+        let id = format!("thread-{}", threads.len() + 1);
+        threads.push(CommentThread {
+            id: id.clone(),
+            file: comment.file.clone(),
+            line: Some(comment.line),
+            side: comment.side,
+            commit_id: (!head_sha.is_empty()).then_some(head_sha),
+            resolved: false,
+            outdated: false,
+            comments: vec![ReviewComment {
+                id: format!("{}-1", id),
+                author: MOCK_USER.to_string(),
+                body: comment.body.clone(),
+                created_at: Utc::now(),
+            }],
+        });
+        Ok(())
+    }
+    
+    async fn list_comment_threads(&self, review_id: &ReviewId) -> Result<Vec<CommentThread>> {
+        Ok(self.threads.lock().unwrap().get(review_id).cloned().unwrap_or_default())
+    }
+    
+    async fn request_reviewers(&self, review_id: &ReviewId, reviewers: &[String]) -> Result<()> {
+        let mut summaries = self.summaries.lock().unwrap();
+        let summary = summaries.get_mut(review_id)
+            .ok_or_else(|| GarryError::ReviewNotFound(review_id.to_string()))?;
+        for reviewer in reviewers {
+            if !summary.requested_reviewers.contains(reviewer) {
+                summary.requested_reviewers.push(reviewer.clone());
+            }
+        }
+        Ok(())
+    }
+    
+    async fn close_review(&self, review_id: &ReviewId) -> Result<()> {
         let mut reviews = self.reviews.lock().unwrap();
-        if let Some(status) = reviews.get_mut(review_id) {
-            status.state = ReviewState::Approved;
+        let status = reviews.get_mut(review_id)
+            .ok_or_else(|| GarryError::ReviewNotFound(review_id.to_string()))?;
+        if status.state == ReviewState::Merged {
+            return Err(GarryError::VcsError(format!("Review {} is already merged", review_id)));
         }
+        status.state = ReviewState::Closed;
+        Ok(())
+    }
+    
+    async fn reopen_review(&self, review_id: &ReviewId) -> Result<()> {
+        let mut reviews = self.reviews.lock().unwrap();
+        let status = reviews.get_mut(review_id)
+            .ok_or_else(|| GarryError::ReviewNotFound(review_id.to_string()))?;
+        if status.state != ReviewState::Closed {
+            return Err(GarryError::VcsError(format!("Review {} is not closed", review_id)));
+        }
+        status.state = ReviewState::Open;
+        Ok(())
+    }
+    
+    async fn approve_review(&self, review_id: &ReviewId, _message: Option<&str>) -> Result<()> {
+        self.add_vote(review_id, MOCK_REVIEWER, ReviewDecision::Approved);
+        Ok(())
+    }
+    
+    async fn submit_votes(
+        &self,
+        review_id: &ReviewId,
+        decision: ReviewDecision,
+        labels: &BTreeMap<String, i8>,
+        _message: Option<&str>,
+    ) -> Result<()> {
+        self.add_label_vote(review_id, MOCK_REVIEWER, decision, labels.clone());
         Ok(())
     }
     
     async fn list_pending_reviews(&self) -> Result<Vec<ReviewId>> {
         let reviews = self.reviews.lock().unwrap();
-        Ok(reviews.keys().cloned().collect())
+        Ok(reviews.values()
+            .filter(|s| !matches!(s.state, ReviewState::Merged | ReviewState::Closed))
+            .map(|s| s.id.clone())
+            .collect())
+    }
+    
+    async fn list_open_reviews(&self) -> Result<Vec<ReviewSummary>> {
+        let reviews = self.reviews.lock().unwrap();
+        let summaries = self.summaries.lock().unwrap();
+        let mut open: Vec<ReviewSummary> = summaries.values()
+            .filter(|s| reviews.get(&s.id)
+                .map(|r| r.state != ReviewState::Merged && r.state != ReviewState::Closed)
+                .unwrap_or(false))
+            .cloned()
+            .collect();
+        open.sort_by_key(|s| s.id.as_str().parse::<u64>().unwrap_or(0));
+        Ok(open)
+    }
+    
+    async fn current_user(&self) -> Result<String> {
+        Ok(MOCK_USER.to_string())
     }
     
     async fn get_ci_status(&self, review_id: &ReviewId) -> Result<CiStatus> {
@@ -79,11 +410,62 @@ impl VcsAdapter for MockVcsAdapter {
             .unwrap_or(CiStatus::Pending))
     }
     
+    async fn get_ci_checks(&self, review_id: &ReviewId) -> Result<Vec<CiCheck>> {
+        let status = self.get_ci_status(review_id).await?;
+        Ok(vec![CiCheck { name: "mock-ci".to_string(), status }])
+    }
+    
+    async fn find_review_for_branch(&self, branch: &str) -> Result<Option<(ReviewId, String)>> {
+        let summaries = self.summaries.lock().unwrap();
+        Ok(summaries.values()
+            .find(|s| s.branch == branch)
+            .map(|s| (s.id.clone(), s.url.clone())))
+    }
+    
+    async fn get_review_head(&self, review_id: &ReviewId) -> Result<ReviewHead> {
+        let summaries = self.summaries.lock().unwrap();
+        let summary = summaries.get(review_id)
+            .ok_or_else(|| GarryError::ReviewNotFound(review_id.to_string()))?;
+        
+        Ok(ReviewHead {
+            branch: summary.branch.clone(),
+            sha: String::new(),
+            review_ref: None,
+            fork_url: None,
+        })
+    }
+    
+    async fn get_file_contents(&self, path: &str, _branch: &str) -> Result<Option<String>> {
+        Ok(self.files.lock().unwrap().get(path).cloned())
+    }
+    
+    async fn find_files(&self, name: &str, _branch: &str) -> Result<Vec<String>> {
+        let mut paths: Vec<String> = self.files.lock().unwrap()
+            .keys()
+            .filter(|path| path.rsplit('/').next() == Some(name))
+            .cloned()
+            .collect();
+        paths.sort();
+        Ok(paths)
+    }
+    
+    async fn get_permission(&self, user: &str) -> Result<RepoPermission> {
+        Ok(self.permissions.lock().unwrap().get(user).copied().unwrap_or(RepoPermission::Read))
+    }
+    
+    async fn get_team_members(&self, team: &str) -> Result<Vec<String>> {
+        Ok(self.teams.lock().unwrap().get(team).cloned().unwrap_or_default())
+    }
+    
     async fn setup_repository_protection(&self, _main_branch: &str, _bot_user: &str) -> Result<()> {
         // Mock implementation - no-op
         Ok(())
     }
     
+    fn review_url(&self, review_id: &ReviewId) -> String {
+        format!("https://mock-vcs.example.com/review/{}", review_id.as_str())
+    }
+    
     fn review_name(&self) -> &str {
         "Review"
     }
@@ -1,5 +1,6 @@
 pub mod github;
 pub mod mock;
+mod http;
 
 pub use github::GithubAdapter;
 pub use mock::MockVcsAdapter;
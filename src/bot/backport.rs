@@ -0,0 +1,208 @@
+use std::path::{Path, PathBuf};
+use chrono::{DateTime, Utc};
+use crate::bot::adapter::VcsAdapter;
+use crate::bot::queue::QueueSnapshot;
+use crate::git::GitRepository;
+use crate::git::cherrypick::CherryPick;
+use crate::review::{ReviewId, ReviewState, ReviewStatus};
+use crate::utils::config::BackportConfig;
+use crate::utils::error::{GarryError, Result};
+use tracing::{info, warn};
+
+/// What backporting a review to a branch did
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BackportOutcome {
+    /// A review of the cherry-pick was opened against the branch
+    Opened { review_id: ReviewId, url: String },
+    /// The cherry-pick conflicted in these files, which was reported on the original review
+    Conflicts(Vec<String>),
+}
+
+/// Hidden marker on the comment reporting a review's backport to `target`
+fn marker(target: &str) -> String {
+    format!("<!-- garry-backport:{} -->", target)
+}
+
+/// Hidden marker on the comment reporting that a backport to `target` conflicted
+///
+/// Distinct from [`marker`], so a conflicted backport can be tried again.
+fn conflict_marker(target: &str) -> String {
+    format!("<!-- garry-backport-conflict:{} -->", target)
+}
+
+/// Branch a review's backport to `target` is pushed as, e.g. "backport/42-release-2.3"
+pub fn branch_name(review_id: &ReviewId, target: &str) -> String {
+    format!("backport/{}-{}", review_id, target.replace('/', "-"))
+}
+
+/// Backport a merged review to `target`
+///
+/// The commit the review merged as is cherry-picked onto `target` in the
+/// clone at `checkout`, pushed to `remote`, and opened as a review linked
+/// to the original. Conflicts are reported on the original review instead.
+pub async fn backport(
+    adapter: &dyn VcsAdapter,
+    checkout: &Path,
+    remote: &str,
+    review_id: &ReviewId,
+    target: &str,
+) -> Result<BackportOutcome> {
+    let status = adapter.get_review_status(review_id).await?;
+    backport_merged(adapter, checkout, remote, &status, target).await
+}
+
+/// Backport a review whose status is already known
+async fn backport_merged(
+    adapter: &dyn VcsAdapter,
+    checkout: &Path,
+    remote: &str,
+    status: &ReviewStatus,
+    target: &str,
+) -> Result<BackportOutcome> {
+    let review_id = &status.id;
+    let commit = match (&status.state, &status.merge_commit) {
+        (ReviewState::Merged, Some(commit)) => commit.clone(),
+        _ => return Err(GarryError::VcsError(format!("Review {} hasn't been merged", review_id))),
+    };
+    if status.target_branch == target {
+        return Err(GarryError::VcsError(format!("Review {} already merged into {}", review_id, target)));
+    }
+    if let Some(comment) = adapter.find_own_comments(review_id, &marker(target)).await?.into_iter().next() {
+        return Err(GarryError::VcsError(format!(
+            "Review {} was already backported to {}: {}",
+            review_id, target, comment.body.lines().next().unwrap_or_default()
+        )));
+    }
+    
+    // git2 blocks, and the clone is opened here so the bot's loop stays Send
+    let branch = branch_name(review_id, target);
+    let (path, remote, source, onto, pick_branch, sha) = (
+        checkout.to_path_buf(), remote.to_string(), status.target_branch.clone(),
+        target.to_string(), branch.clone(), commit.clone(),
+    );
+    let (result, summary) = tokio::task::spawn_blocking(move || -> Result<(CherryPick, String)> {
+        let repo = GitRepository::open(&path)?;
+        let tracking = |branch: &str| format!("refs/remotes/{}/{}", remote, branch);
+        repo.fetch(&remote, &[
+            &format!("+refs/heads/{}:{}", source, tracking(&source)),
+            &format!("+refs/heads/{}:{}", onto, tracking(&onto)),
+        ])?;
+        let summary = repo.commit_summary(&sha)?;
+        let result = repo.cherry_pick_to_branch(&sha, repo.resolve_ref(&tracking(&onto))?, &pick_branch)?;
+        if let CherryPick::Picked(_) = result {
+            repo.force_push_branch(&pick_branch, &remote)?;
+        }
+        Ok((result, summary))
+    }).await.map_err(|e| GarryError::Other(format!("Backport task failed: {}", e)))??;
+    
+    let short = &commit[..commit.len().min(10)];
+    let review_name = adapter.review_name();
+    match result {
+        CherryPick::Picked(_) => {
+            let title = format!("[{}] {}", target, summary);
+            let description = format!(
+                "Backport of #{} ({}) to {}.\n\nCherry-picked from {}.",
+                review_id, adapter.review_url(review_id), target, commit
+            );
+            let (backport_id, url) = adapter.create_review(&branch, target, &title, &description).await?;
+            let message = format!("Backported to {} in #{}: {}\n{}", target, backport_id, url, marker(target));
+            adapter.post_comment(review_id, &message).await?;
+            info!("Backported review {} to {} as {} {}", review_id, target, review_name, backport_id);
+            Ok(BackportOutcome::Opened { review_id: backport_id, url })
+        },
+        CherryPick::Empty => Err(GarryError::VcsError(format!(
+            "{} already has the changes of review {} ({})", target, review_id, short
+        ))),
+        CherryPick::Conflicts(files) => {
+            let listed: Vec<String> = files.iter().map(|f| format!("- `{}`", f)).collect();
+            let message = format!(
+                "⚠️ Couldn't backport to {}: cherry-picking {} conflicts in\n\n{}\n\n\
+                 Backport it by hand with `git cherry-pick -x {}` on a branch from {}.\n{}",
+                target, short, listed.join("\n"), commit, target, conflict_marker(target)
+            );
+            adapter.post_comment(review_id, &message).await?;
+            warn!("Backporting review {} to {} conflicts in {} file(s)", review_id, target, files.len());
+            Ok(BackportOutcome::Conflicts(files))
+        },
+    }
+}
+
+/// Backports reviews the merge queue merges with backport labels
+///
+/// A label like "backport/release-2.3" names the branch with `/` written as
+/// `-`, or as-is. Only labels present when the review merges are seen;
+/// `garry backport` handles the rest.
+pub struct Backporter {
+    checkout: PathBuf,
+    remote: String,
+    label_prefix: String,
+    /// Merges at or before this have been looked at
+    since: DateTime<Utc>,
+}
+
+impl Backporter {
+    /// Create a backporter from the config, or `None` if the bot has no clone to work in
+    pub fn new(config: &BackportConfig, remote: &str, now: DateTime<Utc>) -> Option<Self> {
+        let checkout = config.checkout.as_ref()?;
+        Some(Self {
+            checkout: PathBuf::from(checkout),
+            remote: remote.to_string(),
+            label_prefix: config.label_prefix.clone(),
+            since: now,
+        })
+    }
+    
+    /// Backport reviews merged since the last poll, returning how many backports were attempted
+    ///
+    /// Merges are read from a snapshot of the queue, so the fetches and pushes
+    /// don't hold it locked. A review that can't be backported doesn't hold up
+    /// the others.
+    pub async fn poll(&mut self, adapter: &dyn VcsAdapter, queue: &QueueSnapshot) -> Result<usize> {
+        let merged: Vec<_> = queue.outcomes.iter()
+            .filter(|o| o.at > self.since && o.failure.is_none())
+            .map(|o| (o.review_id.clone(), o.at))
+            .collect();
+        let mut attempted = 0;
+        
+        for (review_id, at) in merged {
+            self.since = self.since.max(at);
+            match self.backport_labelled(adapter, &review_id).await {
+                Ok(count) => attempted += count,
+                Err(e) => warn!("Could not backport review {}: {}", review_id, e),
+            }
+        }
+        
+        Ok(attempted)
+    }
+    
+    /// Backport a merged review to each branch its labels ask for
+    async fn backport_labelled(&self, adapter: &dyn VcsAdapter, review_id: &ReviewId) -> Result<usize> {
+        let status = adapter.get_review_status(review_id).await?;
+        let wanted: Vec<&str> = status.labels.iter()
+            .filter_map(|l| l.strip_prefix(&self.label_prefix))
+            .collect();
+        if wanted.is_empty() {
+            return Ok(0);
+        }
+        
+        let path = self.checkout.clone();
+        let remote = self.remote.clone();
+        let branches = tokio::task::spawn_blocking(move || GitRepository::open(path)?.remote_branches(&remote))
+            .await
+            .map_err(|e| GarryError::Other(format!("Listing branches failed: {}", e)))??;
+        
+        let mut attempted = 0;
+        for label in wanted {
+            let Some(target) = branches.iter().find(|b| *b == label || b.replace('/', "-") == label) else {
+                let message = format!("⚠️ Couldn't backport: no branch matches the label `{}{}`.", self.label_prefix, label);
+                adapter.post_comment(review_id, &message).await?;
+                continue;
+            };
+            attempted += 1;
+            if let Err(e) = backport_merged(adapter, &self.checkout, &self.remote, &status, target).await {
+                warn!("Could not backport review {} to {}: {}", review_id, target, e);
+            }
+        }
+        Ok(attempted)
+    }
+}
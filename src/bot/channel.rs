@@ -0,0 +1,67 @@
+use async_trait::async_trait;
+use crate::bot::notifier::NotificationType;
+use crate::review::ReviewId;
+use crate::utils::config::ChannelConfig;
+use crate::utils::error::Result;
+
+/// A notification about a review or repository, ready to send
+#[derive(Debug, Clone)]
+pub struct Notification {
+    /// Repository the notification is about, as "owner/repo"
+    pub repository: String,
+    /// Review the notification is about; digests aren't about one review
+    pub review_id: Option<ReviewId>,
+    /// Branch the review merges into; digests aren't about one branch
+    pub target: Option<String>,
+    pub kind: NotificationType,
+    /// Rendered one-line summary, e.g. for email subjects
+    pub subject: String,
+    /// Rendered message, in Markdown
+    pub message: String,
+}
+
+/// Somewhere notifications can be sent besides the review itself
+#[async_trait]
+pub trait NotificationChannel: Send + Sync {
+    /// Send a notification
+    async fn send(&self, notification: &Notification) -> Result<()>;
+    
+    /// Name of the channel type, for logs
+    fn name(&self) -> &str;
+}
+
+/// Which notifications a channel receives
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChannelRoute {
+    /// Event names to send (empty = all)
+    pub events: Vec<String>,
+    /// Repositories to send for (empty = all)
+    pub repositories: Vec<String>,
+    /// Digest reports to send, by name (empty = all)
+    pub digests: Vec<String>,
+}
+
+impl ChannelRoute {
+    /// Whether a notification should go to the channel
+    pub fn matches(&self, notification: &Notification) -> bool {
+        let event = notification.kind.event();
+        let digest = match &notification.kind {
+            NotificationType::Digest(report) => self.digests.is_empty() || self.digests.contains(report),
+            _ => true,
+        };
+        (self.events.is_empty() || self.events.iter().any(|e| e == event))
+            && (self.repositories.is_empty()
+                || self.repositories.iter().any(|r| r.eq_ignore_ascii_case(&notification.repository)))
+            && digest
+    }
+}
+
+impl From<&ChannelConfig> for ChannelRoute {
+    fn from(config: &ChannelConfig) -> Self {
+        Self {
+            events: config.events.clone(),
+            repositories: config.repositories.clone(),
+            digests: config.digests.clone(),
+        }
+    }
+}
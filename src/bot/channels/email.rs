@@ -0,0 +1,99 @@
+use async_trait::async_trait;
+use lettre::message::header::ContentType;
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use crate::bot::channel::{Notification, NotificationChannel};
+use crate::bot::templates::MessageFormat;
+use crate::utils::config::SmtpSecurity;
+use crate::utils::error::{GarryError, Result};
+use tracing::debug;
+
+/// Emails notifications through an SMTP server
+pub struct EmailChannel {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+    to: Vec<Mailbox>,
+    format: MessageFormat,
+}
+
+impl EmailChannel {
+    /// Create a channel sending from `from` to every address in `to`
+    ///
+    /// `credentials` is a username and password, if the server needs them.
+    pub fn new(
+        host: &str,
+        port: u16,
+        security: SmtpSecurity,
+        credentials: Option<(String, String)>,
+        from: &str,
+        to: &[String],
+    ) -> Result<Self> {
+        let invalid = |e: lettre::transport::smtp::Error| {
+            GarryError::ConfigError(format!("Invalid SMTP server '{}': {}", host, e))
+        };
+        let mut builder = match security {
+            SmtpSecurity::Starttls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host).map_err(invalid)?,
+            SmtpSecurity::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(host).map_err(invalid)?,
+            SmtpSecurity::None => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(host),
+        }
+        .port(port);
+        if let Some((username, password)) = credentials {
+            builder = builder.credentials(Credentials::new(username, password));
+        }
+        
+        if to.is_empty() {
+            return Err(GarryError::ConfigError("Email channel needs at least one recipient".to_string()));
+        }
+        let to = to.iter().map(|address| parse_mailbox(address)).collect::<Result<Vec<_>>>()?;
+        
+        Ok(Self {
+            transport: builder.build(),
+            from: parse_mailbox(from)?,
+            to,
+            format: MessageFormat::Plain,
+        })
+    }
+    
+    /// Send messages in `format` instead of plain text
+    pub fn with_format(mut self, format: MessageFormat) -> Self {
+        self.format = format;
+        self
+    }
+}
+
+#[async_trait]
+impl NotificationChannel for EmailChannel {
+    async fn send(&self, notification: &Notification) -> Result<()> {
+        debug!("Emailing {} notification to {} recipient(s)", notification.kind.event(), self.to.len());
+        
+        let mut builder = Message::builder()
+            .from(self.from.clone())
+            .subject(notification.subject.clone())
+            .header(ContentType::TEXT_PLAIN);
+        for to in &self.to {
+            builder = builder.to(to.clone());
+        }
+        let email = builder
+            .body(self.format.render(&notification.message))
+            .map_err(|e| GarryError::NotificationError(format!("Could not build email: {}", e)))?;
+        
+        self.transport
+            .send(email)
+            .await
+            .map_err(|e| GarryError::NotificationError(format!("Could not send email: {}", e)))?;
+        
+        Ok(())
+    }
+    
+    fn name(&self) -> &str {
+        "email"
+    }
+}
+
+/// Parse an address like "alice@example.com" or "Alice <alice@example.com>"
+fn parse_mailbox(address: &str) -> Result<Mailbox> {
+    address
+        .parse()
+        .map_err(|e| GarryError::ConfigError(format!("Invalid email address '{}': {}", address, e)))
+}
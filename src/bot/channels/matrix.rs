@@ -0,0 +1,86 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use async_trait::async_trait;
+use reqwest::{Client, Url};
+use serde::Serialize;
+use crate::bot::channel::{Notification, NotificationChannel};
+use crate::bot::templates::MessageFormat;
+use crate::utils::error::{GarryError, Result};
+use tracing::debug;
+
+/// Posts messages to a Matrix room through the client-server API
+pub struct MatrixChannel {
+    client: Client,
+    homeserver: String,
+    room_id: String,
+    access_token: String,
+    format: MessageFormat,
+    /// Makes transaction IDs unique within this process
+    next_txn: AtomicU64,
+}
+
+impl MatrixChannel {
+    /// Create a channel posting to `room_id` on `homeserver` as the token's user
+    pub fn new(homeserver: String, room_id: String, access_token: String) -> Self {
+        Self {
+            client: Client::new(),
+            homeserver,
+            room_id,
+            access_token,
+            format: MessageFormat::Markdown,
+            next_txn: AtomicU64::new(0),
+        }
+    }
+    
+    /// Send messages in `format` instead of Markdown
+    pub fn with_format(mut self, format: MessageFormat) -> Self {
+        self.format = format;
+        self
+    }
+    
+    /// URL for sending a message event, with a fresh transaction ID
+    fn send_url(&self) -> Result<Url> {
+        let mut url = Url::parse(&self.homeserver)
+            .map_err(|e| GarryError::ConfigError(format!("Invalid Matrix homeserver '{}': {}", self.homeserver, e)))?;
+        let txn_id = format!(
+            "garry-{}-{}",
+            chrono::Utc::now().timestamp_millis(),
+            self.next_txn.fetch_add(1, Ordering::Relaxed)
+        );
+        url.path_segments_mut()
+            .map_err(|_| GarryError::ConfigError(format!("Invalid Matrix homeserver '{}'", self.homeserver)))?
+            .pop_if_empty()
+            .extend(["_matrix", "client", "v3", "rooms", &self.room_id, "send", "m.room.message", &txn_id]);
+        Ok(url)
+    }
+}
+
+#[async_trait]
+impl NotificationChannel for MatrixChannel {
+    async fn send(&self, notification: &Notification) -> Result<()> {
+        #[derive(Serialize)]
+        struct Message<'a> {
+            msgtype: &'a str,
+            body: &'a str,
+        }
+        
+        debug!("Posting {} notification to Matrix room {}", notification.kind.event(), self.room_id);
+        let body = format!("{}\n{}", notification.subject, self.format.render(&notification.message));
+        let response = self.client
+            .put(self.send_url()?)
+            .bearer_auth(&self.access_token)
+            .json(&Message { msgtype: "m.text", body: &body })
+            .send()
+            .await?;
+        
+        if !response.status().is_success() {
+            let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(GarryError::NotificationError(format!("Matrix send failed: {}", error_text)));
+        }
+        
+        Ok(())
+    }
+    
+    fn name(&self) -> &str {
+        "matrix"
+    }
+}
@@ -0,0 +1,40 @@
+pub mod email;
+pub mod matrix;
+pub mod slack;
+pub mod webhook;
+
+pub use email::EmailChannel;
+pub use matrix::MatrixChannel;
+pub use slack::SlackChannel;
+pub use webhook::WebhookChannel;
+
+use crate::bot::channel::NotificationChannel;
+use crate::utils::config::{ChannelConfig, ChannelKind};
+use crate::utils::error::Result;
+
+/// Create the channel described by a `[[notifications.channels]]` entry
+pub fn create_channel(config: &ChannelConfig) -> Result<Box<dyn NotificationChannel>> {
+    // Each channel keeps its own default format unless one is configured
+    macro_rules! formatted {
+        ($channel:expr) => {
+            match config.format {
+                Some(format) => Box::new($channel.with_format(format)),
+                None => Box::new($channel),
+            }
+        };
+    }
+    
+    Ok(match &config.kind {
+        ChannelKind::Slack { webhook_url } => formatted!(SlackChannel::new(webhook_url.clone())),
+        ChannelKind::Matrix { homeserver, room_id, access_token } => formatted!(MatrixChannel::new(
+            homeserver.clone(),
+            room_id.clone(),
+            access_token.clone(),
+        )),
+        ChannelKind::Webhook { url, headers } => formatted!(WebhookChannel::new(url.clone(), headers)?),
+        ChannelKind::Email { smtp_host, smtp_port, security, username, password, from, to } => {
+            let credentials = username.clone().zip(password.clone());
+            formatted!(EmailChannel::new(smtp_host, *smtp_port, *security, credentials, from, to)?)
+        },
+    })
+}
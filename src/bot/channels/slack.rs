@@ -0,0 +1,66 @@
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::Serialize;
+use crate::bot::channel::{Notification, NotificationChannel};
+use crate::bot::templates::{self, MessageFormat};
+use crate::utils::error::{GarryError, Result};
+use tracing::debug;
+
+/// Posts to a Slack-compatible incoming webhook
+///
+/// Mattermost and Rocket.Chat accept the same payload.
+pub struct SlackChannel {
+    client: Client,
+    webhook_url: String,
+    format: MessageFormat,
+}
+
+impl SlackChannel {
+    /// Create a channel posting to `webhook_url`
+    pub fn new(webhook_url: String) -> Self {
+        Self { client: Client::new(), webhook_url, format: MessageFormat::SlackBlocks }
+    }
+    
+    /// Send messages in `format` instead of Slack blocks
+    pub fn with_format(mut self, format: MessageFormat) -> Self {
+        self.format = format;
+        self
+    }
+}
+
+#[async_trait]
+impl NotificationChannel for SlackChannel {
+    async fn send(&self, notification: &Notification) -> Result<()> {
+        #[derive(Serialize)]
+        struct Payload {
+            text: String,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            blocks: Option<serde_json::Value>,
+        }
+        
+        debug!("Posting {} notification to Slack", notification.kind.event());
+        // With blocks, the text is only the fallback shown in notifications
+        let payload = match self.format {
+            MessageFormat::SlackBlocks => Payload {
+                text: notification.subject.clone(),
+                blocks: Some(templates::slack_blocks(&notification.message)),
+            },
+            format => Payload {
+                text: format!("{}\n{}", notification.subject, format.render(&notification.message)),
+                blocks: None,
+            },
+        };
+        let response = self.client.post(&self.webhook_url).json(&payload).send().await?;
+        
+        if !response.status().is_success() {
+            let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(GarryError::NotificationError(format!("Slack webhook failed: {}", error_text)));
+        }
+        
+        Ok(())
+    }
+    
+    fn name(&self) -> &str {
+        "slack"
+    }
+}
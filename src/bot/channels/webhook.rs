@@ -0,0 +1,86 @@
+use std::collections::HashMap;
+use async_trait::async_trait;
+use reqwest::{Client, header};
+use serde::Serialize;
+use crate::bot::channel::{Notification, NotificationChannel};
+use crate::bot::templates::{self, MessageFormat};
+use crate::utils::error::{GarryError, Result};
+use tracing::debug;
+
+/// Posts notifications as JSON to any URL
+///
+/// The body has `event`, `repository`, `review_id`, `target`, `subject` and
+/// `message` fields, plus `blocks` when sending Slack blocks. `review_id` and
+/// `target` are null for digests.
+pub struct WebhookChannel {
+    client: Client,
+    url: String,
+    format: MessageFormat,
+}
+
+impl WebhookChannel {
+    /// Create a channel posting to `url` with extra `headers`
+    pub fn new(url: String, headers: &HashMap<String, String>) -> Result<Self> {
+        let mut default_headers = header::HeaderMap::new();
+        for (name, value) in headers {
+            let name = header::HeaderName::from_bytes(name.as_bytes())
+                .map_err(|e| GarryError::ConfigError(format!("Invalid webhook header '{}': {}", name, e)))?;
+            let value = header::HeaderValue::from_str(value)
+                .map_err(|e| GarryError::ConfigError(format!("Invalid value for webhook header '{}': {}", name, e)))?;
+            default_headers.insert(name, value);
+        }
+        
+        let client = Client::builder()
+            .default_headers(default_headers)
+            .build()
+            .map_err(GarryError::NetworkError)?;
+        
+        Ok(Self { client, url, format: MessageFormat::Markdown })
+    }
+    
+    /// Send messages in `format` instead of Markdown
+    pub fn with_format(mut self, format: MessageFormat) -> Self {
+        self.format = format;
+        self
+    }
+}
+
+#[async_trait]
+impl NotificationChannel for WebhookChannel {
+    async fn send(&self, notification: &Notification) -> Result<()> {
+        #[derive(Serialize)]
+        struct Payload<'a> {
+            event: &'a str,
+            repository: &'a str,
+            review_id: Option<&'a str>,
+            target: Option<&'a str>,
+            subject: &'a str,
+            message: String,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            blocks: Option<serde_json::Value>,
+        }
+        
+        debug!("Posting {} notification to {}", notification.kind.event(), self.url);
+        let payload = Payload {
+            event: notification.kind.event(),
+            repository: &notification.repository,
+            review_id: notification.review_id.as_ref().map(|id| id.as_str()),
+            target: notification.target.as_deref(),
+            subject: &notification.subject,
+            message: self.format.render(&notification.message),
+            blocks: (self.format == MessageFormat::SlackBlocks).then(|| templates::slack_blocks(&notification.message)),
+        };
+        let response = self.client.post(&self.url).json(&payload).send().await?;
+        
+        if !response.status().is_success() {
+            let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(GarryError::NotificationError(format!("Webhook failed: {}", error_text)));
+        }
+        
+        Ok(())
+    }
+    
+    fn name(&self) -> &str {
+        "webhook"
+    }
+}
@@ -0,0 +1,243 @@
+use std::collections::HashMap;
+use std::fmt;
+use chrono::{DateTime, Duration, Utc};
+use tokio::sync::Mutex;
+use crate::bot::adapter::VcsAdapter;
+use crate::bot::notifier::{NotificationType, Notifier};
+use crate::bot::queue::{Priority, QueueManager};
+use crate::review::{ReviewId, ReviewSummary};
+use crate::review::roles::Action;
+use crate::utils::error::{GarryError, Result};
+use tracing::{info, warn};
+
+/// Prefix that marks a comment line as a bot command
+pub const COMMAND_PREFIX: &str = "/garry";
+
+/// Comments are picked up this long before the last poll too, in case the
+/// forge's clock is behind the bot's
+const POLL_OVERLAP_SECONDS: i64 = 60;
+
+/// A command posted in a review comment
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChatCommand {
+    /// Queue the review now, or as soon as it's approved with passing CI
+    Merge,
+    /// Queue the review now
+    Queue,
+    /// Take the review out of the queue until it's queued again
+    Dequeue,
+    /// Queue the review again after it failed
+    Retry,
+    /// Move the review ahead of (or back behind) other entries
+    Priority(Priority),
+    /// Keep the review out of the queue until it's queued again
+    Hold,
+}
+
+impl ChatCommand {
+    /// Parse the first `/garry` line of a comment
+    ///
+    /// Returns `None` if the comment has no command, and an error message
+    /// if the command isn't understood.
+    pub fn parse(body: &str) -> Option<std::result::Result<Self, String>> {
+        let line = body.lines().map(str::trim).find(|line| {
+            line.strip_prefix(COMMAND_PREFIX).is_some_and(|rest| rest.is_empty() || rest.starts_with(char::is_whitespace))
+        })?;
+        
+        let words: Vec<&str> = line.split_whitespace().skip(1).collect();
+        let command = match words.as_slice() {
+            ["merge"] => ChatCommand::Merge,
+            ["queue"] => ChatCommand::Queue,
+            ["dequeue"] => ChatCommand::Dequeue,
+            ["retry"] => ChatCommand::Retry,
+            ["priority", "high"] => ChatCommand::Priority(Priority::High),
+            ["priority", "normal"] => ChatCommand::Priority(Priority::Normal),
+            ["hold"] => ChatCommand::Hold,
+            _ => return Some(Err(format!(
+                "unknown command `{}`. Available commands: merge, queue, dequeue, retry, priority high|normal, hold",
+                line
+            ))),
+        };
+        Some(Ok(command))
+    }
+    
+    /// Action the command takes, which decides who may run it
+    pub fn action(&self) -> Action {
+        match self {
+            ChatCommand::Priority(_) => Action::SetPriority,
+            _ => Action::ControlQueue,
+        }
+    }
+    
+    /// Whether the command lets a held review back into the queue
+    pub fn releases_hold(&self) -> bool {
+        matches!(self, ChatCommand::Merge | ChatCommand::Queue | ChatCommand::Retry)
+    }
+    
+    /// Whether a review's author may run the command without the required role
+    ///
+    /// Authors can always keep their own review out of the queue.
+    pub fn allowed_for_author(&self) -> bool {
+        matches!(self, ChatCommand::Dequeue | ChatCommand::Hold)
+    }
+}
+
+impl fmt::Display for ChatCommand {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ChatCommand::Merge => write!(f, "{} merge", COMMAND_PREFIX),
+            ChatCommand::Queue => write!(f, "{} queue", COMMAND_PREFIX),
+            ChatCommand::Dequeue => write!(f, "{} dequeue", COMMAND_PREFIX),
+            ChatCommand::Retry => write!(f, "{} retry", COMMAND_PREFIX),
+            ChatCommand::Priority(Priority::High) => write!(f, "{} priority high", COMMAND_PREFIX),
+            ChatCommand::Priority(Priority::Normal) => write!(f, "{} priority normal", COMMAND_PREFIX),
+            ChatCommand::Hold => write!(f, "{} hold", COMMAND_PREFIX),
+        }
+    }
+}
+
+/// Runs `/garry` commands posted in review comments
+///
+/// Comments are polled: every poll looks at the comments posted on open
+/// reviews since the previous one. Comments from before the bot started
+/// are ignored.
+pub struct ChatOps {
+    bot_user: String,
+    last_poll: DateTime<Utc>,
+    handled: HashMap<String, DateTime<Utc>>,
+}
+
+impl ChatOps {
+    /// Create a handler that ignores `bot_user`'s comments and anything before `since`
+    pub fn new(bot_user: String, since: DateTime<Utc>) -> Self {
+        Self { bot_user, last_poll: since, handled: HashMap::new() }
+    }
+    
+    /// Run the commands posted since the last poll, replying to each
+    ///
+    /// The queue is only locked while a command runs. Returns how many
+    /// commands were handled.
+    pub async fn poll(&mut self, adapter: &dyn VcsAdapter, queue: &Mutex<QueueManager>, notifier: &Notifier, now: DateTime<Utc>) -> Result<usize> {
+        let since = self.last_poll - Duration::seconds(POLL_OVERLAP_SECONDS);
+        let reviews = adapter.list_open_reviews().await?;
+        let mut handled = 0;
+        
+        for review in reviews.iter().filter(|r| r.updated_at >= since) {
+            let comments = match adapter.list_comments(&review.id, Some(since)).await {
+                Ok(comments) => comments,
+                Err(e) => {
+                    warn!("Failed to list comments on review {}: {}", review.id, e);
+                    continue;
+                }
+            };
+            
+            for comment in comments {
+                if comment.created_at < since
+                    || comment.author == self.bot_user
+                    || self.handled.contains_key(&comment.id)
+                {
+                    continue;
+                }
+                let Some(parsed) = ChatCommand::parse(&comment.body) else {
+                    continue;
+                };
+                self.handled.insert(comment.id.clone(), comment.created_at);
+                handled += 1;
+                
+                let notification = match parsed {
+                    Ok(command) => match Self::run(&mut *queue.lock().await, review, &comment.author, command).await {
+                        Ok(reply) => NotificationType::CommandDone(reply),
+                        Err(e) => NotificationType::CommandFailed { command: command.to_string(), reason: e.to_string() },
+                    },
+                    Err(reason) => NotificationType::CommandFailed { command: COMMAND_PREFIX.to_string(), reason },
+                };
+                if let Err(e) = notifier.notify(&review.id, notification).await {
+                    warn!("Failed to reply to command on review {}: {}", review.id, e);
+                }
+            }
+        }
+        
+        self.last_poll = now;
+        self.handled.retain(|_, created_at| *created_at >= now - Duration::seconds(POLL_OVERLAP_SECONDS));
+        Ok(handled)
+    }
+    
+    /// Check `author` may run `command` on `review`, then run it
+    ///
+    /// Returns the reply to post.
+    pub async fn run(queue: &mut QueueManager, review: &ReviewSummary, author: &str, command: ChatCommand) -> Result<String> {
+        info!("{} ran `{}` on review {}", author, command, review.id);
+        
+        let roles = queue.roles().cloned().unwrap_or_default();
+        let own_review = review.author.eq_ignore_ascii_case(author);
+        if !(own_review && command.allowed_for_author()) {
+            roles.check(queue.adapter(), author, command.action()).await?;
+        }
+        // Only the author may release a hold without overriding it
+        if !own_review && command.releases_hold() && queue.is_held(&review.id) {
+            roles.check(queue.adapter(), author, Action::Override).await?;
+        }
+        
+        Self::execute(queue, &review.id, command).await
+    }
+    
+    /// Run a command against the queue, returning the reply to post
+    async fn execute(queue: &mut QueueManager, review_id: &ReviewId, command: ChatCommand) -> Result<String> {
+        match command {
+            ChatCommand::Merge => {
+                queue.release(review_id);
+                if queue.get_queue_position(review_id).is_some() {
+                    return Ok(format!("Already in the merge queue at {}", Self::place(queue, review_id)));
+                }
+                match queue.add_to_queue(review_id.clone()).await {
+                    Ok(()) => Ok(Self::queued(queue, review_id)),
+                    Err(e) => Ok(format!("Not ready to merge yet ({}). It'll be queued once it is.", e)),
+                }
+            },
+            ChatCommand::Queue => {
+                queue.release(review_id);
+                queue.add_to_queue(review_id.clone()).await?;
+                Ok(Self::queued(queue, review_id))
+            },
+            ChatCommand::Dequeue => {
+                if queue.get_queue_position(review_id).is_none() {
+                    return Err(GarryError::Other("it isn't in the merge queue".to_string()));
+                }
+                queue.hold(review_id);
+                Ok(format!("Removed from the merge queue. Run `{}` to add it back.", ChatCommand::Queue))
+            },
+            ChatCommand::Retry => {
+                queue.retry(review_id).await?;
+                Ok(Self::queued(queue, review_id))
+            },
+            ChatCommand::Priority(priority) => {
+                queue.set_priority(review_id, priority)?;
+                let name = match priority {
+                    Priority::High => "high",
+                    Priority::Normal => "normal",
+                };
+                Ok(format!("Priority set to {}; now in the merge queue at {}", name, Self::place(queue, review_id)))
+            },
+            ChatCommand::Hold => {
+                let was_queued = queue.hold(review_id);
+                let note = if was_queued { "Removed from the merge queue and held" } else { "Held" };
+                Ok(format!("{}. Run `{}` to release it.", note, ChatCommand::Merge))
+            },
+        }
+    }
+    
+    /// Reply for a review that was just queued
+    fn queued(queue: &QueueManager, review_id: &ReviewId) -> String {
+        format!("Added to the merge queue at {}", Self::place(queue, review_id))
+    }
+    
+    /// A queued review's place among reviews into the same target,
+    /// e.g. "position 2 of 3 into main"
+    fn place(queue: &QueueManager, review_id: &ReviewId) -> String {
+        let target = queue.get_queue().iter()
+            .find(|e| &e.review_id == review_id)
+            .map_or("", |e| e.target.as_str());
+        let (position, length) = queue.target_position(review_id).unwrap_or_default();
+        format!("position {} of {} into {}", position + 1, length, target)
+    }
+}
@@ -1,31 +1,60 @@
 use crate::bot::adapter::VcsAdapter;
+use crate::bot::metrics;
 use crate::review::{ReviewId, CiStatus};
 use crate::utils::error::Result;
-use std::time::Duration;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 use tokio::time::sleep;
 use tracing::{info, debug};
 
 /// CI monitoring service
+///
+/// The monitor doesn't own an adapter; callers pass the one they talk to.
+/// How long CI took to finish, from the monitor's first check, is recorded
+/// in the `garry_ci_wait_seconds` metric.
 pub struct CiMonitor {
-    // We store a reference to the adapter but can't use trait objects directly
-    // In real implementation, this would be handled differently
+    /// Repository label for the metrics
+    repository: String,
+    /// When each review with unfinished CI was first checked
+    waiting_since: Mutex<HashMap<ReviewId, Instant>>,
 }
 
 impl CiMonitor {
     /// Create a new CI monitor
     pub fn new(_adapter: &dyn VcsAdapter) -> Self {
-        Self {}
+        Self { repository: String::new(), waiting_since: Mutex::new(HashMap::new()) }
+    }
+    
+    /// Label the monitor's metrics with `repository`
+    pub fn with_repository(mut self, repository: String) -> Self {
+        self.repository = repository;
+        self
     }
     
     /// Check CI status for a review
-    pub async fn check_ci_status(&self, review_id: &ReviewId) -> Result<CiStatus> {
+    pub async fn check_ci_status(&self, adapter: &dyn VcsAdapter, review_id: &ReviewId) -> Result<CiStatus> {
         debug!("Checking CI status for review {}", review_id);
-        // This will be called through the adapter in the queue manager
-        Ok(CiStatus::Pending)
+        let status = adapter.get_ci_status(review_id).await?;
+        
+        let mut waiting_since = self.waiting_since.lock().unwrap();
+        let result = match &status {
+            CiStatus::Success => "success",
+            CiStatus::Failed(_) => "failed",
+            CiStatus::Cancelled => "cancelled",
+            CiStatus::Pending | CiStatus::Running => {
+                waiting_since.entry(review_id.clone()).or_insert_with(Instant::now);
+                return Ok(status);
+            },
+        };
+        let waited = waiting_since.remove(review_id).map(|since| since.elapsed()).unwrap_or_default();
+        metrics::metrics().ci_wait.with_label_values(&[&self.repository, result]).observe(waited.as_secs_f64());
+        
+        Ok(status)
     }
     
     /// Wait for CI to complete with timeout
-    pub async fn wait_for_ci(&self, review_id: &ReviewId, timeout: Duration) -> Result<CiStatus> {
+    pub async fn wait_for_ci(&self, adapter: &dyn VcsAdapter, review_id: &ReviewId, timeout: Duration) -> Result<CiStatus> {
         info!("Waiting for CI to complete for review {} (timeout: {:?})", review_id, timeout);
         
         let start = std::time::Instant::now();
@@ -36,7 +65,7 @@ impl CiMonitor {
                 return Ok(CiStatus::Failed(vec!["CI timeout".to_string()]));
             }
             
-            let status = self.check_ci_status(review_id).await?;
+            let status = self.check_ci_status(adapter, review_id).await?;
             
             match status {
                 CiStatus::Success | CiStatus::Failed(_) | CiStatus::Cancelled => {
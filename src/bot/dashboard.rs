@@ -0,0 +1,212 @@
+use std::fmt::Write;
+use chrono::{DateTime, Utc};
+use crate::bot::digest::format_age;
+use crate::bot::queue::{QueueEvent, QueueSnapshot, QueueStatus};
+use crate::review::ReviewId;
+
+/// How many recent merges and failures the dashboard lists
+const RECENT_OUTCOMES: usize = 20;
+
+/// Styles inlined into every page, so the dashboard needs no external assets
+const STYLE: &str = "body{font-family:system-ui,sans-serif;margin:2em auto;max-width:60em;padding:0 1em;color:#222}\
+table{border-collapse:collapse;width:100%;margin-bottom:2em}\
+th,td{text-align:left;padding:.3em .6em;border-bottom:1px solid #ddd}\
+th{background:#f4f4f4}\
+.pending{color:#666}.testing{color:#b58900}.merging{color:#268bd2}.failed{color:#dc322f}.merged{color:#2e7d32}\
+.banner{background:#fff3cd;border:1px solid #e0c97f;padding:.5em 1em}\
+footer{color:#888;font-size:.9em}";
+
+/// Escape text for HTML
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Wrap `body` in a page that reloads itself every `refresh` seconds
+fn page(title: &str, body: &str, refresh: u64, now: DateTime<Utc>) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta http-equiv=\"refresh\" content=\"{refresh}\">\n<title>{title}</title>\n\
+         <style>{STYLE}</style>\n</head>\n<body>\n{body}\
+         <footer>Updated {updated}; refreshes every {refresh}s</footer>\n</body>\n</html>\n",
+        refresh = refresh,
+        title = escape(title),
+        body = body,
+        updated = now.format("%Y-%m-%d %H:%M:%S UTC"),
+    )
+}
+
+/// Link to a review's timeline page, under the dashboard's `base` path
+fn review_link(base: &str, review_id: &ReviewId) -> String {
+    let id = escape(review_id.as_str());
+    format!("<a href=\"{}/reviews/{}\">#{}</a>", escape(base), id, id)
+}
+
+/// Path of the dashboard's front page under `base`
+pub(crate) fn home(base: &str) -> &str {
+    if base.is_empty() { "/" } else { base }
+}
+
+/// Heading naming the queue's repository, if it has one
+fn heading(queue: &QueueSnapshot) -> String {
+    match queue.repository.as_str() {
+        "" => "Merge queue".to_string(),
+        repository => format!("Merge queue: {}", repository),
+    }
+}
+
+/// CSS class for a queue status
+fn status_class(status: &QueueStatus) -> &'static str {
+    match status {
+        QueueStatus::Pending => "pending",
+        QueueStatus::Testing => "testing",
+        QueueStatus::Merging => "merging",
+        QueueStatus::Failed(_) => "failed",
+    }
+}
+
+/// The dashboard's front page: the queue, held reviews, and recent merges and failures
+///
+/// Links point under `base` ("" at the root). `repositories` are listed for
+/// navigation when the bot manages several.
+pub fn render_queue(queue: &QueueSnapshot, base: &str, repositories: &[String], refresh: u64, now: DateTime<Utc>) -> String {
+    let title = heading(queue);
+    let mut body = String::new();
+    if !repositories.is_empty() {
+        let links: Vec<_> = repositories.iter()
+            .map(|r| format!("<a href=\"/repos/{}\">{}</a>", escape(r), escape(r)))
+            .collect();
+        let _ = writeln!(body, "<nav>Repositories: {}</nav>", links.join(" | "));
+    }
+    let _ = writeln!(body, "<h1>{}</h1>", escape(&title));
+    if queue.paused {
+        body.push_str("<p class=\"banner\">The queue is paused: nothing is tested or merged until it's resumed.</p>\n");
+    }
+    
+    let entries = &queue.entries;
+    if entries.is_empty() {
+        body.push_str("<p>The queue is empty.</p>\n");
+    } else {
+        body.push_str("<table>\n<tr><th>#</th><th>Review</th><th>Author</th><th>Branch</th><th>Target</th><th>Status</th><th>Priority</th><th>Queued</th><th>ETA</th></tr>\n");
+        for (position, entry) in entries.iter().enumerate() {
+            let eta = queue.eta(position).map(format_age).unwrap_or_else(|| "-".to_string());
+            let _ = writeln!(
+                body,
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td class=\"{}\">{}</td><td>{:?}</td><td>{} ago</td><td>{}</td></tr>",
+                position + 1,
+                review_link(base, &entry.review_id),
+                escape(&entry.author),
+                escape(&entry.branch),
+                escape(&entry.target),
+                status_class(&entry.status),
+                escape(&entry.status.description()),
+                entry.priority,
+                format_age(now - entry.added_at),
+                eta,
+            );
+        }
+        body.push_str("</table>\n");
+    }
+    
+    if !queue.held.is_empty() {
+        let links: Vec<_> = queue.held.iter().map(|r| review_link(base, r)).collect();
+        let _ = writeln!(body, "<p>Held out of the queue: {}</p>", links.join(", "));
+    }
+    
+    body.push_str("<h2>Recent merges and failures</h2>\n");
+    let outcomes = &queue.outcomes;
+    if outcomes.is_empty() {
+        body.push_str("<p>Nothing has merged or failed yet.</p>\n");
+    } else {
+        body.push_str("<table>\n<tr><th>Review</th><th>Result</th><th>When</th></tr>\n");
+        for outcome in outcomes.iter().rev().take(RECENT_OUTCOMES) {
+            let (class, result) = match &outcome.failure {
+                Some(reason) => ("failed", format!("failed: {}", escape(reason))),
+                None => ("merged", "merged".to_string()),
+            };
+            let _ = writeln!(
+                body,
+                "<tr><td>{}</td><td class=\"{}\">{}</td><td>{} ago</td></tr>",
+                review_link(base, &outcome.review_id),
+                class,
+                result,
+                format_age(now - outcome.at),
+            );
+        }
+        body.push_str("</table>\n");
+    }
+    
+    page(&title, &body, refresh, now)
+}
+
+/// Append a timeline of `events` to `body`
+fn timeline(body: &mut String, heading: &str, events: &[QueueEvent], now: DateTime<Utc>) {
+    let _ = writeln!(body, "<h2>{}</h2>\n<table>\n<tr><th>When</th><th>What</th></tr>", escape(heading));
+    for event in events {
+        let _ = writeln!(
+            body,
+            "<tr><td title=\"{}\">{} ago</td><td>{}</td></tr>",
+            event.at.format("%Y-%m-%d %H:%M:%S UTC"),
+            format_age(now - event.at),
+            escape(&event.description),
+        );
+    }
+    body.push_str("</table>\n");
+}
+
+/// A review's timeline page, or `None` if the queue doesn't know the review
+pub fn render_review(queue: &QueueSnapshot, base: &str, review_id: &ReviewId, refresh: u64, now: DateTime<Utc>) -> Option<String> {
+    let position = queue.position(review_id);
+    let held = queue.is_held(review_id);
+    let outcomes: Vec<_> = queue.outcomes.iter().filter(|o| &o.review_id == review_id).collect();
+    if position.is_none() && !held && outcomes.is_empty() {
+        return None;
+    }
+    
+    let id = escape(review_id.as_str());
+    let mut body = format!(
+        "<p><a href=\"{}\">&larr; {}</a></p>\n<h1>Review #{}</h1>\n<p><a href=\"{}\">Open the review</a></p>\n",
+        escape(home(base)),
+        escape(&heading(queue)),
+        id,
+        escape(queue.review_url(review_id)),
+    );
+    match position {
+        Some(position) => {
+            let entry = &queue.entries[position];
+            let (position, length) = queue.target_position(review_id).unwrap_or((position, queue.entries.len()));
+            let _ = writeln!(
+                body,
+                "<p>Position {} of {} into {}: <span class=\"{}\">{}</span></p>",
+                position + 1,
+                length,
+                escape(&entry.target),
+                status_class(&entry.status),
+                escape(&entry.status.description()),
+            );
+            timeline(&mut body, "Current run", &entry.history, now);
+        },
+        None if held => body.push_str("<p>Held out of the queue.</p>\n"),
+        None => body.push_str("<p>Not in the queue.</p>\n"),
+    }
+    
+    for outcome in outcomes.iter().rev() {
+        let heading = match &outcome.failure {
+            Some(reason) => format!("Failed {} into {}: {}", outcome.at.format("%Y-%m-%d %H:%M UTC"), outcome.target, reason),
+            None => format!("Merged into {} {}", outcome.target, outcome.at.format("%Y-%m-%d %H:%M UTC")),
+        };
+        timeline(&mut body, &heading, &outcome.history, now);
+    }
+    
+    Some(page(&format!("Review #{}", review_id), &body, refresh, now))
+}
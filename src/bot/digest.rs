@@ -0,0 +1,330 @@
+use std::collections::BTreeMap;
+use chrono::{DateTime, Datelike, Duration, NaiveTime, TimeZone, Utc};
+use serde::Serialize;
+use crate::bot::adapter::VcsAdapter;
+use crate::bot::notifier::Notifier;
+use crate::bot::queue::QueueSnapshot;
+use crate::review::{ReviewId, ReviewState, ReviewSummary};
+use crate::utils::config::{DigestConfig, DigestReportConfig, DigestSchedule};
+use crate::utils::error::Result;
+use tracing::{debug, info, warn};
+
+/// Name of the report sent when none are configured
+pub const DEFAULT_REPORT: &str = "Review";
+
+/// A review listed in a digest
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DigestReview {
+    pub id: ReviewId,
+    /// Empty if the review is no longer open
+    pub title: String,
+    /// Empty if the review is no longer open
+    pub author: String,
+    pub url: String,
+    /// When the review was opened, queued or failed, depending on the section
+    pub since: DateTime<Utc>,
+    /// Queue state or failure reason
+    pub detail: Option<String>,
+}
+
+/// Reviews that need someone's attention
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Digest {
+    pub generated_at: DateTime<Utc>,
+    /// Open reviews by the requested reviewer they're waiting on
+    pub waiting_on: BTreeMap<String, Vec<DigestReview>>,
+    /// Reviews in the merge queue for too long (`None` without the queue)
+    pub stuck: Option<Vec<DigestReview>>,
+    /// Merges that failed during the digest's period, newest first (`None` without the queue)
+    pub failed_merges: Option<Vec<DigestReview>>,
+    /// Open reviews nobody but their author has voted on, oldest first
+    pub unreviewed: Vec<DigestReview>,
+}
+
+impl Digest {
+    /// Only what concerns `members`: reviews waiting on them and reviews they wrote
+    pub fn for_members(&self, members: &[String]) -> Digest {
+        let is_member = |user: &str| members.iter().any(|m| m.eq_ignore_ascii_case(user));
+        let authored = |reviews: &Vec<DigestReview>| -> Vec<DigestReview> {
+            reviews.iter().filter(|r| is_member(&r.author)).cloned().collect()
+        };
+        Digest {
+            generated_at: self.generated_at,
+            waiting_on: self.waiting_on.iter()
+                .filter(|(reviewer, _)| is_member(reviewer))
+                .map(|(reviewer, reviews)| (reviewer.clone(), reviews.clone()))
+                .collect(),
+            stuck: self.stuck.as_ref().map(authored),
+            failed_merges: self.failed_merges.as_ref().map(authored),
+            unreviewed: authored(&self.unreviewed),
+        }
+    }
+    
+    /// Whether nothing needs attention
+    pub fn is_empty(&self) -> bool {
+        self.waiting_on.is_empty()
+            && self.stuck.as_ref().is_none_or(Vec::is_empty)
+            && self.failed_merges.as_ref().is_none_or(Vec::is_empty)
+            && self.unreviewed.is_empty()
+    }
+    
+    /// Render the digest as Markdown, titled with the report name
+    ///
+    /// Queue sections are left out when the digest was collected without the queue.
+    pub fn to_markdown(&self, report: &str) -> String {
+        let now = self.generated_at;
+        let mut out = format!("**{} digest** for {}\n", report, now.format("%Y-%m-%d"));
+        
+        out.push_str("\n**Waiting on reviewers**\n");
+        if self.waiting_on.is_empty() {
+            out.push_str("- none\n");
+        }
+        for (reviewer, reviews) in &self.waiting_on {
+            out.push_str(&format!("- @{} ({}):\n", reviewer, reviews.len()));
+            for review in reviews {
+                out.push_str(&format!("  {}\n", list_item(review, "opened", now)));
+            }
+        }
+        
+        if let Some(stuck) = &self.stuck {
+            section(&mut out, "Stuck in the merge queue", stuck, "queued", now);
+        }
+        if let Some(failed) = &self.failed_merges {
+            section(&mut out, "Failed merges", failed, "failed", now);
+        }
+        section(&mut out, "Oldest unreviewed", &self.unreviewed, "opened", now);
+        
+        out
+    }
+}
+
+/// Append a section listing `reviews`
+fn section(out: &mut String, heading: &str, reviews: &[DigestReview], what: &str, now: DateTime<Utc>) {
+    out.push_str(&format!("\n**{}**\n", heading));
+    if reviews.is_empty() {
+        out.push_str("- none\n");
+    }
+    for review in reviews {
+        out.push_str(&format!("{}\n", list_item(review, what, now)));
+    }
+}
+
+/// One review as a Markdown list item, e.g. "- [#12](url) Title by @bob, opened 3d ago"
+fn list_item(review: &DigestReview, what: &str, now: DateTime<Utc>) -> String {
+    let mut item = format!("- [#{}]({})", review.id, review.url);
+    if !review.title.is_empty() {
+        item.push_str(&format!(" {}", review.title));
+    }
+    if !review.author.is_empty() {
+        item.push_str(&format!(" by @{}", review.author));
+    }
+    item.push_str(&format!(", {} {} ago", what, format_age(now - review.since)));
+    if let Some(detail) = &review.detail {
+        item.push_str(&format!(": {}", detail));
+    }
+    item
+}
+
+/// Compact age, e.g. "3d", "5h" or "12m"
+pub(crate) fn format_age(age: Duration) -> String {
+    if age.num_days() >= 1 {
+        format!("{}d", age.num_days())
+    } else if age.num_hours() >= 1 {
+        format!("{}h", age.num_hours())
+    } else {
+        format!("{}m", age.num_minutes().max(0))
+    }
+}
+
+/// How far back a digest looks for failed merges
+fn period(schedule: DigestSchedule) -> Duration {
+    match schedule {
+        DigestSchedule::Weekly => Duration::days(7),
+        DigestSchedule::Daily | DigestSchedule::Off => Duration::days(1),
+    }
+}
+
+/// Expand teams ("org/team") in `members` to their users
+pub async fn resolve_members(adapter: &dyn VcsAdapter, members: &[String]) -> Vec<String> {
+    let mut users = Vec::new();
+    for member in members {
+        if !member.contains('/') {
+            users.push(member.clone());
+            continue;
+        }
+        match adapter.get_team_members(member).await {
+            Ok(logins) => users.extend(logins),
+            Err(e) => warn!("Could not resolve members of team {}: {}", member, e),
+        }
+    }
+    users
+}
+
+/// Gathers digests from the platform and, in the bot, the merge queue
+pub struct DigestCollector {
+    period: Duration,
+    stuck_after: Duration,
+    limit: usize,
+}
+
+impl DigestCollector {
+    /// Create a collector from the `[digest]` config
+    pub fn new(config: &DigestConfig) -> Self {
+        Self {
+            period: period(config.schedule),
+            stuck_after: Duration::hours(config.stuck_after_hours as i64),
+            limit: config.limit,
+        }
+    }
+    
+    /// Collect a digest of every open review
+    ///
+    /// Without `queue` (e.g. from the CLI), the queue sections are `None`.
+    pub async fn collect(&self, adapter: &dyn VcsAdapter, queue: Option<&QueueSnapshot>, now: DateTime<Utc>) -> Result<Digest> {
+        let reviews = adapter.list_open_reviews().await?;
+        
+        let mut waiting_on: BTreeMap<String, Vec<DigestReview>> = BTreeMap::new();
+        let mut unreviewed = Vec::new();
+        for summary in &reviews {
+            for reviewer in &summary.requested_reviewers {
+                waiting_on.entry(reviewer.clone()).or_default().push(listed(summary, summary.created_at, None));
+            }
+            
+            let status = match adapter.get_review_status(&summary.id).await {
+                Ok(status) => status,
+                Err(e) => {
+                    warn!("Failed to get status for review {}: {}", summary.id, e);
+                    continue;
+                }
+            };
+            if status.state != ReviewState::Draft && status.votes.iter().all(|v| v.reviewer == status.author) {
+                unreviewed.push(listed(summary, summary.created_at, None));
+            }
+        }
+        for reviews in waiting_on.values_mut() {
+            reviews.sort_by_key(|r| r.since);
+            reviews.truncate(self.limit);
+        }
+        unreviewed.sort_by_key(|r| r.since);
+        unreviewed.truncate(self.limit);
+        
+        let lookup = |review_id: &ReviewId, since: DateTime<Utc>, detail: String| {
+            match reviews.iter().find(|r| &r.id == review_id) {
+                Some(summary) => listed(summary, since, Some(detail)),
+                None => DigestReview {
+                    id: review_id.clone(),
+                    title: String::new(),
+                    author: String::new(),
+                    url: adapter.review_url(review_id),
+                    since,
+                    detail: Some(detail),
+                },
+            }
+        };
+        let (stuck, failed_merges) = match queue {
+            Some(queue) => {
+                let mut stuck: Vec<_> = queue.entries.iter()
+                    .filter(|e| now - e.added_at >= self.stuck_after)
+                    .map(|e| lookup(&e.review_id, e.added_at, e.status.description()))
+                    .collect();
+                stuck.truncate(self.limit);
+                let mut failed: Vec<_> = queue.outcomes.iter().rev()
+                    .filter(|o| now - o.at <= self.period)
+                    .filter_map(|o| o.failure.as_ref().map(|reason| lookup(&o.review_id, o.at, reason.clone())))
+                    .collect();
+                failed.truncate(self.limit);
+                (Some(stuck), Some(failed))
+            },
+            None => (None, None),
+        };
+        
+        Ok(Digest { generated_at: now, waiting_on, stuck, failed_merges, unreviewed })
+    }
+}
+
+/// List an open review
+fn listed(summary: &ReviewSummary, since: DateTime<Utc>, detail: Option<String>) -> DigestReview {
+    DigestReview {
+        id: summary.id.clone(),
+        title: summary.title.clone(),
+        author: summary.author.clone(),
+        url: summary.url.clone(),
+        since,
+        detail,
+    }
+}
+
+/// Sends the configured digest reports on the daily or weekly schedule
+///
+/// The first digest goes out at the first scheduled time after the bot
+/// starts, so restarting the bot doesn't send a digest twice.
+pub struct DigestScheduler {
+    config: DigestConfig,
+    collector: DigestCollector,
+    last_run: DateTime<Utc>,
+}
+
+impl DigestScheduler {
+    /// Create a scheduler from the `[digest]` config, or `None` if digests are off
+    pub fn new(config: &DigestConfig, now: DateTime<Utc>) -> Option<Self> {
+        if config.schedule == DigestSchedule::Off {
+            return None;
+        }
+        Some(Self { config: config.clone(), collector: DigestCollector::new(config), last_run: now })
+    }
+    
+    /// Most recent scheduled time at or before `now`
+    fn last_slot(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        let time = NaiveTime::from_hms_opt(self.config.hour, 0, 0).unwrap_or(NaiveTime::MIN);
+        let mut slot = Utc.from_utc_datetime(&now.date_naive().and_time(time));
+        if slot > now {
+            slot -= Duration::days(1);
+        }
+        if self.config.schedule == DigestSchedule::Weekly {
+            while slot.weekday() != self.config.weekday {
+                slot -= Duration::days(1);
+            }
+        }
+        slot
+    }
+    
+    /// Whether a scheduled time has passed since the last digest
+    pub fn is_due(&self, now: DateTime<Utc>) -> bool {
+        self.last_slot(now) > self.last_run
+    }
+    
+    /// Send the digest reports if they're due
+    ///
+    /// Reports with nothing in them aren't sent. Returns how many were sent.
+    pub async fn poll(&mut self, adapter: &dyn VcsAdapter, queue: &QueueSnapshot, notifier: &Notifier, now: DateTime<Utc>) -> Result<usize> {
+        if !self.is_due(now) {
+            return Ok(0);
+        }
+        // A failed collect is retried on the next poll rather than skipped until the next slot
+        let digest = self.collector.collect(adapter, Some(queue), now).await?;
+        self.last_run = now;
+        let reports = if self.config.reports.is_empty() {
+            vec![DigestReportConfig { name: DEFAULT_REPORT.to_string(), members: vec![] }]
+        } else {
+            self.config.reports.clone()
+        };
+        
+        let mut sent = 0;
+        for report in reports {
+            let digest = if report.members.is_empty() {
+                digest.clone()
+            } else {
+                digest.for_members(&resolve_members(adapter, &report.members).await)
+            };
+            if digest.is_empty() {
+                debug!("Nothing to report in the {} digest", report.name);
+                continue;
+            }
+            if notifier.send_digest(&report.name, digest.to_markdown(&report.name)).await > 0 {
+                sent += 1;
+            }
+        }
+        info!("Sent {} digest report(s)", sent);
+        Ok(sent)
+    }
+}
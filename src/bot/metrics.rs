@@ -0,0 +1,102 @@
+use std::sync::OnceLock;
+use prometheus::{Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGaugeVec, Opts, Registry, TextEncoder};
+
+/// Queue entry states the queue length is reported for
+pub const QUEUE_STATES: &[&str] = &["pending", "testing", "merging", "failed"];
+
+/// Prometheus metrics exported by the bot on `/metrics`
+///
+/// Metrics are process-wide, so the queue, CI monitor and adapters record
+/// them without being handed a registry.
+pub struct Metrics {
+    registry: Registry,
+    /// Queue entries by repository and state
+    pub queue_length: IntGaugeVec,
+    /// Time from being queued to merging or failing, by repository and result
+    pub queue_time: HistogramVec,
+    /// Finished queue entries by repository and result: "success", "ci_failed", "merge_failed" or "not_mergeable"
+    pub merges: IntCounterVec,
+    /// Time from the queue first checking CI to it finishing, by repository and result
+    pub ci_wait: HistogramVec,
+    /// Forge API requests by platform, endpoint and HTTP status
+    pub adapter_requests: IntCounterVec,
+    /// Forge API latency by platform and endpoint
+    pub adapter_latency: HistogramVec,
+    /// Requests left in the forge's rate limit window, by platform
+    pub rate_limit_remaining: IntGaugeVec,
+    /// When each repository's bot loop last completed a pass, as a Unix timestamp
+    pub last_pass: IntGaugeVec,
+}
+
+/// The bot's metrics
+pub fn metrics() -> &'static Metrics {
+    static METRICS: OnceLock<Metrics> = OnceLock::new();
+    METRICS.get_or_init(Metrics::new)
+}
+
+impl Metrics {
+    fn new() -> Self {
+        let registry = Registry::new();
+        let metrics = Self {
+            queue_length: IntGaugeVec::new(
+                Opts::new("garry_queue_length", "Merge queue entries by state"),
+                &["repository", "state"],
+            ).unwrap(),
+            queue_time: HistogramVec::new(
+                HistogramOpts::new("garry_queue_time_seconds", "Time from queueing to merging or failing")
+                    .buckets(vec![60.0, 300.0, 900.0, 1800.0, 3600.0, 7200.0, 14400.0, 28800.0, 86400.0]),
+                &["repository", "result"],
+            ).unwrap(),
+            merges: IntCounterVec::new(
+                Opts::new("garry_merges_total", "Queue entries that merged or failed, by result"),
+                &["repository", "result"],
+            ).unwrap(),
+            ci_wait: HistogramVec::new(
+                HistogramOpts::new("garry_ci_wait_seconds", "Time the queue waited for CI to finish")
+                    .buckets(vec![10.0, 30.0, 60.0, 120.0, 300.0, 600.0, 1200.0, 1800.0, 3600.0]),
+                &["repository", "result"],
+            ).unwrap(),
+            adapter_requests: IntCounterVec::new(
+                Opts::new("garry_adapter_requests_total", "Forge API requests by endpoint and status"),
+                &["platform", "endpoint", "status"],
+            ).unwrap(),
+            adapter_latency: HistogramVec::new(
+                HistogramOpts::new("garry_adapter_request_duration_seconds", "Forge API request latency"),
+                &["platform", "endpoint"],
+            ).unwrap(),
+            rate_limit_remaining: IntGaugeVec::new(
+                Opts::new("garry_adapter_rate_limit_remaining", "Requests left in the forge's rate limit window"),
+                &["platform"],
+            ).unwrap(),
+            last_pass: IntGaugeVec::new(
+                Opts::new("garry_last_pass_timestamp_seconds", "When the bot loop last completed a pass"),
+                &["repository"],
+            ).unwrap(),
+            registry,
+        };
+        
+        metrics.registry.register(Box::new(metrics.queue_length.clone())).unwrap();
+        metrics.registry.register(Box::new(metrics.queue_time.clone())).unwrap();
+        metrics.registry.register(Box::new(metrics.merges.clone())).unwrap();
+        metrics.registry.register(Box::new(metrics.ci_wait.clone())).unwrap();
+        metrics.registry.register(Box::new(metrics.adapter_requests.clone())).unwrap();
+        metrics.registry.register(Box::new(metrics.adapter_latency.clone())).unwrap();
+        metrics.registry.register(Box::new(metrics.rate_limit_remaining.clone())).unwrap();
+        metrics.registry.register(Box::new(metrics.last_pass.clone())).unwrap();
+        metrics
+    }
+    
+    /// Render every metric in the Prometheus text format
+    pub fn render(&self) -> String {
+        let mut buffer = Vec::new();
+        TextEncoder::new()
+            .encode(&self.registry.gather(), &mut buffer)
+            .expect("metrics encode to a Vec");
+        String::from_utf8(buffer).expect("the text format is UTF-8")
+    }
+}
+
+/// A duration in seconds, as metrics record them
+pub fn seconds(duration: chrono::Duration) -> f64 {
+    duration.num_milliseconds() as f64 / 1000.0
+}
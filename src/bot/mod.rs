@@ -2,6 +2,16 @@ pub mod adapter;
 pub mod adapters;
 pub mod queue;
 pub mod ci;
+pub mod channel;
+pub mod channels;
 pub mod notifier;
+pub mod stale;
+pub mod digest;
+pub mod server;
+pub mod dashboard;
+pub mod metrics;
+pub mod templates;
+pub mod chatops;
+pub mod backport;
 
 pub use adapter::{VcsAdapter, AdapterRegistry};
@@ -1,7 +1,18 @@
 use crate::bot::adapter::VcsAdapter;
-use crate::review::ReviewId;
+use crate::bot::channel::{ChannelRoute, Notification, NotificationChannel};
+use crate::bot::channels;
+use crate::bot::queue::QueueStatus;
+use crate::bot::templates::MessageTemplates;
+use crate::review::{CiStatus, ReviewId};
+use crate::utils::config::Config;
 use crate::utils::error::Result;
-use tracing::info;
+use chrono::Duration;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tracing::{debug, info, warn};
+
+/// Hidden marker that identifies the bot's status comment on a review
+pub const STATUS_MARKER: &str = "<!-- garry-status -->";
 
 /// Type of notification to send
 #[derive(Debug, Clone)]
@@ -11,42 +22,392 @@ pub enum NotificationType {
     CiFailed(Vec<String>),
     QueuePosition(usize),
     ReviewRequiresUpdate,
+    /// A `/garry` command ran; the reply says what it did
+    CommandDone(String),
+    /// A `/garry` command was refused or failed
+    CommandFailed { command: String, reason: String },
+    /// A scheduled digest report, by report name
+    Digest(String),
+}
+
+impl NotificationType {
+    /// Event name used to route notifications to channels
+    pub fn event(&self) -> &'static str {
+        match self {
+            NotificationType::MergeSuccess => "merge_success",
+            NotificationType::MergeConflict(_) => "merge_conflict",
+            NotificationType::CiFailed(_) => "ci_failed",
+            NotificationType::QueuePosition(_) => "queue_position",
+            NotificationType::ReviewRequiresUpdate => "review_requires_update",
+            NotificationType::CommandDone(_) => "command_done",
+            NotificationType::CommandFailed { .. } => "command_failed",
+            NotificationType::Digest(_) => "digest",
+        }
+    }
+    
+    /// Short description of the event
+    pub fn title(&self) -> &'static str {
+        match self {
+            NotificationType::MergeSuccess => "merged",
+            NotificationType::MergeConflict(_) => "merge conflict",
+            NotificationType::CiFailed(_) => "CI failed",
+            NotificationType::QueuePosition(_) => "queued",
+            NotificationType::ReviewRequiresUpdate => "needs updates",
+            NotificationType::CommandDone(_) => "command ran",
+            NotificationType::CommandFailed { .. } => "command failed",
+            NotificationType::Digest(_) => "digest",
+        }
+    }
+    
+    /// Template variables that come with the event
+    pub fn variables(&self) -> Vec<(&'static str, String)> {
+        match self {
+            NotificationType::MergeConflict(details) => vec![("details", details.clone())],
+            NotificationType::CiFailed(failures) => vec![("checks", failures.join("\n"))],
+            NotificationType::QueuePosition(position) => vec![("position", (position + 1).to_string())],
+            NotificationType::CommandDone(reply) => vec![("reply", reply.clone())],
+            NotificationType::CommandFailed { command, reason } => {
+                vec![("command", command.clone()), ("reason", reason.clone())]
+            },
+            NotificationType::Digest(report) => vec![("report", report.clone())],
+            NotificationType::MergeSuccess | NotificationType::ReviewRequiresUpdate => vec![],
+        }
+    }
+    
+    /// Whether the event ends the review's time in the queue
+    ///
+    /// Only these (and command replies) get a new comment; progress is
+    /// shown by editing the status comment.
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            NotificationType::MergeSuccess
+                | NotificationType::MergeConflict(_)
+                | NotificationType::CiFailed(_)
+                | NotificationType::ReviewRequiresUpdate
+        )
+    }
+}
+
+/// What a review's status comment shows while it's in the merge queue
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueueReport {
+    /// Zero-based position in the target branch's queue
+    pub position: usize,
+    pub queue_length: usize,
+    /// Branch the review merges into
+    pub target: String,
+    pub state: QueueStatus,
+    pub ci_status: CiStatus,
+    /// Estimated time until the review merges, if there's enough history
+    pub eta: Option<Duration>,
+}
+
+impl QueueReport {
+    /// Template variables for the status comment
+    pub fn variables(&self) -> Vec<(&'static str, String)> {
+        let ci = match &self.ci_status {
+            CiStatus::Pending => "pending".to_string(),
+            CiStatus::Running => "running".to_string(),
+            CiStatus::Success => "passed".to_string(),
+            CiStatus::Failed(checks) => format!("failed ({})", checks.join(", ")),
+            CiStatus::Cancelled => "cancelled".to_string(),
+        };
+        let eta = match self.eta {
+            Some(eta) if eta.num_minutes() >= 60 => format!("~{}h {}m", eta.num_hours(), eta.num_minutes() % 60),
+            Some(eta) => format!("~{}m", eta.num_minutes().max(1)),
+            None => "unknown".to_string(),
+        };
+        vec![
+            ("position", (self.position + 1).to_string()),
+            ("queue_length", self.queue_length.to_string()),
+            ("target", self.target.clone()),
+            ("state", self.state.description()),
+            ("ci", ci),
+            ("eta", eta),
+        ]
+    }
+}
+
+/// A status comment the notifier has posted
+#[derive(Debug, Clone)]
+struct StatusComment {
+    id: String,
+    body: String,
 }
 
 /// Notification service
+///
+/// Posts notifications as review comments and sends them to every channel
+/// whose route matches.
 pub struct Notifier {
     adapter: Box<dyn VcsAdapter>,
+    repository: String,
+    main_branch: String,
+    templates: MessageTemplates,
+    review_comments: bool,
+    channels: Vec<(ChannelRoute, Box<dyn NotificationChannel>)>,
+    /// Status comments by review, so unchanged statuses aren't re-sent
+    status_comments: Mutex<HashMap<ReviewId, StatusComment>>,
+    /// Last terminal message sent for each review, to drop repeats
+    last_terminal: Mutex<HashMap<ReviewId, String>>,
 }
 
 impl Notifier {
-    /// Create a new notifier
+    /// Create a notifier that only posts review comments
     pub fn new(adapter: Box<dyn VcsAdapter>) -> Self {
-        Self { adapter }
+        Self {
+            adapter,
+            repository: String::new(),
+            main_branch: "main".to_string(),
+            templates: MessageTemplates::default(),
+            review_comments: true,
+            channels: vec![],
+            status_comments: Mutex::new(HashMap::new()),
+            last_terminal: Mutex::new(HashMap::new()),
+        }
+    }
+    
+    /// Create a notifier with the channels from `[notifications]`
+    pub fn from_config(adapter: Box<dyn VcsAdapter>, config: &Config) -> Result<Self> {
+        let templates = match &config.notifications.templates {
+            Some(path) => MessageTemplates::load(path, &config.notifications.locale)?,
+            None => MessageTemplates::new(&config.notifications.locale),
+        };
+        let mut notifier = Self::new(adapter)
+            .with_repository(config.vcs.repository.clone())
+            .with_main_branch(config.bot.main_branch.clone())
+            .with_templates(templates);
+        notifier.review_comments = config.notifications.review_comments;
+        for channel in &config.notifications.channels {
+            notifier = notifier.with_channel(ChannelRoute::from(channel), channels::create_channel(channel)?);
+        }
+        Ok(notifier)
+    }
+    
+    /// Set the repository notifications are about, used for routing
+    pub fn with_repository(mut self, repository: String) -> Self {
+        self.repository = repository;
+        self
+    }
+    
+    /// Set the branch reviews merge into, for messages
+    pub fn with_main_branch(mut self, main_branch: String) -> Self {
+        self.main_branch = main_branch;
+        self
+    }
+    
+    /// Render messages with `templates` instead of the built-in ones
+    pub fn with_templates(mut self, templates: MessageTemplates) -> Self {
+        self.templates = templates;
+        self
+    }
+    
+    /// Also send notifications matching `route` to `channel`
+    pub fn with_channel(mut self, route: ChannelRoute, channel: Box<dyn NotificationChannel>) -> Self {
+        self.channels.push((route, channel));
+        self
     }
     
-    /// Send a notification
+    /// Send a notification about a review into the main branch
+    ///
+    /// Queue positions edit the review's status comment. Terminal events
+    /// replace the status comment with a new comment and go to the channels,
+    /// unless the same message was the last one sent for the review.
     pub async fn notify(&self, review_id: &ReviewId, notification: NotificationType) -> Result<()> {
-        let message = match notification {
-            NotificationType::MergeSuccess => {
-                "✅ Your review has been successfully merged to main!".to_string()
-            },
-            NotificationType::MergeConflict(ref details) => {
-                format!("⚠️ Merge conflict detected:\n\n{}\n\nPlease rebase your branch and update the review.", details)
-            },
-            NotificationType::CiFailed(ref failures) => {
-                format!("❌ CI checks failed:\n\n{}\n\nPlease fix the issues and update the review.", failures.join("\n"))
-            },
-            NotificationType::QueuePosition(position) => {
-                format!("📋 Your review is in the merge queue at position {}", position + 1)
+        let main_branch = self.main_branch.clone();
+        self.notify_target(review_id, &main_branch, notification).await
+    }
+    
+    /// Send a notification about a review into `target`, like [`Notifier::notify`]
+    pub async fn notify_target(&self, review_id: &ReviewId, target: &str, notification: NotificationType) -> Result<()> {
+        let (subject, message) = self.render_for(review_id, target, &notification).await;
+        
+        if let NotificationType::QueuePosition(_) = notification {
+            return self.set_status_comment(review_id, &message).await;
+        }
+        
+        if notification.is_terminal() {
+            let mut last_terminal = self.last_terminal.lock().unwrap();
+            if last_terminal.get(review_id) == Some(&message) {
+                debug!("Skipping repeated notification to review {}: {:?}", review_id, notification);
+                return Ok(());
+            }
+            last_terminal.insert(review_id.clone(), message.clone());
+        }
+        
+        info!("Sending notification to review {}: {:?}", review_id, notification);
+        let posted = if self.review_comments {
+            if notification.is_terminal() {
+                if let Err(e) = self.clear_status(review_id).await {
+                    warn!("Failed to remove the status comment on review {}: {}", review_id, e);
+                }
+            }
+            self.adapter.post_comment(review_id, &message).await.map(|_| ())
+        } else {
+            Ok(())
+        };
+        
+        let notification = Notification {
+            repository: self.repository.clone(),
+            review_id: Some(review_id.clone()),
+            target: Some(target.to_string()),
+            kind: notification,
+            subject,
+            message,
+        };
+        self.send_to_channels(&notification).await;
+        
+        posted
+    }
+    
+    /// Send a digest report to the channels routed to it
+    ///
+    /// Returns how many channels it was delivered to.
+    pub async fn send_digest(&self, report: &str, message: String) -> usize {
+        let kind = NotificationType::Digest(report.to_string());
+        let mut variables: HashMap<&str, String> = HashMap::from([
+            ("repository", self.repository.clone()),
+            ("main_branch", self.main_branch.clone()),
+            ("event", kind.event().to_string()),
+            ("title", kind.title().to_string()),
+        ]);
+        variables.extend(kind.variables());
+        
+        info!("Sending {} digest", report);
+        let notification = Notification {
+            repository: self.repository.clone(),
+            review_id: None,
+            target: None,
+            subject: self.templates.render("digest_subject", &variables),
+            kind,
+            message,
+        };
+        self.send_to_channels(&notification).await
+    }
+    
+    /// Send a notification to every channel whose route matches
+    ///
+    /// A failing channel shouldn't stop the others. Returns how many
+    /// channels the notification was delivered to.
+    async fn send_to_channels(&self, notification: &Notification) -> usize {
+        let mut delivered = 0;
+        for (route, channel) in &self.channels {
+            if !route.matches(notification) {
+                continue;
+            }
+            match channel.send(notification).await {
+                Ok(()) => delivered += 1,
+                Err(e) => warn!("Failed to send {} notification to {}: {}", notification.kind.event(), channel.name(), e),
+            }
+        }
+        delivered
+    }
+    
+    /// Show a queued review's progress in its status comment
+    pub async fn update_status(&self, review_id: &ReviewId, report: &QueueReport) -> Result<()> {
+        let message = self.render_template(review_id, "status", report.variables()).await;
+        // A review back in the queue may end the same way again
+        self.last_terminal.lock().unwrap().remove(review_id);
+        self.set_status_comment(review_id, &message).await
+    }
+    
+    /// Delete a review's status comment, if the notifier posted one
+    pub async fn clear_status(&self, review_id: &ReviewId) -> Result<()> {
+        let comment = self.status_comments.lock().unwrap().remove(review_id);
+        if let Some(comment) = comment {
+            self.adapter.delete_comment(review_id, &comment.id).await?;
+        }
+        Ok(())
+    }
+    
+    /// Create or edit the status comment, doing nothing if it's unchanged
+    ///
+    /// The comment is found by its marker when the notifier hasn't posted it
+    /// yet, e.g. after the bot restarts.
+    async fn set_status_comment(&self, review_id: &ReviewId, message: &str) -> Result<()> {
+        if !self.review_comments {
+            return Ok(());
+        }
+        let body = format!("{}\n{}", STATUS_MARKER, message);
+        
+        let cached = self.status_comments.lock().unwrap().get(review_id).cloned();
+        let existing = match cached {
+            Some(comment) => Some(comment),
+            None => self.adapter.find_own_comments(review_id, STATUS_MARKER).await?
+                .into_iter()
+                .next()
+                .map(|c| StatusComment { id: c.id, body: c.body }),
+        };
+        
+        let id = match existing {
+            Some(comment) if comment.body == body => comment.id,
+            Some(comment) => {
+                debug!("Updating status comment on review {}", review_id);
+                self.adapter.update_comment(review_id, &comment.id, &body).await?;
+                comment.id
             },
-            NotificationType::ReviewRequiresUpdate => {
-                "🔄 Your review requires updates. Please address the feedback and push new commits.".to_string()
+            None => {
+                debug!("Posting status comment on review {}", review_id);
+                self.adapter.post_comment(review_id, &body).await?
             },
         };
         
-        info!("Sending notification to review {}: {:?}", review_id, notification);
-        self.adapter.post_comment(review_id, &message).await?;
-        
+        self.status_comments.lock().unwrap().insert(review_id.clone(), StatusComment { id, body });
         Ok(())
     }
+    
+    /// Render a notification's subject and message from the templates
+    pub async fn render(&self, review_id: &ReviewId, notification: &NotificationType) -> (String, String) {
+        self.render_for(review_id, &self.main_branch, notification).await
+    }
+    
+    /// Render a notification about a review into `target`
+    async fn render_for(&self, review_id: &ReviewId, target: &str, notification: &NotificationType) -> (String, String) {
+        let event = notification.event();
+        let mut variables = notification.variables();
+        variables.push(("target", target.to_string()));
+        variables.push(("event", event.to_string()));
+        variables.push(("title", notification.title().to_string()));
+        (
+            self.render_template(review_id, "subject", variables.clone()).await,
+            self.render_template(review_id, event, variables).await,
+        )
+    }
+    
+    /// Render a template with the review's variables and `extra`
+    ///
+    /// The review's author and branch are only looked up if the template uses them.
+    async fn render_template(&self, review_id: &ReviewId, name: &str, extra: Vec<(&'static str, String)>) -> String {
+        let Some(template) = self.templates.get(name) else {
+            return String::new();
+        };
+        let mut variables: HashMap<&str, String> = HashMap::from([
+            ("review", review_id.to_string()),
+            ("url", self.adapter.review_url(review_id)),
+            ("repository", self.repository.clone()),
+            ("main_branch", self.main_branch.clone()),
+            ("target", self.main_branch.clone()),
+        ]);
+        variables.extend(extra);
+        
+        if template.uses("author") {
+            match self.adapter.get_review_status(review_id).await {
+                Ok(status) => {
+                    variables.insert("author", status.author);
+                },
+                Err(e) => debug!("Could not get the author of review {}: {}", review_id, e),
+            }
+        }
+        if template.uses("branch") {
+            match self.adapter.get_review_head(review_id).await {
+                Ok(head) => {
+                    variables.insert("branch", head.branch);
+                },
+                Err(e) => debug!("Could not get the branch of review {}: {}", review_id, e),
+            }
+        }
+        
+        template.render(&variables)
+    }
 }
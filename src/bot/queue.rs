@@ -1,23 +1,62 @@
-use std::collections::VecDeque;
-use chrono::{DateTime, Utc};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Arc;
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
 use crate::bot::adapter::VcsAdapter;
 use crate::bot::ci::CiMonitor;
-use crate::review::{ReviewId, ReviewState, CiStatus};
+use crate::bot::metrics::{self, QUEUE_STATES};
+use crate::bot::notifier::{NotificationType, Notifier, QueueReport};
+use crate::review::{ReviewId, ReviewState, ReviewStatus, CiStatus};
+use crate::review::owners;
+use crate::review::policy::ApprovalPolicy;
+use crate::review::roles::Roles;
 use crate::utils::error::{GarryError, Result};
-use tracing::{info, warn, error};
+use tracing::{debug, info, warn, error};
 
 /// Entry in the merge queue
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct QueueEntry {
     pub review_id: ReviewId,
     pub branch: String,
+    /// Branch the review merges into; each target has its own queue
+    pub target: String,
     pub author: String,
     pub added_at: DateTime<Utc>,
+    /// When the queue started testing the entry
+    pub started_at: Option<DateTime<Utc>>,
     pub status: QueueStatus,
+    pub priority: Priority,
+    /// What happened to the entry, oldest first
+    pub history: Vec<QueueEvent>,
+}
+
+impl QueueEntry {
+    /// Add an event to the entry's history
+    fn record(&mut self, description: impl Into<String>) {
+        self.history.push(QueueEvent { at: Utc::now(), description: description.into() });
+    }
+}
+
+/// Something that happened to a queue entry
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct QueueEvent {
+    pub at: DateTime<Utc>,
+    /// What happened, e.g. "testing" or "moved to position 2"
+    pub description: String,
+}
+
+/// Priority of a queue entry; high-priority entries merge first
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Priority {
+    #[default]
+    Normal,
+    High,
 }
 
 /// Status of a queue entry
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum QueueStatus {
     Pending,
     Testing,
@@ -25,62 +64,528 @@ pub enum QueueStatus {
     Failed(String),
 }
 
+impl QueueStatus {
+    /// Short description for people, e.g. "testing"
+    pub fn description(&self) -> String {
+        match self {
+            QueueStatus::Pending => "waiting".to_string(),
+            QueueStatus::Testing => "testing".to_string(),
+            QueueStatus::Merging => "merging".to_string(),
+            QueueStatus::Failed(reason) => format!("failed ({})", reason),
+        }
+    }
+}
+
+/// How a review's time at the front of the queue ended
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct QueueOutcome {
+    pub review_id: ReviewId,
+    /// Branch the review was merging into
+    pub target: String,
+    pub at: DateTime<Utc>,
+    /// Why the review failed, or `None` if it merged
+    pub failure: Option<String>,
+    /// The entry's history up to the outcome
+    pub history: Vec<QueueEvent>,
+}
+
+/// How many recent merges the ETA estimate averages over
+const MERGE_HISTORY: usize = 10;
+
+/// How many recent merges and failures are kept for reports
+const OUTCOME_HISTORY: usize = 100;
+
+/// A copy of the queue at one moment, for reading without holding it locked
+#[derive(Debug, Clone, Default)]
+pub struct QueueSnapshot {
+    /// The repository the queue merges into, or "" if unnamed
+    pub repository: String,
+    /// Every target branch's entries in one list
+    pub entries: Vec<QueueEntry>,
+    /// Recent merges and failures, oldest first
+    pub outcomes: Vec<QueueOutcome>,
+    /// Reviews held out of the queue, sorted
+    pub held: Vec<ReviewId>,
+    pub paused: bool,
+    /// Average time from testing to merged over recent merges
+    pub average_merge: Option<Duration>,
+    /// Links to the reviews in the snapshot
+    pub review_urls: HashMap<ReviewId, String>,
+}
+
+impl QueueSnapshot {
+    /// Get position of a review in the queue
+    pub fn position(&self, review_id: &ReviewId) -> Option<usize> {
+        self.entries.iter().position(|e| &e.review_id == review_id)
+    }
+    
+    /// Position of a review in its target branch's queue, and that queue's length
+    pub fn target_position(&self, review_id: &ReviewId) -> Option<(usize, usize)> {
+        target_position(self.entries.iter(), review_id)
+    }
+    
+    /// Estimated time until the entry at `position` merges, from recent merges
+    pub fn eta(&self, position: usize) -> Option<Duration> {
+        self.average_merge.map(|average| average * (position as i32 + 1))
+    }
+    
+    /// Whether a review is held out of the queue
+    pub fn is_held(&self, review_id: &ReviewId) -> bool {
+        self.held.contains(review_id)
+    }
+    
+    /// Link to a review in the snapshot, or "" for others
+    pub fn review_url(&self, review_id: &ReviewId) -> &str {
+        self.review_urls.get(review_id).map(String::as_str).unwrap_or_default()
+    }
+}
+
+/// Position of a review among `entries` into the same target, and how many there are
+fn target_position<'a>(entries: impl Iterator<Item = &'a QueueEntry> + Clone, review_id: &ReviewId) -> Option<(usize, usize)> {
+    let target = &entries.clone().find(|e| &e.review_id == review_id)?.target;
+    let mut entries = entries.filter(|e| &e.target == target);
+    let position = entries.position(|e| &e.review_id == review_id)?;
+    Some((position, position + 1 + entries.count()))
+}
+
+/// Which reviews the queue takes, into which branches, under which policies
+///
+/// Kept apart from the queue itself, so the bot can walk reviews without
+/// holding the queue locked.
+#[derive(Debug, Clone)]
+pub struct QueueRules {
+    /// Branch reviews merge into unless they target another
+    main_branch: String,
+    policy: ApprovalPolicy,
+    /// Other branches reviews may target, as patterns, and their policies
+    targets: Vec<(String, ApprovalPolicy)>,
+    roles: Option<Roles>,
+}
+
+impl QueueRules {
+    /// The policy reviews into `target` are approved under, or `None` if the
+    /// queue doesn't merge into `target`
+    pub fn policy_for(&self, target: &str) -> Option<&ApprovalPolicy> {
+        if target.is_empty() || target == self.main_branch {
+            return Some(&self.policy);
+        }
+        self.targets.iter()
+            .find(|(pattern, _)| owners::glob_match(pattern.as_bytes(), target.as_bytes()))
+            .map(|(_, policy)| policy)
+    }
+    
+    /// Branch a review merges into, going by its status
+    fn target_of(&self, status: &ReviewStatus) -> String {
+        if status.target_branch.is_empty() {
+            self.main_branch.clone()
+        } else {
+            status.target_branch.clone()
+        }
+    }
+    
+    /// Get a review's status, without approvals from users who may not approve
+    pub async fn review_status(&self, adapter: &dyn VcsAdapter, review_id: &ReviewId) -> Result<ReviewStatus> {
+        let mut status = adapter.get_review_status(review_id).await?;
+        if let Some(roles) = &self.roles {
+            roles.discard_unauthorized_votes(adapter, &mut status).await?;
+        }
+        Ok(status)
+    }
+    
+    /// Find the pending reviews the queue would take, leaving out those in
+    /// `skip`, with the statuses that qualified them
+    pub async fn find_queueable(&self, adapter: &dyn VcsAdapter, skip: &HashSet<ReviewId>) -> Result<Vec<ReviewStatus>> {
+        let mut found = Vec::new();
+        for review_id in adapter.list_pending_reviews().await? {
+            if skip.contains(&review_id) {
+                continue;
+            }
+            
+            // Check if approved and CI passed
+            match self.review_status(adapter, &review_id).await {
+                Ok(status) => {
+                    let Some(policy) = self.policy_for(&status.target_branch) else {
+                        debug!("Skipping review {} into {}, which the queue doesn't merge into", review_id, status.target_branch);
+                        continue;
+                    };
+                    if QueueManager::is_queueable(policy, &status) {
+                        found.push(status);
+                    }
+                },
+                Err(e) => {
+                    warn!("Failed to get status for review {}: {}", review_id, e);
+                }
+            }
+        }
+        Ok(found)
+    }
+    
+    /// Why a review about to merge into `target` no longer qualifies, if it doesn't
+    async fn merge_blocker(&self, adapter: &dyn VcsAdapter, review_id: &ReviewId, target: &str) -> Result<Option<String>> {
+        let status = self.review_status(adapter, review_id).await?;
+        match status.state {
+            ReviewState::Draft => return Ok(Some("it was turned back into a draft".to_string())),
+            ReviewState::Merged | ReviewState::Closed => return Ok(Some("it was closed".to_string())),
+            _ => {},
+        }
+        if self.target_of(&status) != target {
+            return Ok(Some(format!("it now targets {}", self.target_of(&status))));
+        }
+        let Some(policy) = self.policy_for(target) else {
+            return Ok(Some(format!("the merge queue no longer merges into {}", target)));
+        };
+        let decision = policy.evaluate(&status);
+        if !decision.is_approved() {
+            return Ok(Some(decision.problems.join("; ")));
+        }
+        Ok(None)
+    }
+}
+
+/// A pass over the queue, planned from the queue and run without it
+///
+/// The bot plans a pass with [`QueueManager::plan_pass`] while it holds the
+/// queue, runs it against the forge unlocked, then applies what it did with
+/// [`QueueManager::finish_pass`].
+pub struct QueuePass {
+    rules: QueueRules,
+    ci_monitor: Arc<CiMonitor>,
+    /// The front entry of each target's queue, as planned
+    fronts: Vec<QueueEntry>,
+}
+
+/// How a step left an entry
+enum StepResult {
+    /// Still queued, maybe in a new state
+    Stays,
+    /// Failed, to be reported and dropped on the next pass
+    Failed(NotificationType),
+    /// Merged, so it leaves the queue
+    Merged,
+    /// Failed on an earlier pass and now dropped
+    Dropped,
+}
+
+/// One entry moved on by a pass
+struct Step {
+    /// The entry after the step
+    entry: QueueEntry,
+    /// How many events the entry's history had when planned
+    planned: usize,
+    result: StepResult,
+}
+
+/// What a pass did, for [`QueueManager::finish_pass`]
+pub struct PassOutcome {
+    steps: Vec<Step>,
+    /// The first error a step ran into
+    error: Option<GarryError>,
+}
+
+impl QueuePass {
+    /// Move the front entry of each target's queue one step on
+    ///
+    /// Each target's queue advances on its own, so a review stuck in CI for
+    /// a release branch doesn't hold up the main branch.
+    pub async fn run(self, adapter: &dyn VcsAdapter) -> PassOutcome {
+        let mut steps = Vec::new();
+        let mut error = None;
+        for mut entry in self.fronts.iter().cloned() {
+            let planned = entry.history.len();
+            let result = match self.step(adapter, &mut entry).await {
+                Ok(result) => result,
+                Err(e) => {
+                    error!("Error processing review {}: {}", entry.review_id, e);
+                    error.get_or_insert(e);
+                    StepResult::Stays
+                },
+            };
+            steps.push(Step { entry, planned, result });
+        }
+        PassOutcome { steps, error }
+    }
+    
+    /// Move `entry`, the front of its target's queue, one step on
+    async fn step(&self, adapter: &dyn VcsAdapter, entry: &mut QueueEntry) -> Result<StepResult> {
+        match entry.status {
+            QueueStatus::Pending => {
+                info!("Testing review {} for {}", entry.review_id, entry.target);
+                entry.status = QueueStatus::Testing;
+                entry.started_at = Some(Utc::now());
+                entry.record("testing");
+                
+                // Check CI status
+                match self.ci_monitor.check_ci_status(adapter, &entry.review_id).await? {
+                    CiStatus::Success => {
+                        info!("CI passed for review {}, proceeding to merge", entry.review_id);
+                        entry.status = QueueStatus::Merging;
+                        entry.record("CI passed, merging");
+                    },
+                    CiStatus::Failed(failures) => {
+                        error!("CI failed for review {}: {:?}", entry.review_id, failures);
+                        entry.status = QueueStatus::Failed(format!("CI failed: {:?}", failures));
+                        entry.record(format!("CI failed: {}", failures.join(", ")));
+                        return Ok(StepResult::Failed(NotificationType::CiFailed(failures)));
+                    },
+                    _ => {
+                        info!("CI still running for review {}", entry.review_id);
+                    }
+                }
+            },
+            QueueStatus::Testing => {
+                // Re-check CI
+                match self.ci_monitor.check_ci_status(adapter, &entry.review_id).await? {
+                    CiStatus::Success => {
+                        entry.status = QueueStatus::Merging;
+                        entry.record("CI passed, merging");
+                    },
+                    CiStatus::Failed(failures) => {
+                        entry.status = QueueStatus::Failed(format!("CI failed: {:?}", failures));
+                        entry.record(format!("CI failed: {}", failures.join(", ")));
+                        return Ok(StepResult::Failed(NotificationType::CiFailed(failures)));
+                    },
+                    _ => {}
+                }
+            },
+            QueueStatus::Merging => {
+                // Approvals can be withdrawn and reviews closed while CI runs
+                if let Some(problem) = self.rules.merge_blocker(adapter, &entry.review_id, &entry.target).await? {
+                    warn!("Not merging review {}: {}", entry.review_id, problem);
+                    entry.status = QueueStatus::Failed(format!("No longer mergeable: {}", problem));
+                    entry.record(format!("no longer mergeable: {}", problem));
+                    return Ok(StepResult::Failed(NotificationType::ReviewRequiresUpdate));
+                }
+                info!("Merging review {} into {}", entry.review_id, entry.target);
+                
+                match adapter.merge_review(&entry.review_id).await {
+                    Ok(_) => {
+                        info!("Successfully merged review {}", entry.review_id);
+                        entry.record("merged");
+                        return Ok(StepResult::Merged);
+                    },
+                    Err(e) => {
+                        error!("Failed to merge review {}: {}", entry.review_id, e);
+                        entry.status = QueueStatus::Failed(format!("Merge failed: {}", e));
+                        entry.record(format!("merge failed: {}", e));
+                        return Ok(StepResult::Failed(NotificationType::MergeConflict(e.to_string())));
+                    }
+                }
+            },
+            QueueStatus::Failed(_) => {
+                warn!("Removing failed review {} from queue", entry.review_id);
+                return Ok(StepResult::Dropped);
+            }
+        }
+        Ok(StepResult::Stays)
+    }
+}
+
+/// Notifications a finished pass leaves to send, once the queue is unlocked
+pub struct QueueNotices {
+    notifier: Option<Arc<Notifier>>,
+    /// Entries that merged or failed, and how
+    finished: Vec<(QueueEntry, NotificationType)>,
+    /// Status comments to update
+    reports: Vec<(ReviewId, QueueReport)>,
+    /// Reviews that left the queue without a terminal event
+    cleared: Vec<ReviewId>,
+    /// The first error the pass ran into
+    error: Option<GarryError>,
+}
+
+impl QueueNotices {
+    /// Notify reviews that merged or failed, update the status comments of
+    /// queued reviews and remove those of reviews that left the queue
+    ///
+    /// Returns the first error the pass ran into, after sending.
+    pub async fn send(self) -> Result<()> {
+        if let Some(notifier) = &self.notifier {
+            for (entry, notification) in self.finished {
+                if let Err(e) = notifier.notify_target(&entry.review_id, &entry.target, notification).await {
+                    warn!("Failed to notify review {}: {}", entry.review_id, e);
+                }
+            }
+            for (review_id, report) in &self.reports {
+                if let Err(e) = notifier.update_status(review_id, report).await {
+                    warn!("Failed to update the status comment on review {}: {}", review_id, e);
+                }
+            }
+            for review_id in &self.cleared {
+                if let Err(e) = notifier.clear_status(review_id).await {
+                    warn!("Failed to remove the status comment on review {}: {}", review_id, e);
+                }
+            }
+        }
+        self.error.map_or(Ok(()), Err)
+    }
+}
+
 /// Manages the merge queue
 pub struct QueueManager {
+    /// Repository the queue merges into, as "owner/repo"
+    repository: String,
     queue: VecDeque<QueueEntry>,
     adapter: Box<dyn VcsAdapter>,
-    ci_monitor: CiMonitor,
+    ci_monitor: Arc<CiMonitor>,
+    rules: QueueRules,
+    notifier: Option<Arc<Notifier>>,
+    held: HashSet<ReviewId>,
+    /// Whether processing is paused; reviews are still queued
+    paused: bool,
+    /// How long recent entries took from testing to merged, newest last
+    merge_durations: VecDeque<Duration>,
+    /// Recent merges and failures, newest last
+    outcomes: VecDeque<QueueOutcome>,
+    /// Reviews with a status comment from the last tick
+    reported: HashSet<ReviewId>,
 }
 
 impl QueueManager {
     /// Create a new queue manager
     pub fn new(adapter: Box<dyn VcsAdapter>) -> Self {
-        let ci_monitor = CiMonitor::new(adapter.as_ref());
+        let ci_monitor = Arc::new(CiMonitor::new(adapter.as_ref()));
         Self {
+            repository: String::new(),
             queue: VecDeque::new(),
             adapter,
             ci_monitor,
+            rules: QueueRules {
+                main_branch: "main".to_string(),
+                policy: ApprovalPolicy::default(),
+                targets: vec![],
+                roles: None,
+            },
+            notifier: None,
+            held: HashSet::new(),
+            paused: false,
+            merge_durations: VecDeque::new(),
+            outcomes: VecDeque::new(),
+            reported: HashSet::new(),
         }
     }
     
+    /// Name the repository the queue merges into, for logs and metrics
+    pub fn with_repository(mut self, repository: String) -> Self {
+        self.ci_monitor = Arc::new(CiMonitor::new(self.adapter.as_ref()).with_repository(repository.clone()));
+        self.repository = repository;
+        self
+    }
+    
+    /// Use the given approval policy instead of the default one
+    pub fn with_policy(mut self, policy: ApprovalPolicy) -> Self {
+        self.rules.policy = policy;
+        self
+    }
+    
+    /// Set the branch reviews merge into by default (default: "main")
+    pub fn with_main_branch(mut self, main_branch: String) -> Self {
+        self.rules.main_branch = main_branch;
+        self
+    }
+    
+    /// Also queue reviews into branches matching `pattern` (e.g. "release/*"),
+    /// approved under `policy`
+    ///
+    /// Reviews into other branches than these and the main branch are left alone.
+    pub fn with_target(mut self, pattern: String, policy: ApprovalPolicy) -> Self {
+        self.rules.targets.push((pattern, policy));
+        self
+    }
+    
+    /// The policy reviews into `target` are approved under, or `None` if the
+    /// queue doesn't merge into `target`
+    pub fn policy_for(&self, target: &str) -> Option<&ApprovalPolicy> {
+        self.rules.policy_for(target)
+    }
+    
+    /// Which reviews the queue takes, for checking reviews without the queue
+    pub fn rules(&self) -> &QueueRules {
+        &self.rules
+    }
+    
+    /// Only count approvals from users whose role lets them approve
+    ///
+    /// Without roles, every approval the platform reports counts.
+    pub fn with_roles(mut self, roles: Roles) -> Self {
+        self.rules.roles = Some(roles);
+        self
+    }
+    
+    /// Notify reviews (and configured channels) when they merge or fail
+    pub fn with_notifier(mut self, notifier: Notifier) -> Self {
+        self.notifier = Some(Arc::new(notifier));
+        self
+    }
+    
+    /// Get a review's status, without approvals from users who may not approve
+    pub async fn review_status(&self, review_id: &ReviewId) -> Result<ReviewStatus> {
+        self.rules.review_status(self.adapter.as_ref(), review_id).await
+    }
+    
+    /// Roles used to check who may approve and control the queue, if configured
+    pub fn roles(&self) -> Option<&Roles> {
+        self.rules.roles.as_ref()
+    }
+    
+    /// Whether a review is ready to be picked up by the queue
+    ///
+    /// Approval is decided by the policy from the individual votes, not by
+    /// the platform's summary state. Drafts are never queueable.
+    pub fn is_queueable(policy: &ApprovalPolicy, status: &ReviewStatus) -> bool {
+        status.state != ReviewState::Draft
+            && policy.evaluate(status).is_approved()
+            && status.ci_status == CiStatus::Success
+    }
+    
     /// Discover approved reviews and add them to the queue
     pub async fn discover_and_queue_reviews(&mut self) -> Result<()> {
-        // Get all pending reviews
-        let pending_reviews = self.adapter.list_pending_reviews().await?;
-        
-        for review_id in pending_reviews {
-            // Skip if already in queue
-            if self.queue.iter().any(|e| e.review_id == review_id) {
-                continue;
-            }
-            
-            // Check if approved and CI passed
-            match self.adapter.get_review_status(&review_id).await {
-                Ok(status) => {
-                    if status.state == ReviewState::Approved && status.ci_status == CiStatus::Success {
-                        info!("Found approved review {} with passing CI, adding to queue", review_id);
-                        let _ = self.add_to_queue(review_id).await;
-                    }
-                },
-                Err(e) => {
-                    warn!("Failed to get status for review {}: {}", review_id, e);
-                }
-            }
-        }
-        
+        let found = self.rules.find_queueable(self.adapter.as_ref(), &self.skipped()).await?;
+        self.add_discovered(found);
         Ok(())
     }
     
+    /// Reviews discovery leaves alone: those queued or held out of the queue
+    pub fn skipped(&self) -> HashSet<ReviewId> {
+        self.queue.iter().map(|e| e.review_id.clone()).chain(self.held.iter().cloned()).collect()
+    }
+    
+    /// Queue reviews found by [`QueueRules::find_queueable`], going by the
+    /// statuses it found them with
+    ///
+    /// Reviews queued or held since they were found are left alone.
+    pub fn add_discovered(&mut self, found: Vec<ReviewStatus>) {
+        let skip = self.skipped();
+        for status in found.into_iter().filter(|s| !skip.contains(&s.id)) {
+            info!("Found approved review {} with passing CI, adding to queue", status.id);
+            let _ = self.add_with_status(status);
+        }
+    }
+    
     /// Add a review to the merge queue
     pub async fn add_to_queue(&mut self, review_id: ReviewId) -> Result<()> {
+        // Validate review is approved and CI passed
+        let status = self.review_status(&review_id).await?;
+        self.add_with_status(status)
+    }
+    
+    /// Add a review to the merge queue, if `status` shows it's ready
+    fn add_with_status(&mut self, status: ReviewStatus) -> Result<()> {
+        let review_id = status.id.clone();
         info!("Adding review {} to merge queue", review_id);
         
-        // Validate review is approved and CI passed
-        let status = self.adapter.get_review_status(&review_id).await?;
+        // Drafts are never merged, even when approved
+        if status.state == ReviewState::Draft {
+            return Err(GarryError::VcsError(format!("Review {} is a draft", review_id)));
+        }
         
-        if status.state != ReviewState::Approved {
+        let target = self.rules.target_of(&status);
+        let policy = self.policy_for(&target).ok_or_else(|| GarryError::VcsError(
+            format!("Review {} targets {}, which the merge queue doesn't merge into", review_id, target)
+        ))?;
+        let decision = policy.evaluate(&status);
+        if !decision.is_approved() {
             return Err(GarryError::VcsError(
-                format!("Review {} is not approved", review_id)
+                format!("Review {} is not approved: {}", review_id, decision.problems.join("; "))
             ));
         }
         
@@ -96,100 +601,364 @@ impl QueueManager {
             return Ok(());
         }
         
-        let entry = QueueEntry {
+        let mut entry = QueueEntry {
             review_id: review_id.clone(),
-            branch: "unknown".to_string(), // TODO: Get from review
-            author: "unknown".to_string(),
+            branch: status.head_branch,
+            target,
+            author: status.author,
             added_at: Utc::now(),
+            started_at: None,
             status: QueueStatus::Pending,
+            priority: Priority::Normal,
+            history: vec![],
         };
+        entry.record("queued");
         
         self.queue.push_back(entry);
-        info!("Review {} added to queue at position {}", review_id, self.queue.len());
+        self.update_queue_metrics();
+        info!("Review {} added to the {} queue at position {}", review_id, self.queue.back().map_or("", |e| e.target.as_str()), self.queue.len());
         
         Ok(())
     }
     
     /// Process the merge queue
+    ///
+    /// Plans a pass, runs it and applies it in one go; the bot runs the pass
+    /// without holding the queue instead.
     pub async fn process_queue(&mut self) -> Result<()> {
-        if self.queue.is_empty() {
-            return Ok(());
+        let pass = self.plan_pass();
+        let outcome = pass.run(self.adapter.as_ref()).await;
+        self.finish_pass(outcome).send().await
+    }
+    
+    /// Plan a pass over the front entry of each target's queue, or over
+    /// none while the queue is paused
+    pub fn plan_pass(&self) -> QueuePass {
+        let fronts = if self.paused {
+            vec![]
+        } else {
+            self.targets().iter()
+                .filter_map(|target| self.queue.iter().find(|e| &e.target == target).cloned())
+                .collect()
+        };
+        if !fronts.is_empty() {
+            info!("Processing merge queue ({} entries)", self.queue.len());
         }
-        
-        info!("Processing merge queue ({} entries)", self.queue.len());
-        
-        // Process first entry
-        if let Some(entry) = self.queue.front_mut() {
-            match entry.status {
-                QueueStatus::Pending => {
-                    info!("Testing review {}", entry.review_id);
-                    entry.status = QueueStatus::Testing;
-                    
-                    // Check CI status
-                    match self.ci_monitor.check_ci_status(&entry.review_id).await? {
-                        CiStatus::Success => {
-                            info!("CI passed for review {}, proceeding to merge", entry.review_id);
-                            entry.status = QueueStatus::Merging;
-                        },
-                        CiStatus::Failed(failures) => {
-                            error!("CI failed for review {}: {:?}", entry.review_id, failures);
-                            entry.status = QueueStatus::Failed(format!("CI failed: {:?}", failures));
-                        },
-                        _ => {
-                            info!("CI still running for review {}", entry.review_id);
-                        }
-                    }
-                },
-                QueueStatus::Testing => {
-                    // Re-check CI
-                    match self.ci_monitor.check_ci_status(&entry.review_id).await? {
-                        CiStatus::Success => {
-                            entry.status = QueueStatus::Merging;
-                        },
-                        CiStatus::Failed(failures) => {
-                            entry.status = QueueStatus::Failed(format!("CI failed: {:?}", failures));
-                        },
-                        _ => {}
-                    }
+        QueuePass { rules: self.rules.clone(), ci_monitor: self.ci_monitor.clone(), fronts }
+    }
+    
+    /// Apply what a pass did to the queue, and work out what to tell reviews
+    ///
+    /// Entries changed by the API or chat commands while the pass ran keep
+    /// those changes. Entries taken out of the queue meanwhile are left out,
+    /// unless they merged.
+    pub fn finish_pass(&mut self, outcome: PassOutcome) -> QueueNotices {
+        let mut finished = Vec::new();
+        for mut step in outcome.steps {
+            let index = self.queue.iter()
+                .position(|e| e.review_id == step.entry.review_id && e.added_at == step.entry.added_at);
+            let entry = match index {
+                Some(index) => {
+                    let entry = &mut self.queue[index];
+                    entry.status = step.entry.status;
+                    entry.started_at = step.entry.started_at;
+                    entry.history.extend(step.entry.history.drain(step.planned..));
+                    entry.clone()
                 },
-                QueueStatus::Merging => {
-                    info!("Merging review {}", entry.review_id);
-                    
-                    match self.adapter.merge_review(&entry.review_id).await {
-                        Ok(_) => {
-                            info!("Successfully merged review {}", entry.review_id);
-                            self.queue.pop_front();
-                        },
-                        Err(e) => {
-                            error!("Failed to merge review {}: {}", entry.review_id, e);
-                            entry.status = QueueStatus::Failed(format!("Merge failed: {}", e));
+                None if matches!(step.result, StepResult::Merged) => step.entry,
+                None => continue,
+            };
+            match step.result {
+                StepResult::Stays => {},
+                StepResult::Failed(notification) => finished.push((entry, notification)),
+                StepResult::Merged => {
+                    if let Some(started_at) = entry.started_at {
+                        self.merge_durations.push_back(Utc::now() - started_at);
+                        if self.merge_durations.len() > MERGE_HISTORY {
+                            self.merge_durations.pop_front();
                         }
                     }
+                    self.queue.retain(|e| e.review_id != entry.review_id);
+                    finished.push((entry, NotificationType::MergeSuccess));
                 },
-                QueueStatus::Failed(_) => {
-                    warn!("Removing failed review {} from queue", entry.review_id);
-                    self.queue.pop_front();
-                }
+                StepResult::Dropped => self.queue.retain(|e| e.review_id != entry.review_id),
             }
         }
         
-        Ok(())
+        for (entry, notification) in &finished {
+            let (result, failure) = match notification {
+                NotificationType::CiFailed(checks) => ("ci_failed", Some(format!("CI failed: {}", checks.join(", ")))),
+                NotificationType::MergeConflict(details) => ("merge_failed", Some(format!("Merge failed: {}", details))),
+                NotificationType::ReviewRequiresUpdate => match &entry.status {
+                    QueueStatus::Failed(reason) => ("not_mergeable", Some(reason.clone())),
+                    _ => ("not_mergeable", None),
+                },
+                _ => ("success", None),
+            };
+            let now = Utc::now();
+            let metrics = metrics::metrics();
+            metrics.merges.with_label_values(&[&self.repository, result]).inc();
+            metrics.queue_time.with_label_values(&[&self.repository, result]).observe(metrics::seconds(now - entry.added_at));
+            self.outcomes.push_back(QueueOutcome {
+                review_id: entry.review_id.clone(),
+                target: entry.target.clone(),
+                at: now,
+                failure,
+                history: entry.history.clone(),
+            });
+            if self.outcomes.len() > OUTCOME_HISTORY {
+                self.outcomes.pop_front();
+            }
+            // The terminal notification replaces the status comment
+            self.reported.remove(&entry.review_id);
+        }
+        
+        self.update_queue_metrics();
+        let (reports, cleared) = self.status_reports();
+        QueueNotices { notifier: self.notifier.clone(), finished, reports, cleared, error: outcome.error }
+    }
+    
+    /// Report the queue length by state
+    fn update_queue_metrics(&self) {
+        let metrics = metrics::metrics();
+        for state in QUEUE_STATES {
+            metrics.queue_length.with_label_values(&[&self.repository, state]).set(0);
+        }
+        for entry in &self.queue {
+            let state = match entry.status {
+                QueueStatus::Pending => "pending",
+                QueueStatus::Testing => "testing",
+                QueueStatus::Merging => "merging",
+                QueueStatus::Failed(_) => "failed",
+            };
+            metrics.queue_length.with_label_values(&[&self.repository, state]).inc();
+        }
+    }
+    
+    /// Estimated time until the entry at `position` merges, from recent merges
+    pub fn eta(&self, position: usize) -> Option<Duration> {
+        self.average_merge().map(|average| average * (position as i32 + 1))
+    }
+    
+    /// Average time from testing to merged over recent merges
+    fn average_merge(&self) -> Option<Duration> {
+        if self.merge_durations.is_empty() {
+            return None;
+        }
+        let total: Duration = self.merge_durations.iter().sum();
+        Some(total / self.merge_durations.len() as i32)
+    }
+    
+    /// Status reports for every queued review, and the reviews that left
+    /// the queue without a terminal event, whose status comments go
+    fn status_reports(&mut self) -> (Vec<(ReviewId, QueueReport)>, Vec<ReviewId>) {
+        if self.notifier.is_none() {
+            return (vec![], vec![]);
+        }
+        
+        let mut reports = Vec::new();
+        for entry in &self.queue {
+            // Failed entries get a terminal notification instead
+            if matches!(entry.status, QueueStatus::Failed(_)) {
+                continue;
+            }
+            let (position, queue_length) = self.target_position(&entry.review_id).unwrap_or_default();
+            reports.push((entry.review_id.clone(), QueueReport {
+                position,
+                queue_length,
+                target: entry.target.clone(),
+                state: entry.status.clone(),
+                ci_status: if entry.status == QueueStatus::Testing { CiStatus::Running } else { CiStatus::Success },
+                eta: self.eta(position),
+            }));
+        }
+        
+        let reported: HashSet<ReviewId> = reports.iter().map(|(review_id, _)| review_id.clone()).collect();
+        let cleared = self.reported.difference(&reported).cloned().collect();
+        self.reported = reported;
+        (reports, cleared)
     }
     
     /// Remove a review from the queue
     pub async fn remove_from_queue(&mut self, review_id: &ReviewId) -> Result<()> {
         info!("Removing review {} from queue", review_id);
         self.queue.retain(|e| &e.review_id != review_id);
+        self.update_queue_metrics();
         Ok(())
     }
     
+    /// Take a review out of the queue and keep discovery from adding it back
+    ///
+    /// Returns whether the review was queued.
+    pub fn hold(&mut self, review_id: &ReviewId) -> bool {
+        info!("Holding review {}", review_id);
+        let queued = self.get_queue_position(review_id).is_some();
+        self.queue.retain(|e| &e.review_id != review_id);
+        self.held.insert(review_id.clone());
+        self.update_queue_metrics();
+        queued
+    }
+    
+    /// Let discovery queue a held review again
+    ///
+    /// Returns whether the review was held.
+    pub fn release(&mut self, review_id: &ReviewId) -> bool {
+        self.held.remove(review_id)
+    }
+    
+    /// Whether a review is held out of the queue
+    pub fn is_held(&self, review_id: &ReviewId) -> bool {
+        self.held.contains(review_id)
+    }
+    
+    /// Reviews held out of the queue
+    pub fn held(&self) -> &HashSet<ReviewId> {
+        &self.held
+    }
+    
+    /// Queue a review again after it failed
+    pub async fn retry(&mut self, review_id: &ReviewId) -> Result<()> {
+        info!("Retrying review {}", review_id);
+        let failed = self.queue.iter().position(|e| &e.review_id == review_id && matches!(e.status, QueueStatus::Failed(_)));
+        if let Some(position) = failed {
+            self.queue.remove(position);
+        }
+        self.release(review_id);
+        self.add_to_queue(review_id.clone()).await
+    }
+    
+    /// Change a queued review's priority, moving it ahead of or behind
+    /// entries of lower priority. Entries being processed keep their place.
+    ///
+    /// Returns the review's new position.
+    pub fn set_priority(&mut self, review_id: &ReviewId, priority: Priority) -> Result<usize> {
+        let position = self.get_queue_position(review_id)
+            .ok_or_else(|| GarryError::ReviewNotFound(format!("review {} in the merge queue", review_id)))?;
+        let mut entry = self.queue.remove(position).expect("position is in the queue");
+        entry.priority = priority;
+        entry.record(format!("priority set to {:?}", priority).to_lowercase());
+        
+        let start = self.first_movable(&entry.target);
+        let position = (start..self.queue.len())
+            .find(|&i| self.queue[i].priority < priority)
+            .unwrap_or(self.queue.len());
+        
+        info!("Moving review {} to queue position {} ({:?} priority)", review_id, position, priority);
+        self.queue.insert(position, entry);
+        Ok(position)
+    }
+    
+    /// Move a queued review to `position` among reviews into the same
+    /// target, regardless of priority
+    ///
+    /// Entries being processed keep their place, and positions past the end
+    /// move the review to the back. Returns the review's new position among
+    /// reviews into its target.
+    pub fn move_to(&mut self, review_id: &ReviewId, position: usize) -> Result<usize> {
+        let current = self.get_queue_position(review_id)
+            .ok_or_else(|| GarryError::ReviewNotFound(format!("review {} in the merge queue", review_id)))?;
+        if self.queue[current].status != QueueStatus::Pending {
+            return Err(GarryError::Other(format!("review {} is already being processed", review_id)));
+        }
+        
+        let entry = self.queue.remove(current).expect("position is in the queue");
+        let start = self.first_movable(&entry.target);
+        let index = self.queue.iter().enumerate()
+            .filter(|(_, e)| e.target == entry.target)
+            .nth(position)
+            .map_or(self.queue.len(), |(i, _)| i)
+            .max(start);
+        
+        info!("Moving review {} to queue position {}", review_id, index);
+        self.queue.insert(index, entry);
+        let (position, _) = self.target_position(review_id).expect("review was just moved");
+        self.queue[index].record(format!("moved to position {}", position + 1));
+        Ok(position)
+    }
+    
+    /// First position an entry into `target` may move to: behind the entry
+    /// being processed for that target, if any
+    fn first_movable(&self, target: &str) -> usize {
+        self.queue.iter()
+            .rposition(|e| e.target == target && e.status != QueueStatus::Pending)
+            .map_or(0, |i| i + 1)
+    }
+    
+    /// Stop testing and merging queued reviews; discovery still queues them
+    pub fn pause(&mut self) {
+        info!("Pausing the merge queue");
+        self.paused = true;
+    }
+    
+    /// Continue testing and merging queued reviews
+    pub fn resume(&mut self) {
+        info!("Resuming the merge queue");
+        self.paused = false;
+    }
+    
+    /// Whether processing is paused
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+    
     /// Get position of a review in the queue
     pub fn get_queue_position(&self, review_id: &ReviewId) -> Option<usize> {
         self.queue.iter().position(|e| &e.review_id == review_id)
     }
     
-    /// Get the current queue
+    /// Position of a review in its target branch's queue, and that queue's length
+    pub fn target_position(&self, review_id: &ReviewId) -> Option<(usize, usize)> {
+        target_position(self.queue.iter(), review_id)
+    }
+    
+    /// Target branches with queued reviews, in queue order
+    pub fn targets(&self) -> Vec<String> {
+        let mut targets: Vec<String> = Vec::new();
+        for entry in &self.queue {
+            if !targets.contains(&entry.target) {
+                targets.push(entry.target.clone());
+            }
+        }
+        targets
+    }
+    
+    /// Get the current queue, every target branch's entries in one list
     pub fn get_queue(&self) -> &VecDeque<QueueEntry> {
         &self.queue
     }
+    
+    /// Recent merges and failures, oldest first
+    pub fn recent_outcomes(&self) -> &VecDeque<QueueOutcome> {
+        &self.outcomes
+    }
+    
+    /// The repository the queue merges into, or "" if unnamed
+    pub fn repository(&self) -> &str {
+        &self.repository
+    }
+    
+    /// Copy the queue, for reading once the lock is released
+    pub fn snapshot(&self) -> QueueSnapshot {
+        let mut held: Vec<_> = self.held.iter().cloned().collect();
+        held.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+        let review_urls = self.queue.iter().map(|e| &e.review_id)
+            .chain(self.outcomes.iter().map(|o| &o.review_id))
+            .chain(&held)
+            .map(|id| (id.clone(), self.adapter.review_url(id)))
+            .collect();
+        QueueSnapshot {
+            repository: self.repository.clone(),
+            entries: self.queue.iter().cloned().collect(),
+            outcomes: self.outcomes.iter().cloned().collect(),
+            held,
+            paused: self.paused,
+            average_merge: self.average_merge(),
+            review_urls,
+        }
+    }
+    
+    /// Get the adapter the queue talks to
+    pub fn adapter(&self) -> &dyn VcsAdapter {
+        self.adapter.as_ref()
+    }
 }
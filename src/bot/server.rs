@@ -0,0 +1,452 @@
+use std::net::TcpListener;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use axum::extract::{Json, Path, State};
+use axum::http::{header, Request, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{Html, IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::Router;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tokio::sync::Mutex;
+use crate::bot::dashboard;
+use crate::bot::metrics;
+use crate::bot::queue::{QueueEntry, QueueManager, QueueOutcome, QueueSnapshot};
+use crate::review::ReviewId;
+use crate::utils::config::Config;
+use crate::utils::error::{GarryError, Result};
+use tracing::{info, warn};
+
+/// Whether the bot loop is running, for health and readiness checks
+pub struct Health {
+    started: DateTime<Utc>,
+    stall_after: Duration,
+    ready: AtomicBool,
+    last_tick: std::sync::Mutex<Option<DateTime<Utc>>>,
+}
+
+impl Health {
+    /// Consider the bot stalled when a pass of its loop takes longer than `stall_after`
+    pub fn new(stall_after: Duration, now: DateTime<Utc>) -> Self {
+        Self { started: now, stall_after, ready: AtomicBool::new(false), last_tick: std::sync::Mutex::new(None) }
+    }
+    
+    /// Record a completed pass of the bot loop; the first makes the bot ready
+    pub fn tick(&self, now: DateTime<Utc>) {
+        *self.last_tick.lock().unwrap() = Some(now);
+        self.ready.store(true, Ordering::Relaxed);
+    }
+    
+    /// When the bot loop last completed a pass
+    pub fn last_tick(&self) -> Option<DateTime<Utc>> {
+        *self.last_tick.lock().unwrap()
+    }
+    
+    /// Whether the bot has finished starting up and processed the queue once
+    pub fn is_ready(&self) -> bool {
+        self.ready.load(Ordering::Relaxed)
+    }
+    
+    /// Whether the bot loop has completed a pass recently
+    pub fn is_live(&self, now: DateTime<Utc>) -> bool {
+        now - self.last_tick().unwrap_or(self.started) <= self.stall_after
+    }
+}
+
+/// What the HTTP server's handlers share
+#[derive(Clone)]
+pub struct ServerState {
+    queue: Arc<Mutex<QueueManager>>,
+    /// The queue as of the bot's last pass, which the dashboard shows
+    snapshot: Arc<RwLock<QueueSnapshot>>,
+    health: Arc<Health>,
+    token: Option<String>,
+    /// Dashboard refresh interval in seconds, if the dashboard is served
+    dashboard_refresh: Option<u64>,
+    /// Path the routes are served under: "" at the root, or "/repos/owner/repo"
+    base: String,
+    /// Every repository, when the bot manages several
+    repositories: Vec<RepositoryState>,
+    /// Names of every repository, for the dashboard's navigation
+    names: Vec<String>,
+}
+
+/// A repository's queue and bot loop
+#[derive(Clone)]
+struct RepositoryState {
+    name: String,
+    queue: Arc<Mutex<QueueManager>>,
+    snapshot: Arc<RwLock<QueueSnapshot>>,
+    health: Arc<Health>,
+}
+
+impl ServerState {
+    /// Serve health checks for the bot running `queue`
+    ///
+    /// The bot publishes `snapshot` after each pass, so the dashboard doesn't
+    /// wait on the queue's lock.
+    pub fn new(queue: Arc<Mutex<QueueManager>>, snapshot: Arc<RwLock<QueueSnapshot>>, health: Arc<Health>) -> Self {
+        Self { queue, snapshot, health, token: None, dashboard_refresh: None, base: String::new(), repositories: vec![], names: vec![] }
+    }
+    
+    /// Also serve `repository` ("owner/repo") under `/repos/owner/repo`
+    ///
+    /// The root dashboard keeps showing the queue given to [`ServerState::new`],
+    /// the root health checks cover every repository, and the root API is off.
+    pub fn with_repository(
+        mut self,
+        repository: String,
+        queue: Arc<Mutex<QueueManager>>,
+        snapshot: Arc<RwLock<QueueSnapshot>>,
+        health: Arc<Health>,
+    ) -> Self {
+        self.repositories.push(RepositoryState { name: repository, queue, snapshot, health });
+        self
+    }
+    
+    /// Serve the admin API to requests bearing `token`
+    pub fn with_token(mut self, token: String) -> Self {
+        self.token = Some(token);
+        self
+    }
+    
+    /// Serve the HTML dashboard, reloading every `refresh_seconds`
+    pub fn with_dashboard(mut self, refresh_seconds: u64) -> Self {
+        self.dashboard_refresh = Some(refresh_seconds.max(1));
+        self
+    }
+}
+
+/// Build the router: `/healthz`, `/readyz` and `/metrics`, the dashboard
+/// if enabled, plus `/api` with a token
+///
+/// Each repository added with [`ServerState::with_repository`] gets the same
+/// routes, except `/metrics`, under `/repos/owner/repo`. The root `/api` is
+/// then left out, so changes always name the repository they're for.
+pub fn router(mut state: ServerState) -> Router {
+    state.names = state.repositories.iter().map(|r| r.name.clone()).collect();
+    let root = if state.repositories.is_empty() {
+        state.clone()
+    } else {
+        ServerState { token: None, ..state.clone() }
+    };
+    let mut router = routes(root).route("/metrics", get(render_metrics));
+    for repository in &state.repositories {
+        let base = format!("/repos/{}", repository.name);
+        let nested = ServerState {
+            queue: repository.queue.clone(),
+            snapshot: repository.snapshot.clone(),
+            health: repository.health.clone(),
+            base: base.clone(),
+            repositories: vec![],
+            ..state.clone()
+        };
+        router = router.nest(&base, routes(nested));
+    }
+    router
+}
+
+/// The routes served for one queue
+fn routes(state: ServerState) -> Router {
+    let mut router = Router::new()
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz));
+    
+    if state.dashboard_refresh.is_some() {
+        router = router
+            .route("/", get(dashboard_queue))
+            .route("/reviews/:review", get(dashboard_review));
+    }
+    
+    if state.token.is_some() {
+        let api = Router::new()
+            .route("/queue", get(list_queue).post(enqueue))
+            .route("/queue/pause", post(pause))
+            .route("/queue/resume", post(resume))
+            .route("/queue/:review", get(show_entry).delete(dequeue))
+            .route("/queue/:review/move", post(move_entry))
+            .route_layer(middleware::from_fn_with_state(state.clone(), authorize));
+        router = router.nest("/api", api);
+    }
+    
+    router.with_state(state)
+}
+
+/// Bind the listener configured by `[api]`, or `None` if the server is off
+pub fn bind(config: &Config) -> Result<Option<TcpListener>> {
+    if !config.api.enabled {
+        return Ok(None);
+    }
+    let port = config.api.port.unwrap_or(config.bot.webhook_port);
+    let listener = TcpListener::bind((config.api.bind.as_str(), port))?;
+    listener.set_nonblocking(true)?;
+    Ok(Some(listener))
+}
+
+/// Serve `state` on `listener` until the server fails
+pub async fn serve(listener: TcpListener, state: ServerState) -> Result<()> {
+    info!("Serving HTTP on {}", listener.local_addr()?);
+    if state.token.is_none() {
+        warn!("No API token configured; the admin API is off");
+    }
+    axum::Server::from_tcp(listener)
+        .map_err(|e| GarryError::Other(format!("HTTP server failed: {}", e)))?
+        .serve(router(state).into_make_service())
+        .await
+        .map_err(|e| GarryError::Other(format!("HTTP server failed: {}", e)))
+}
+
+/// An error as a JSON response
+struct ApiError(StatusCode, String);
+
+impl From<GarryError> for ApiError {
+    fn from(e: GarryError) -> Self {
+        let status = match e {
+            GarryError::ReviewNotFound(_) => StatusCode::NOT_FOUND,
+            GarryError::PermissionDenied(_) => StatusCode::FORBIDDEN,
+            GarryError::VcsError(_) | GarryError::CiFailed(_) | GarryError::Other(_) => StatusCode::CONFLICT,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        ApiError(status, e.to_string())
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.0, Json(json!({ "error": self.1 }))).into_response()
+    }
+}
+
+type ApiResult<T> = std::result::Result<Json<T>, ApiError>;
+
+/// Reject requests without the API token
+async fn authorize<B>(State(state): State<ServerState>, request: Request<B>, next: Next<B>) -> Response {
+    let expected = state.token.as_deref().unwrap_or_default();
+    let given = request.headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    match given {
+        Some(token) if !expected.is_empty() && constant_time_eq(token.as_bytes(), expected.as_bytes()) => {
+            next.run(request).await
+        },
+        _ => ApiError(StatusCode::UNAUTHORIZED, "Missing or invalid API token".to_string()).into_response(),
+    }
+}
+
+/// Compare secrets without leaking where they differ through timing
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+impl ServerState {
+    /// Repositories whose bot loop fails `check`, by name
+    fn failing(&self, check: impl Fn(&Health) -> bool) -> Vec<&str> {
+        self.repositories.iter()
+            .filter(|repository| !check(&repository.health))
+            .map(|repository| repository.name.as_str())
+            .collect()
+    }
+    
+    /// Publish the queue after an API change, so the dashboard shows it straight away
+    fn publish(&self, queue: &QueueManager) {
+        *self.snapshot.write().unwrap() = queue.snapshot();
+    }
+}
+
+/// Live while the bot loop is, or with several repositories, while any of theirs is
+///
+/// A stalled repository is listed, and fails its own `/repos/owner/repo/healthz`,
+/// but doesn't get the whole bot restarted.
+async fn healthz(State(state): State<ServerState>) -> Response {
+    let now = Utc::now();
+    let stalled = state.failing(|health| health.is_live(now));
+    let live = if state.repositories.is_empty() {
+        state.health.is_live(now)
+    } else {
+        stalled.len() < state.repositories.len()
+    };
+    let status = if live { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+    let mut body = json!({
+        "status": if live { "ok" } else { "stalled" },
+        "last_tick": state.health.last_tick(),
+    });
+    if !state.repositories.is_empty() {
+        body["stalled"] = json!(stalled);
+    }
+    (status, Json(body)).into_response()
+}
+
+async fn readyz(State(state): State<ServerState>) -> Response {
+    let starting = state.failing(Health::is_ready);
+    let ready = state.health.is_ready() && starting.is_empty();
+    let status = if ready { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+    let mut body = json!({ "status": if ready { "ready" } else { "starting" } });
+    if !state.repositories.is_empty() {
+        body["starting"] = json!(starting);
+    }
+    (status, Json(body)).into_response()
+}
+
+async fn render_metrics() -> Response {
+    let content_type = [(header::CONTENT_TYPE, "text/plain; version=0.0.4")];
+    (content_type, metrics::metrics().render()).into_response()
+}
+
+async fn dashboard_queue(State(state): State<ServerState>) -> Html<String> {
+    let refresh = state.dashboard_refresh.unwrap_or_default();
+    let queue = state.snapshot.read().unwrap();
+    Html(dashboard::render_queue(&queue, &state.base, &state.names, refresh, Utc::now()))
+}
+
+async fn dashboard_review(State(state): State<ServerState>, Path(review): Path<String>) -> Response {
+    let refresh = state.dashboard_refresh.unwrap_or_default();
+    let queue = state.snapshot.read().unwrap();
+    match dashboard::render_review(&queue, &state.base, &ReviewId::new(review), refresh, Utc::now()) {
+        Some(page) => Html(page).into_response(),
+        None => {
+            let page = format!(
+                "<!DOCTYPE html>\n<title>Not found</title>\n<p>The merge queue doesn't know this review. <a href=\"{}\">Back to the queue</a></p>\n",
+                dashboard::home(&state.base),
+            );
+            (StatusCode::NOT_FOUND, Html(page)).into_response()
+        },
+    }
+}
+
+/// The merge queue, in order
+#[derive(Serialize)]
+struct QueueView {
+    paused: bool,
+    entries: Vec<QueueEntry>,
+    /// Reviews held out of the queue
+    held: Vec<ReviewId>,
+}
+
+async fn list_queue(State(state): State<ServerState>) -> ApiResult<QueueView> {
+    let queue = state.queue.lock().await;
+    let mut held: Vec<_> = queue.held().iter().cloned().collect();
+    held.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+    Ok(Json(QueueView {
+        paused: queue.is_paused(),
+        entries: queue.get_queue().iter().cloned().collect(),
+        held,
+    }))
+}
+
+/// A review's place in the queue and how its earlier runs ended
+#[derive(Serialize)]
+struct EntryView {
+    review: ReviewId,
+    /// 1-based position among reviews into the same target, if queued
+    position: Option<usize>,
+    /// How many reviews are queued into the same target
+    queue_length: Option<usize>,
+    held: bool,
+    entry: Option<QueueEntry>,
+    /// Earlier merges and failures, oldest first
+    outcomes: Vec<QueueOutcome>,
+}
+
+async fn show_entry(State(state): State<ServerState>, Path(review): Path<String>) -> ApiResult<EntryView> {
+    let review_id = ReviewId::new(review);
+    let queue = state.queue.lock().await;
+    let entry = queue.get_queue().iter().find(|e| e.review_id == review_id).cloned();
+    let outcomes: Vec<_> = queue.recent_outcomes().iter().filter(|o| o.review_id == review_id).cloned().collect();
+    let held = queue.is_held(&review_id);
+    if entry.is_none() && !held && outcomes.is_empty() {
+        return Err(GarryError::ReviewNotFound(format!("review {} in the merge queue", review_id)).into());
+    }
+    let position = queue.target_position(&review_id);
+    Ok(Json(EntryView {
+        entry,
+        position: position.map(|(p, _)| p + 1),
+        queue_length: position.map(|(_, length)| length),
+        review: review_id,
+        held,
+        outcomes,
+    }))
+}
+
+#[derive(Deserialize)]
+struct EnqueueRequest {
+    review: String,
+}
+
+/// Where a review is now
+#[derive(Serialize)]
+struct PositionView {
+    review: ReviewId,
+    /// 1-based position among reviews into the same target, if queued
+    position: Option<usize>,
+    /// How many reviews are queued into the same target
+    queue_length: Option<usize>,
+}
+
+impl PositionView {
+    fn new(queue: &QueueManager, review: ReviewId) -> Self {
+        let position = queue.target_position(&review);
+        Self {
+            review,
+            position: position.map(|(p, _)| p + 1),
+            queue_length: position.map(|(_, length)| length),
+        }
+    }
+}
+
+async fn enqueue(State(state): State<ServerState>, Json(request): Json<EnqueueRequest>) -> ApiResult<PositionView> {
+    let review_id = ReviewId::new(request.review);
+    let mut queue = state.queue.lock().await;
+    info!("Queueing review {} through the API", review_id);
+    queue.release(&review_id);
+    queue.add_to_queue(review_id.clone()).await?;
+    state.publish(&queue);
+    Ok(Json(PositionView::new(&queue, review_id)))
+}
+
+async fn dequeue(State(state): State<ServerState>, Path(review): Path<String>) -> ApiResult<PositionView> {
+    let review_id = ReviewId::new(review);
+    let mut queue = state.queue.lock().await;
+    if queue.get_queue_position(&review_id).is_none() {
+        return Err(GarryError::ReviewNotFound(format!("review {} in the merge queue", review_id)).into());
+    }
+    // Held, so discovery doesn't queue it straight back
+    info!("Dequeueing review {} through the API", review_id);
+    queue.hold(&review_id);
+    state.publish(&queue);
+    Ok(Json(PositionView::new(&queue, review_id)))
+}
+
+#[derive(Deserialize)]
+struct MoveRequest {
+    /// 1-based position to move to, among reviews into the same target
+    position: usize,
+}
+
+async fn move_entry(
+    State(state): State<ServerState>,
+    Path(review): Path<String>,
+    Json(request): Json<MoveRequest>,
+) -> ApiResult<PositionView> {
+    let review_id = ReviewId::new(review);
+    let mut queue = state.queue.lock().await;
+    queue.move_to(&review_id, request.position.saturating_sub(1))?;
+    state.publish(&queue);
+    Ok(Json(PositionView::new(&queue, review_id)))
+}
+
+async fn pause(State(state): State<ServerState>) -> ApiResult<serde_json::Value> {
+    let mut queue = state.queue.lock().await;
+    queue.pause();
+    state.publish(&queue);
+    Ok(Json(json!({ "paused": true })))
+}
+
+async fn resume(State(state): State<ServerState>) -> ApiResult<serde_json::Value> {
+    let mut queue = state.queue.lock().await;
+    queue.resume();
+    state.publish(&queue);
+    Ok(Json(json!({ "paused": false })))
+}
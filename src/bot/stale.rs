@@ -0,0 +1,109 @@
+use std::collections::HashMap;
+use chrono::{DateTime, Duration, Utc};
+use crate::bot::adapter::VcsAdapter;
+use crate::review::ReviewId;
+use crate::utils::config::BotConfig;
+use crate::utils::error::Result;
+use tracing::{info, warn};
+
+/// Posting the warning bumps a review's activity time, so only activity
+/// this long after the warning counts as a response to it
+const WARNING_GRACE_MINUTES: i64 = 5;
+
+/// What a sweep did
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SweepResult {
+    /// Reviews that were warned they'll be abandoned
+    pub warned: Vec<ReviewId>,
+    /// Reviews that were abandoned
+    pub abandoned: Vec<ReviewId>,
+}
+
+/// Abandons open reviews that have gone without activity, warning first
+///
+/// Warnings are tracked in memory, so after a restart reviews are warned
+/// again before being abandoned.
+pub struct StaleReviewSweeper {
+    abandon_after: Duration,
+    warning: Duration,
+    warned: HashMap<ReviewId, DateTime<Utc>>,
+}
+
+impl StaleReviewSweeper {
+    /// Create a sweeper from the bot config, or `None` if auto-abandon is off
+    pub fn new(config: &BotConfig) -> Option<Self> {
+        if config.abandon_after_days == 0 {
+            return None;
+        }
+        let abandon_after = Duration::days(config.abandon_after_days as i64);
+        let warning = Duration::days(config.abandon_warning_days as i64).min(abandon_after);
+        Some(Self { abandon_after, warning, warned: HashMap::new() })
+    }
+    
+    /// Warn about and abandon inactive reviews, leaving those in `skip` alone
+    ///
+    /// A review that can't be warned or abandoned is logged and retried on the
+    /// next sweep, without holding up the others.
+    pub async fn sweep(&mut self, adapter: &dyn VcsAdapter, skip: &[ReviewId], now: DateTime<Utc>) -> Result<SweepResult> {
+        let reviews = adapter.list_open_reviews().await?;
+        let mut result = SweepResult::default();
+        
+        // Forget reviews that were closed or merged in the meantime
+        self.warned.retain(|id, _| reviews.iter().any(|r| &r.id == id));
+        
+        for review in reviews {
+            if skip.contains(&review.id) {
+                self.warned.remove(&review.id);
+                continue;
+            }
+            
+            if let Some(warned_at) = self.warned.get(&review.id).copied() {
+                if review.updated_at > warned_at + Duration::minutes(WARNING_GRACE_MINUTES) {
+                    info!("Review {} saw activity after the abandon warning", review.id);
+                    self.warned.remove(&review.id);
+                } else if now - warned_at >= self.warning {
+                    // Left warned, so the next sweep tries again
+                    if let Err(e) = self.abandon(adapter, &review.id).await {
+                        warn!("Failed to abandon review {}: {}", review.id, e);
+                        continue;
+                    }
+                    self.warned.remove(&review.id);
+                    result.abandoned.push(review.id);
+                }
+                continue;
+            }
+            
+            if now - review.updated_at >= self.abandon_after - self.warning {
+                let message = format!(
+                    "This {} has had no activity for {} days and will be abandoned in {} days. \
+                     Comment or push to keep it open.",
+                    adapter.review_name(),
+                    (now - review.updated_at).num_days(),
+                    self.warning.num_days()
+                );
+                if let Err(e) = adapter.post_comment(&review.id, &message).await {
+                    warn!("Failed to warn review {} that it will be abandoned: {}", review.id, e);
+                    continue;
+                }
+                self.warned.insert(review.id.clone(), now);
+                result.warned.push(review.id);
+            }
+        }
+        
+        Ok(result)
+    }
+    
+    /// Close a review, saying why and how to bring it back
+    async fn abandon(&self, adapter: &dyn VcsAdapter, review_id: &ReviewId) -> Result<()> {
+        info!("Abandoning inactive review {}", review_id);
+        let message = format!(
+            "Abandoned after {} days without activity. Run `garry restore {}` to reopen it.",
+            self.abandon_after.num_days(),
+            review_id
+        );
+        if let Err(e) = adapter.post_comment(review_id, &message).await {
+            warn!("Failed to comment on review {} before abandoning it: {}", review_id, e);
+        }
+        adapter.close_review(review_id).await
+    }
+}
@@ -0,0 +1,269 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use crate::utils::error::{GarryError, Result};
+
+/// Variables templates can use, as `{name}`
+pub const VARIABLES: &[&str] = &[
+    "review",
+    "url",
+    "author",
+    "branch",
+    "repository",
+    "main_branch",
+    "target",
+    "event",
+    "title",
+    "checks",
+    "position",
+    "details",
+    "reply",
+    "command",
+    "reason",
+    "queue_length",
+    "state",
+    "ci",
+    "eta",
+    "report",
+];
+
+/// Locale used when no template exists for the configured one
+pub const DEFAULT_LOCALE: &str = "en";
+
+/// Built-in English templates, by event
+const DEFAULT_TEMPLATES: &[(&str, &str)] = &[
+    ("subject", "[{repository}] Review {review}: {title}"),
+    ("merge_success", "✅ Your review has been successfully merged to {target}!"),
+    ("merge_conflict", "⚠️ Merge conflict detected:\n\n{details}\n\nPlease rebase your branch and update the review."),
+    ("ci_failed", "❌ CI checks failed:\n\n{checks}\n\nPlease fix the issues and update the review."),
+    ("queue_position", "📋 Your review is in the merge queue at position {position}"),
+    ("review_requires_update", "🔄 Your review requires updates. Please address the feedback and push new commits."),
+    ("command_done", "🤖 {reply}"),
+    ("command_failed", "🚫 `{command}` failed: {reason}"),
+    ("digest_subject", "[{repository}] {report} digest"),
+    ("status", "**Garry status**\n\n- Merge queue position: {position} of {queue_length} for {target}\n- State: {state}\n- CI: {ci}\n- Estimated merge: {eta}"),
+];
+
+/// How a channel wants messages formatted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MessageFormat {
+    /// Markdown, as rendered from the templates
+    Markdown,
+    /// Text with Markdown markup removed
+    Plain,
+    /// Slack Block Kit, with the text in Slack's mrkdwn dialect
+    SlackBlocks,
+}
+
+impl MessageFormat {
+    /// Convert a rendered Markdown message to this format's text
+    pub fn render(&self, markdown: &str) -> String {
+        match self {
+            MessageFormat::Markdown => markdown.to_string(),
+            MessageFormat::Plain => to_plain(markdown),
+            MessageFormat::SlackBlocks => to_slack_mrkdwn(markdown),
+        }
+    }
+}
+
+/// Wrap a Markdown message in a Slack Block Kit section
+pub fn slack_blocks(markdown: &str) -> serde_json::Value {
+    serde_json::json!([{
+        "type": "section",
+        "text": { "type": "mrkdwn", "text": to_slack_mrkdwn(markdown) },
+    }])
+}
+
+/// A piece of a parsed template
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Text(String),
+    Variable(String),
+}
+
+/// A message template with `{variable}` placeholders; `{{` and `}}` are literal braces
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+impl Template {
+    /// Parse a template, rejecting unknown variables
+    pub fn parse(source: &str) -> Result<Self> {
+        let mut parts = Vec::new();
+        let mut text = String::new();
+        let mut chars = source.chars().peekable();
+        
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    text.push('{');
+                },
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    text.push('}');
+                },
+                '{' => {
+                    let name: String = chars.by_ref().take_while(|&c| c != '}').collect();
+                    let name = name.trim();
+                    if !VARIABLES.contains(&name) {
+                        return Err(GarryError::ConfigError(format!(
+                            "Unknown template variable '{{{}}}' (known variables: {})",
+                            name,
+                            VARIABLES.join(", ")
+                        )));
+                    }
+                    if !text.is_empty() {
+                        parts.push(Part::Text(std::mem::take(&mut text)));
+                    }
+                    parts.push(Part::Variable(name.to_string()));
+                },
+                c => text.push(c),
+            }
+        }
+        if !text.is_empty() {
+            parts.push(Part::Text(text));
+        }
+        
+        Ok(Self { parts })
+    }
+    
+    /// Whether the template uses a variable
+    pub fn uses(&self, variable: &str) -> bool {
+        self.parts.iter().any(|part| matches!(part, Part::Variable(name) if name == variable))
+    }
+    
+    /// Fill in the template; missing variables render as empty
+    pub fn render(&self, variables: &HashMap<&str, String>) -> String {
+        self.parts.iter().map(|part| match part {
+            Part::Text(text) => text.as_str(),
+            Part::Variable(name) => variables.get(name.as_str()).map(String::as_str).unwrap_or_default(),
+        }).collect()
+    }
+}
+
+/// Notification templates by locale and event
+///
+/// Templates files have a table per locale, keyed by event name:
+///
+/// ```toml
+/// [de]
+/// merge_success = "✅ {review} wurde in {main_branch} gemergt"
+/// ```
+///
+/// Lookups try the configured locale, then its language ("pt" for "pt-BR"),
+/// then English, then the built-in template.
+#[derive(Debug, Clone)]
+pub struct MessageTemplates {
+    locale: String,
+    locales: HashMap<String, HashMap<String, Template>>,
+    defaults: HashMap<String, Template>,
+}
+
+impl MessageTemplates {
+    /// Use the built-in templates, with overrides for `locale` added later
+    pub fn new(locale: &str) -> Self {
+        let defaults = DEFAULT_TEMPLATES.iter()
+            .map(|(event, source)| (event.to_string(), Template::parse(source).expect("built-in templates are valid")))
+            .collect();
+        Self { locale: locale.to_string(), locales: HashMap::new(), defaults }
+    }
+    
+    /// Load templates from a TOML file
+    pub fn load<P: AsRef<Path>>(path: P, locale: &str) -> Result<Self> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path).map_err(|e| {
+            GarryError::ConfigError(format!("Failed to read templates file {}: {}", path.display(), e))
+        })?;
+        Self::parse(&content, locale)
+    }
+    
+    /// Parse templates from TOML
+    pub fn parse(content: &str, locale: &str) -> Result<Self> {
+        let tables: HashMap<String, HashMap<String, String>> = toml::from_str(content)
+            .map_err(|e| GarryError::ConfigError(format!("Failed to parse templates: {}", e)))?;
+        
+        let mut templates = Self::new(locale);
+        for (locale, events) in tables {
+            let mut parsed = HashMap::new();
+            for (event, source) in events {
+                if !templates.defaults.contains_key(&event) {
+                    let known: Vec<&str> = DEFAULT_TEMPLATES.iter().map(|(event, _)| *event).collect();
+                    return Err(GarryError::ConfigError(format!(
+                        "Unknown template '{}.{}' (known templates: {})", locale, event, known.join(", ")
+                    )));
+                }
+                let template = Template::parse(&source).map_err(|e| match e {
+                    GarryError::ConfigError(message) => {
+                        GarryError::ConfigError(format!("In template '{}.{}': {}", locale, event, message))
+                    },
+                    e => e,
+                })?;
+                parsed.insert(event, template);
+            }
+            templates.locales.insert(locale, parsed);
+        }
+        Ok(templates)
+    }
+    
+    /// Get the template for an event
+    pub fn get(&self, event: &str) -> Option<&Template> {
+        let language = self.locale.split(['-', '_']).next().unwrap_or_default();
+        [self.locale.as_str(), language, DEFAULT_LOCALE]
+            .iter()
+            .find_map(|locale| self.locales.get(*locale).and_then(|events| events.get(event)))
+            .or_else(|| self.defaults.get(event))
+    }
+    
+    /// Render the template for an event, or nothing if there isn't one
+    pub fn render(&self, event: &str, variables: &HashMap<&str, String>) -> String {
+        self.get(event).map(|template| template.render(variables)).unwrap_or_default()
+    }
+}
+
+impl Default for MessageTemplates {
+    fn default() -> Self {
+        Self::new(DEFAULT_LOCALE)
+    }
+}
+
+/// Remove Markdown emphasis, code and link markup
+fn to_plain(markdown: &str) -> String {
+    let text = rewrite_links(markdown, |text, url| format!("{} ({})", text, url));
+    text.replace("**", "").replace("__", "").replace('`', "")
+}
+
+/// Convert Markdown to Slack's mrkdwn: single-asterisk bold and <url|text> links
+fn to_slack_mrkdwn(markdown: &str) -> String {
+    let text = rewrite_links(markdown, |text, url| format!("<{}|{}>", url, text));
+    text.replace("**", "*").replace("__", "_")
+}
+
+/// Replace every `[text](url)` link with `rewrite(text, url)`
+fn rewrite_links(markdown: &str, rewrite: impl Fn(&str, &str) -> String) -> String {
+    let mut out = String::new();
+    let mut rest = markdown;
+    
+    while let Some(start) = rest.find('[') {
+        let link = rest[start + 1..].split_once("](").and_then(|(text, after)| {
+            let (url, tail) = after.split_once(')')?;
+            (!text.contains(']')).then_some((text, url, tail))
+        });
+        match link {
+            Some((text, url, tail)) => {
+                out.push_str(&rest[..start]);
+                out.push_str(&rewrite(text, url));
+                rest = tail;
+            },
+            None => {
+                out.push_str(&rest[..=start]);
+                rest = &rest[start + 1..];
+            },
+        }
+    }
+    out.push_str(rest);
+    out
+}
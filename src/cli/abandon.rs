@@ -0,0 +1,28 @@
+use crate::utils::error::Result;
+use crate::utils::config::Config;
+use crate::review::ReviewId;
+use tracing::info;
+
+/// Execute the abandon command - closes a review without merging it
+pub async fn execute(review_id: String, reason: Option<String>) -> Result<()> {
+    info!("Abandoning review {}", review_id);
+    
+    // Load config
+    let config = Config::load_with_env()?;
+    
+    // Create adapter
+    let adapter = super::create_adapter(&config)?;
+    
+    let review_id = ReviewId::new(review_id);
+    
+    println!("Abandoning review {}...", review_id);
+    adapter.close_review(&review_id).await?;
+    if let Some(reason) = &reason {
+        adapter.post_comment(&review_id, &format!("Abandoned: {}", reason)).await?;
+    }
+    
+    println!("✓ Abandoned review {}", review_id);
+    println!("  Run 'garry restore {}' to reopen it", review_id);
+    
+    Ok(())
+}
@@ -1,34 +1,71 @@
+use std::collections::BTreeMap;
 use crate::utils::error::{GarryError, Result};
-use crate::utils::config::Config;
-use crate::bot::adapters::GithubAdapter;
-use crate::bot::adapter::VcsAdapter;
+use crate::utils::config::{Config, PolicyConfig};
 use crate::review::ReviewId;
+use crate::review::labels;
+use crate::review::roles::{Action, Roles};
 use tracing::info;
 
-/// Execute the approve command - approves a review
-pub async fn execute(review_id: String, message: Option<String>) -> Result<()> {
+/// Execute the approve command - approves a review, or votes on its labels
+pub async fn execute(review_id: String, message: Option<String>, label_votes: Vec<String>) -> Result<()> {
     info!("Approving review {}", review_id);
     
     // Load config
     let config = Config::load_with_env()?;
     
+    // Check votes before talking to the platform
+    let votes = parse_votes(&config.policy, &label_votes)?;
+    
     // Create adapter
-    let adapter: Box<dyn VcsAdapter> = match config.vcs.platform.as_str() {
-        "github" => Box::new(GithubAdapter::new(
-            config.vcs.host.clone(),
-            config.vcs.token.clone(),
-            config.vcs.repository.clone(),
-        )?),
-        _ => return Err(GarryError::VcsError(format!("Unsupported platform: {}", config.vcs.platform))),
-    };
+    let adapter = super::create_adapter(&config)?;
     
     let review_id = ReviewId::new(review_id);
     
-    println!("Approving review {}...", review_id);
-    adapter.approve_review(&review_id, message.as_deref()).await?;
+    // Anyone may vote against a review, but only maintainers may vote for it
+    if votes.is_empty() || votes.values().any(|&value| value > 0) {
+        let roles = Roles::load(config.roles.clone(), adapter.as_ref()).await;
+        let user = adapter.current_user().await?;
+        roles.check(adapter.as_ref(), &user, Action::Approve).await?;
+    }
+    
+    if votes.is_empty() {
+        println!("Approving review {}...", review_id);
+        adapter.approve_review(&review_id, message.as_deref()).await?;
+        
+        println!("✓ Successfully approved review {}", review_id);
+        println!("  The bot will automatically merge it when CI passes");
+        return Ok(());
+    }
     
-    println!("✓ Successfully approved review {}", review_id);
-    println!("  The bot will automatically merge it when CI passes");
+    let decision = labels::decision_for(&config.policy.labels, &config.policy.review_label, &votes);
+    println!("Voting on review {}...", review_id);
+    adapter.submit_votes(&review_id, decision, &votes, message.as_deref()).await?;
+    
+    println!("✓ Voted on review {}", review_id);
+    for (label, value) in &votes {
+        println!("  {} {:+}", label, value);
+    }
     
     Ok(())
 }
+
+/// Parse `Label=Value` votes, checking them against the configured labels
+fn parse_votes(policy: &PolicyConfig, votes: &[String]) -> Result<BTreeMap<String, i8>> {
+    let mut parsed = BTreeMap::new();
+    
+    for vote in votes {
+        let (label, value) = labels::parse_vote(vote)?;
+        let definition = policy.labels.iter().find(|l| l.name == label).ok_or_else(|| {
+            let known: Vec<&str> = policy.labels.iter().map(|l| l.name.as_str()).collect();
+            GarryError::InvalidVote(format!("unknown label '{}' (known labels: {})", label, known.join(", ")))
+        })?;
+        if value < definition.min || value > definition.max {
+            return Err(GarryError::InvalidVote(format!(
+                "{} votes range from {:+} to {:+}, got {:+}", label, definition.min, definition.max, value
+            )));
+        }
+        parsed.insert(label, value);
+    }
+    
+    Ok(parsed)
+}
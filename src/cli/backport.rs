@@ -0,0 +1,47 @@
+use crate::utils::error::{GarryError, Result};
+use crate::utils::config::Config;
+use crate::bot::backport::{self, BackportOutcome};
+use crate::git::GitRepository;
+use crate::review::ReviewId;
+use tracing::info;
+
+/// Execute the backport command - cherry-picks a merged review onto other branches
+pub async fn execute(review_id: String, targets: Vec<String>) -> Result<()> {
+    info!("Backporting review {} to {}", review_id, targets.join(", "));
+    
+    // Load config
+    let config = Config::load_with_env()?;
+    let adapter = super::create_adapter(&config)?;
+    let review_id = ReviewId::new(review_id);
+    
+    // The cherry-pick happens in the current repository without touching its working tree
+    let checkout = GitRepository::open_current()?.inner().path().to_path_buf();
+    let review_name = adapter.review_name();
+    
+    let mut conflicted = Vec::new();
+    for target in &targets {
+        println!("Backporting review {} to {}...", review_id, target);
+        match backport::backport(adapter.as_ref(), &checkout, &config.git.default_remote, &review_id, target).await? {
+            BackportOutcome::Opened { review_id: backport_id, url } => {
+                println!("✓ Opened {} #{} into {}", review_name, backport_id, target);
+                println!("  Branch: {}", backport::branch_name(&review_id, target));
+                println!("  {} URL: {}", review_name, url);
+            },
+            BackportOutcome::Conflicts(files) => {
+                println!("✗ Cherry-picking into {} conflicts in:", target);
+                for file in &files {
+                    println!("    {}", file);
+                }
+                println!("  Reported on review {}", review_id);
+                conflicted.push(target.clone());
+            },
+        }
+    }
+    
+    if !conflicted.is_empty() {
+        return Err(GarryError::MergeConflict(format!(
+            "review {} doesn't apply cleanly to {}", review_id, conflicted.join(", ")
+        )));
+    }
+    Ok(())
+}
@@ -0,0 +1,75 @@
+use crate::utils::error::{GarryError, Result};
+use crate::utils::config::Config;
+use crate::git::GitRepository;
+use crate::review::ReviewId;
+use tracing::{info, warn};
+
+/// Execute the checkout command - fetches a review and checks it out locally
+pub async fn execute(review_id: String, detach: bool, patchset: Option<u32>) -> Result<()> {
+    info!("Checking out review {}", review_id);
+    
+    // Load config
+    let config = Config::load_with_env()?;
+    let remote = &config.git.default_remote;
+    let repo = GitRepository::open_current()?;
+    
+    // Earlier revisions are only kept as patchset refs, so check them out detached
+    if let Some(number) = patchset {
+        println!("Fetching patchset {} of review {}...", number, review_id);
+        let oid = repo.fetch_patchset(remote, &review_id, number)
+            .map_err(|e| GarryError::ReviewNotFound(format!(
+                "patchset {} of review {} ({})", number, review_id, e
+            )))?;
+        repo.checkout_detached(oid)?;
+        
+        println!("✓ Checked out patchset {} of review {} (detached HEAD)", number, review_id);
+        println!("  Commit: {}", oid);
+        return Ok(());
+    }
+    
+    let adapter = super::create_adapter(&config)?;
+    let review_id = ReviewId::new(review_id);
+    let head = adapter.get_review_head(&review_id).await?;
+    
+    // Same-repository reviews are fetched as a remote-tracking branch so the
+    // local branch can track it; forks are fetched into a garry-owned ref
+    let (fetched_ref, upstream, local_branch) = match (&head.fork_url, &head.review_ref) {
+        (None, _) => {
+            println!("Fetching branch '{}' from {}...", head.branch, remote);
+            let tracking_ref = format!("refs/remotes/{}/{}", remote, head.branch);
+            repo.fetch(remote, &[&format!("+refs/heads/{}:{}", head.branch, tracking_ref)])?;
+            (tracking_ref, Some(format!("{}/{}", remote, head.branch)), head.branch.clone())
+        },
+        (Some(_), Some(review_ref)) => {
+            println!("Fetching {} from {}...", review_ref, remote);
+            let local_ref = format!("refs/garry/reviews/{}", review_id);
+            repo.fetch(remote, &[&format!("+{}:{}", review_ref, local_ref)])?;
+            (local_ref, None, format!("review/{}", review_id))
+        },
+        (Some(fork_url), None) => {
+            println!("Fetching branch '{}' from fork {}...", head.branch, fork_url);
+            let local_ref = format!("refs/garry/reviews/{}", review_id);
+            repo.fetch_url(fork_url, &[&format!("+refs/heads/{}:{}", head.branch, local_ref)])?;
+            (local_ref, None, format!("review/{}", review_id))
+        },
+    };
+    
+    let oid = repo.resolve_ref(&fetched_ref)?;
+    if !head.sha.is_empty() && oid.to_string() != head.sha {
+        warn!("Fetched {} but review {} head is {}", oid, review_id, head.sha);
+    }
+    
+    if detach {
+        repo.checkout_detached(oid)?;
+        println!("✓ Checked out review {} (detached HEAD)", review_id);
+    } else {
+        repo.checkout_branch_at(&local_branch, oid, upstream.as_deref())?;
+        println!("✓ Checked out review {} on branch '{}'", review_id, local_branch);
+        if let Some(upstream) = &upstream {
+            println!("  Tracking: {}", upstream);
+        }
+    }
+    println!("  Commit: {}", oid);
+    
+    Ok(())
+}
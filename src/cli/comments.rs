@@ -0,0 +1,80 @@
+use crate::utils::error::{GarryError, Result};
+use crate::utils::config::Config;
+use crate::git::GitRepository;
+use crate::review::{CommentThread, DiffSide};
+use tracing::{debug, info};
+
+/// Execute the comments command - lists review comment threads for the current branch
+pub async fn execute(all: bool) -> Result<()> {
+    // Load config
+    let config = Config::load_with_env()?;
+    
+    // Find the current branch's review
+    let repo = GitRepository::open_current()?;
+    let branch = repo.get_current_branch()?;
+    let adapter = super::create_adapter(&config)?;
+    let (review_id, _) = adapter.find_review_for_branch(&branch).await?
+        .ok_or_else(|| GarryError::ReviewNotFound(format!("open review for branch '{}'", branch)))?;
+    info!("Listing comment threads for review {}", review_id);
+    
+    let threads: Vec<CommentThread> = adapter.list_comment_threads(&review_id).await?
+        .into_iter()
+        .filter(|t| all || !t.resolved)
+        .collect();
+    
+    if threads.is_empty() {
+        println!("No {}comment threads on review {}", if all { "" } else { "unresolved " }, review_id);
+        return Ok(());
+    }
+    
+    // Comments may have been made on an older patchset
+    if let Err(e) = repo.fetch_patchsets(&config.git.default_remote, review_id.as_str()) {
+        debug!("Could not fetch patchsets of review {}: {}", review_id, e);
+    }
+    
+    for thread in &threads {
+        let mut notes = Vec::new();
+        if thread.resolved {
+            notes.push("resolved");
+        }
+        if thread.outdated {
+            notes.push("outdated");
+        }
+        let notes = if notes.is_empty() { String::new() } else { format!(" ({})", notes.join(", ")) };
+        
+        println!("{}{}", location(&repo, thread), notes);
+        for comment in &thread.comments {
+            println!("  {}: {}", comment.author, comment.body.trim().replace('\n', "\n    "));
+        }
+        println!();
+    }
+    
+    let unresolved = threads.iter().filter(|t| !t.resolved).count();
+    println!("{} unresolved thread(s) on review {}", unresolved, review_id);
+    
+    Ok(())
+}
+
+/// Where a thread's line is in the working tree
+fn location(repo: &GitRepository, thread: &CommentThread) -> String {
+    let line = match thread.line {
+        Some(line) => line,
+        None => return thread.file.clone(),
+    };
+    
+    // Comments on removed lines have nothing to point at locally
+    if thread.side == DiffSide::Old {
+        return format!("{} (removed line {})", thread.file, line);
+    }
+    
+    let mapped = thread.commit_id.as_deref().map(|commit| repo.map_line(commit, &thread.file, line));
+    match mapped {
+        Some(Ok(Some(local))) => format!("{}:{}", thread.file, local),
+        Some(Ok(None)) => format!("{} (line {} has changed locally)", thread.file, line),
+        Some(Err(e)) => {
+            debug!("Could not map {}:{} to the working tree: {}", thread.file, line, e);
+            format!("{}:{}", thread.file, line)
+        },
+        None => format!("{}:{}", thread.file, line),
+    }
+}
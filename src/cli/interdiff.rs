@@ -0,0 +1,39 @@
+use crate::utils::error::{GarryError, Result};
+use crate::utils::config::Config;
+use crate::git::GitRepository;
+use crate::review::ReviewId;
+use tracing::info;
+
+/// Execute the interdiff command - shows what changed between two patchsets
+pub async fn execute(review_id: String, from: Option<u32>, to: Option<u32>) -> Result<()> {
+    info!("Showing interdiff for review {}", review_id);
+    
+    // Load config
+    let config = Config::load_with_env()?;
+    let repo = GitRepository::open_current()?;
+    
+    let patchsets = repo.fetch_patchsets(&config.git.default_remote, &review_id)?;
+    let latest = patchsets.last().map(|(number, _)| *number)
+        .ok_or_else(|| GarryError::ReviewNotFound(format!("patchsets of review {}", review_id)))?;
+    
+    // Default to the latest patchset against the one before it
+    let to = to.unwrap_or(latest);
+    let from = match from {
+        Some(from) => from,
+        None if to > 1 => to - 1,
+        None => return Err(GarryError::Other(format!("Review {} has only one patchset", review_id))),
+    };
+    
+    // Patchsets are compared against the branch the review merges into
+    let adapter = super::create_adapter(&config)?;
+    let target = adapter.get_review_status(&ReviewId::new(review_id.clone())).await?.target_branch;
+    let interdiff = repo.interdiff(&review_id, &config.base_for(&target), from, to)?;
+    
+    print!("{}", interdiff.summary());
+    if !interdiff.patch.is_empty() {
+        println!();
+        print!("{}", interdiff.patch);
+    }
+    
+    Ok(())
+}
@@ -0,0 +1,172 @@
+use crate::utils::error::Result;
+use crate::utils::config::Config;
+use crate::cli::queue::{self, QueuePositions};
+use crate::review::{ReviewId, ReviewState, CiStatus};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tracing::info;
+
+/// Filters for the list command; all enabled filters must match
+#[derive(Debug, Clone, Default)]
+pub struct ListFilters {
+    /// Only reviews authored by the current user
+    pub mine: bool,
+    /// Only reviews where the current user's review is requested
+    pub needs_review: bool,
+    /// Only approved reviews
+    pub approved: bool,
+    /// Only reviews with failing CI
+    pub failing: bool,
+    /// Only reviews the merge queue would pick up
+    pub queued: bool,
+}
+
+/// A review as shown by the list command
+#[derive(Debug, Serialize)]
+struct ReviewRow {
+    id: ReviewId,
+    title: String,
+    author: String,
+    branch: String,
+    url: String,
+    updated_at: DateTime<Utc>,
+    state: ReviewState,
+    ci_status: CiStatus,
+    approvals: Vec<String>,
+    requested_reviewers: Vec<String>,
+    /// Position in the merge queue among reviews into the same branch (1-based)
+    queue_position: Option<usize>,
+    /// The position is an estimate rather than the bot's
+    queue_position_estimated: bool,
+}
+
+/// Execute the list command - lists open reviews
+pub async fn execute(filters: ListFilters, json: bool) -> Result<()> {
+    info!("Listing open reviews");
+    
+    // Load config
+    let config = Config::load_with_env()?;
+    let adapter = super::create_adapter(&config)?;
+    
+    // Evaluate approvals the same way the bot does
+    let queue = queue::policy_queue(&config, adapter).await?;
+    let adapter = queue.adapter();
+    
+    let me = if filters.mine || filters.needs_review {
+        Some(adapter.current_user().await?)
+    } else {
+        None
+    };
+    
+    let bot_positions = QueuePositions::from_bot(&config).await?;
+    let mut rows = Vec::new();
+    let mut queueable = Vec::new();
+    
+    for summary in adapter.list_open_reviews().await? {
+        let status = queue.review_status(&summary.id).await?;
+        let decision = queue.policy_for(&status.target_branch)
+            .or_else(|| queue.policy_for(&config.bot.main_branch))
+            .map(|policy| policy.evaluate(&status))
+            .expect("the main branch always has a policy");
+        if queue::is_queueable(&queue, &status) {
+            queueable.push((summary.id.clone(), status.target_branch.clone()));
+        }
+        
+        rows.push(ReviewRow {
+            id: summary.id,
+            title: summary.title,
+            author: summary.author,
+            branch: summary.branch,
+            url: summary.url,
+            updated_at: summary.updated_at,
+            state: decision.state,
+            ci_status: status.ci_status,
+            approvals: decision.approvals.into_iter().map(|a| a.reviewer).collect(),
+            requested_reviewers: summary.requested_reviewers,
+            queue_position: None,
+            queue_position_estimated: false,
+        });
+    }
+    
+    // Without the bot's queue, estimate it the way the bot discovers reviews
+    let positions = bot_positions.unwrap_or_else(|| QueuePositions::estimate(queueable));
+    for row in &mut rows {
+        row.queue_position = positions.position(&row.id);
+        row.queue_position_estimated = positions.is_estimated();
+    }
+    
+    rows.retain(|row| {
+        let me = me.as_deref().unwrap_or_default();
+        (!filters.mine || row.author == me)
+            && (!filters.needs_review || row.requested_reviewers.iter().any(|r| r == me))
+            && (!filters.approved || row.state == ReviewState::Approved)
+            && (!filters.failing || matches!(row.ci_status, CiStatus::Failed(_)))
+            && (!filters.queued || row.queue_position.is_some())
+    });
+    
+    if json {
+        println!("{}", serde_json::to_string_pretty(&rows)?);
+        return Ok(());
+    }
+    
+    if rows.is_empty() {
+        println!("No matching {}s", adapter.review_name());
+        return Ok(());
+    }
+    
+    print_table(&rows);
+    if positions.is_estimated() && rows.iter().any(|row| row.queue_position.is_some()) {
+        println!();
+        println!("QUEUE positions are estimates; set api.url to ask the bot");
+    }
+    Ok(())
+}
+
+/// Print reviews as an aligned table
+fn print_table(rows: &[ReviewRow]) {
+    let header = ["ID", "TITLE", "AUTHOR", "BRANCH", "STATE", "CI", "QUEUE", "UPDATED"];
+    let cells: Vec<[String; 8]> = rows
+        .iter()
+        .map(|row| [
+            format!("#{}", row.id),
+            truncate(&row.title, 50),
+            row.author.clone(),
+            row.branch.clone(),
+            format!("{:?}", row.state),
+            super::status::ci_label(&row.ci_status).to_string(),
+            row.queue_position.map(|p| p.to_string()).unwrap_or_else(|| "-".to_string()),
+            row.updated_at.format("%Y-%m-%d %H:%M").to_string(),
+        ])
+        .collect();
+    
+    let mut widths = header.map(|h| h.chars().count());
+    for row in &cells {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    
+    let format_line = |line: Vec<&str>| {
+        line.iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+            .collect::<Vec<_>>()
+            .join("  ")
+            .trim_end()
+            .to_string()
+    };
+    
+    println!("{}", format_line(header.to_vec()));
+    for row in &cells {
+        println!("{}", format_line(row.iter().map(String::as_str).collect()));
+    }
+}
+
+/// Shorten a string to at most `max` characters
+fn truncate(s: &str, max: usize) -> String {
+    if s.chars().count() <= max {
+        s.to_string()
+    } else {
+        format!("{}…", s.chars().take(max - 1).collect::<String>())
+    }
+}
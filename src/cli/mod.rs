@@ -3,9 +3,23 @@ mod squash;
 mod upload;
 mod update;
 mod approve;
+mod status;
+mod list;
+mod checkout;
+mod interdiff;
+mod abandon;
+mod restore;
+mod ready;
+mod comments;
+mod report;
+mod backport;
+mod queue;
 
 use clap::Subcommand;
-use crate::utils::error::Result;
+use crate::bot::adapter::VcsAdapter;
+use crate::bot::adapters::GithubAdapter;
+use crate::utils::config::Config;
+use crate::utils::error::{GarryError, Result};
 
 #[derive(Subcommand)]
 pub enum Command {
@@ -24,6 +38,21 @@ pub enum Command {
         /// Optional description for the review
         #[arg(short, long)]
         description: Option<String>,
+        /// Skip presubmit checks
+        #[arg(long)]
+        no_verify: bool,
+        /// Request a review from a user or team ("org/team"); repeatable
+        #[arg(short, long = "reviewer")]
+        reviewers: Vec<String>,
+        /// Also request reviewers suggested from code owners and blame history
+        #[arg(long)]
+        suggest_reviewers: bool,
+        /// Create a draft review that the merge queue never picks up
+        #[arg(long)]
+        draft: bool,
+        /// Branch the review merges into, e.g. release/2.3 (default: the main branch)
+        #[arg(long, value_name = "BRANCH")]
+        target: Option<String>,
     },
     /// Update an existing review with new changes
     Update,
@@ -34,6 +63,111 @@ pub enum Command {
         /// Optional approval message
         #[arg(short, long)]
         message: Option<String>,
+        /// Vote on a label instead of plain approval, e.g. Code-Review=+2 (repeatable)
+        #[arg(short, long = "label", value_name = "LABEL=VALUE")]
+        labels: Vec<String>,
+    },
+    /// Show the review, CI and queue status of the current branch
+    Status,
+    /// List open reviews
+    List {
+        /// Only show reviews you authored
+        #[arg(long)]
+        mine: bool,
+        /// Only show reviews waiting on your review
+        #[arg(long)]
+        needs_review: bool,
+        /// Only show approved reviews
+        #[arg(long)]
+        approved: bool,
+        /// Only show reviews with failing CI
+        #[arg(long)]
+        failing: bool,
+        /// Only show reviews the merge queue would pick up
+        #[arg(long)]
+        queued: bool,
+        /// Output as JSON instead of a table
+        #[arg(long)]
+        json: bool,
+    },
+    /// Fetch a review and check it out locally
+    Checkout {
+        /// Review ID to check out
+        review_id: String,
+        /// Check out with a detached HEAD instead of a local branch
+        #[arg(long)]
+        detach: bool,
+        /// Check out an earlier patchset of the review (implies --detach)
+        #[arg(long)]
+        patchset: Option<u32>,
+    },
+    /// Mark a draft review as ready for review
+    Ready {
+        /// Review ID (default: the current branch's review)
+        review_id: Option<String>,
+    },
+    /// Close a review without merging it
+    Abandon {
+        /// Review ID to abandon
+        review_id: String,
+        /// Why the review is being abandoned, posted as a comment
+        #[arg(short, long)]
+        reason: Option<String>,
+    },
+    /// Reopen an abandoned review
+    Restore {
+        /// Review ID to restore
+        review_id: String,
+        /// Why the review is being restored, posted as a comment
+        #[arg(short, long)]
+        reason: Option<String>,
+    },
+    /// Show what changed between two patchsets of a review
+    Interdiff {
+        /// Review ID to compare
+        review_id: String,
+        /// Older patchset (default: the one before --to)
+        #[arg(long)]
+        from: Option<u32>,
+        /// Newer patchset (default: the latest)
+        #[arg(long)]
+        to: Option<u32>,
+    },
+    /// List review comment threads on the current branch's review
+    Comments {
+        /// Include resolved threads
+        #[arg(long)]
+        all: bool,
+    },
+    /// Cherry-pick a merged review onto other branches and open reviews of it
+    Backport {
+        /// Review ID to backport
+        review_id: String,
+        /// Branch to backport to, e.g. release/2.3; repeatable
+        #[arg(long = "to", value_name = "BRANCH", required = true)]
+        targets: Vec<String>,
+    },
+    /// Show reports about the repository's reviews
+    Report {
+        #[command(subcommand)]
+        report: ReportCommand,
+    },
+}
+
+/// Reports shown by `garry report`
+#[derive(Subcommand)]
+pub enum ReportCommand {
+    /// Show the review digest: reviews waiting on people and the oldest unreviewed ones
+    Digest {
+        /// Only reviews by or waiting on this user or team ("org/team"); repeatable
+        #[arg(short, long = "member")]
+        members: Vec<String>,
+        /// Only reviews by or waiting on you
+        #[arg(long)]
+        mine: bool,
+        /// Output as JSON instead of Markdown
+        #[arg(long)]
+        json: bool,
     },
 }
 
@@ -42,8 +176,48 @@ pub async fn execute(command: Command) -> Result<()> {
     match command {
         Command::Start { branch_name } => start::execute(&branch_name).await,
         Command::Squash => squash::execute().await,
-        Command::Upload { title, description } => upload::execute(title, description).await,
+        Command::Upload { title, description, no_verify, reviewers, suggest_reviewers, draft, target } => {
+            let options = upload::UploadOptions { title, description, no_verify, reviewers, suggest_reviewers, draft, target };
+            upload::execute(options).await
+        },
         Command::Update => update::execute().await,
-        Command::Approve { review_id, message } => approve::execute(review_id, message).await,
+        Command::Approve { review_id, message, labels } => approve::execute(review_id, message, labels).await,
+        Command::Status => status::execute().await,
+        Command::List { mine, needs_review, approved, failing, queued, json } => {
+            let filters = list::ListFilters { mine, needs_review, approved, failing, queued };
+            list::execute(filters, json).await
+        },
+        Command::Checkout { review_id, detach, patchset } => checkout::execute(review_id, detach, patchset).await,
+        Command::Ready { review_id } => ready::execute(review_id).await,
+        Command::Abandon { review_id, reason } => abandon::execute(review_id, reason).await,
+        Command::Restore { review_id, reason } => restore::execute(review_id, reason).await,
+        Command::Interdiff { review_id, from, to } => interdiff::execute(review_id, from, to).await,
+        Command::Comments { all } => comments::execute(all).await,
+        Command::Backport { review_id, targets } => backport::execute(review_id, targets).await,
+        Command::Report { report: ReportCommand::Digest { members, mine, json } } => {
+            report::digest(members, mine, json).await
+        },
+    }
+}
+
+/// Ref the changes on `branch` are based on: its review's target branch, or
+/// the main branch without a review
+pub(crate) async fn review_base(config: &Config, adapter: &dyn VcsAdapter, branch: &str) -> Result<String> {
+    let target = match adapter.find_review_for_branch(branch).await? {
+        Some((review_id, _)) => adapter.get_review_status(&review_id).await?.target_branch,
+        None => String::new(),
+    };
+    Ok(config.base_for(&target))
+}
+
+/// Create the VCS adapter for the configured platform
+pub(crate) fn create_adapter(config: &Config) -> Result<Box<dyn VcsAdapter>> {
+    match config.vcs.platform.as_str() {
+        "github" => Ok(Box::new(GithubAdapter::new(
+            config.vcs.host.clone(),
+            config.vcs.token.clone(),
+            config.vcs.repository.clone(),
+        )?.with_threads_required(config.blocks_on_unresolved_threads()))),
+        _ => Err(GarryError::VcsError(format!("Unsupported platform: {}", config.vcs.platform))),
     }
 }
@@ -0,0 +1,121 @@
+use std::collections::HashMap;
+use serde::Deserialize;
+use crate::utils::error::{GarryError, Result};
+use crate::utils::config::Config;
+use crate::bot::VcsAdapter;
+use crate::bot::queue::QueueManager;
+use crate::review::{ReviewId, ReviewStatus};
+use crate::review::owners::{self, CodeOwners};
+use crate::review::policy::ApprovalPolicy;
+use crate::review::roles::Roles;
+use tracing::debug;
+
+/// Where reviews stand in the merge queue, as far as the CLI can tell
+///
+/// The queue lives in the bot. With `api.url` configured the positions come
+/// from the bot itself; otherwise they're estimated from the reviews the bot
+/// would queue, which doesn't know about holds, priorities, failures or
+/// entries already being tested.
+pub(crate) struct QueuePositions {
+    /// 1-based position of each queued review among reviews into the same target
+    positions: HashMap<ReviewId, usize>,
+    estimated: bool,
+}
+
+impl QueuePositions {
+    /// Ask the bot's API for the queue, or `None` if the CLI isn't configured to
+    pub async fn from_bot(config: &Config) -> Result<Option<Self>> {
+        #[derive(Deserialize)]
+        struct QueueView {
+            entries: Vec<Entry>,
+        }
+
+        #[derive(Deserialize)]
+        struct Entry {
+            review_id: ReviewId,
+            #[serde(default)]
+            target: String,
+        }
+
+        let (Some(url), Some(token)) = (&config.api.url, &config.api.token) else {
+            return Ok(None);
+        };
+        let url = format!("{}/api/queue", url.trim_end_matches('/'));
+        debug!("Fetching the merge queue from {}", url);
+        let response = reqwest::Client::new().get(&url).bearer_auth(token).send().await?;
+        if !response.status().is_success() {
+            let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(GarryError::VcsError(format!("Failed to get the merge queue from the bot: {}", error_text)));
+        }
+        let queue: QueueView = response.json().await?;
+        Ok(Some(Self::ordered(queue.entries.into_iter().map(|e| (e.review_id, e.target)), false)))
+    }
+
+    /// Estimate positions from queueable reviews, taken in the order the bot discovers them
+    pub fn estimate(queueable: impl IntoIterator<Item = (ReviewId, String)>) -> Self {
+        Self::ordered(queueable, true)
+    }
+
+    /// Number reviews in order, separately for each target
+    fn ordered(reviews: impl IntoIterator<Item = (ReviewId, String)>, estimated: bool) -> Self {
+        let mut lengths: HashMap<String, usize> = HashMap::new();
+        let positions = reviews.into_iter().map(|(review_id, target)| {
+            let length = lengths.entry(target).or_insert(0);
+            *length += 1;
+            (review_id, *length)
+        }).collect();
+        Self { positions, estimated }
+    }
+
+    /// 1-based position of a review among those into the same target
+    pub fn position(&self, review_id: &ReviewId) -> Option<usize> {
+        self.positions.get(review_id).copied()
+    }
+
+    /// Whether the positions are estimates rather than the bot's
+    pub fn is_estimated(&self) -> bool {
+        self.estimated
+    }
+}
+
+/// A queue set up with the bot's policies, for deciding what it would queue
+///
+/// Code owners come from the main branch and only maintainers' approvals
+/// count, as in the bot.
+pub(crate) async fn policy_queue(config: &Config, adapter: Box<dyn VcsAdapter>) -> Result<QueueManager> {
+    let code_owners = CodeOwners::from_adapter(adapter.as_ref(), &config.bot.main_branch).await?;
+    let team_members = owners::resolve_team_members(adapter.as_ref(), &code_owners).await;
+    let policy = ApprovalPolicy::new(config.policy.clone()).with_owners(code_owners.clone(), team_members.clone());
+    let roles = Roles::load(config.roles.clone(), adapter.as_ref()).await;
+    let mut queue = QueueManager::new(adapter)
+        .with_main_branch(config.bot.main_branch.clone())
+        .with_policy(policy)
+        .with_roles(roles);
+    for target in &config.targets {
+        let policy = target.policy.clone().unwrap_or_else(|| config.policy.clone());
+        let policy = ApprovalPolicy::new(policy).with_owners(code_owners.clone(), team_members.clone());
+        queue = queue.with_target(target.branch.clone(), policy);
+    }
+    Ok(queue)
+}
+
+/// Whether the bot would queue a review with this status
+pub(crate) fn is_queueable(queue: &QueueManager, status: &ReviewStatus) -> bool {
+    queue.policy_for(&status.target_branch)
+        .is_some_and(|policy| QueueManager::is_queueable(policy, status))
+}
+
+/// Get the bot's queue positions, or estimate them by walking every open review
+pub(crate) async fn positions(config: &Config, queue: &QueueManager) -> Result<QueuePositions> {
+    if let Some(positions) = QueuePositions::from_bot(config).await? {
+        return Ok(positions);
+    }
+    let mut queueable = Vec::new();
+    for summary in queue.adapter().list_open_reviews().await? {
+        let status = queue.review_status(&summary.id).await?;
+        if is_queueable(queue, &status) {
+            queueable.push((summary.id, status.target_branch));
+        }
+    }
+    Ok(QueuePositions::estimate(queueable))
+}
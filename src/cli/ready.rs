@@ -0,0 +1,34 @@
+use crate::utils::error::{GarryError, Result};
+use crate::utils::config::Config;
+use crate::git::GitRepository;
+use crate::review::ReviewId;
+use tracing::info;
+
+/// Execute the ready command - marks a draft review as ready for review
+pub async fn execute(review_id: Option<String>) -> Result<()> {
+    // Load config
+    let config = Config::load_with_env()?;
+    
+    // Create adapter
+    let adapter = super::create_adapter(&config)?;
+    
+    // Default to the current branch's review
+    let review_id = match review_id {
+        Some(id) => ReviewId::new(id),
+        None => {
+            let branch = GitRepository::open_current()?.get_current_branch()?;
+            adapter.find_review_for_branch(&branch).await?
+                .map(|(id, _)| id)
+                .ok_or_else(|| GarryError::ReviewNotFound(format!("open review for branch '{}'", branch)))?
+        },
+    };
+    info!("Marking review {} ready for review", review_id);
+    
+    println!("Marking review {} ready for review...", review_id);
+    adapter.mark_ready_for_review(&review_id).await?;
+    
+    println!("✓ Review {} is ready for review", review_id);
+    println!("  The bot will queue it once it's approved and CI passes");
+    
+    Ok(())
+}
@@ -0,0 +1,34 @@
+use crate::utils::error::Result;
+use crate::utils::config::Config;
+use crate::bot::digest::{self, DigestCollector, DEFAULT_REPORT};
+use tracing::info;
+
+/// Execute the report digest command - prints the digest the bot would send
+///
+/// The merge queue lives in the bot, so the queue sections are left out.
+pub async fn digest(members: Vec<String>, mine: bool, json: bool) -> Result<()> {
+    info!("Collecting review digest");
+    
+    // Load config
+    let config = Config::load_with_env()?;
+    let adapter = super::create_adapter(&config)?;
+    
+    let mut members = digest::resolve_members(adapter.as_ref(), &members).await;
+    if mine {
+        members.push(adapter.current_user().await?);
+    }
+    
+    let collector = DigestCollector::new(&config.digest);
+    let mut digest = collector.collect(adapter.as_ref(), None, chrono::Utc::now()).await?;
+    if !members.is_empty() {
+        digest = digest.for_members(&members);
+    }
+    
+    if json {
+        println!("{}", serde_json::to_string_pretty(&digest)?);
+        return Ok(());
+    }
+    
+    print!("{}", digest.to_markdown(DEFAULT_REPORT));
+    Ok(())
+}
@@ -0,0 +1,27 @@
+use crate::utils::error::Result;
+use crate::utils::config::Config;
+use crate::review::ReviewId;
+use tracing::info;
+
+/// Execute the restore command - reopens an abandoned review
+pub async fn execute(review_id: String, reason: Option<String>) -> Result<()> {
+    info!("Restoring review {}", review_id);
+    
+    // Load config
+    let config = Config::load_with_env()?;
+    
+    // Create adapter
+    let adapter = super::create_adapter(&config)?;
+    
+    let review_id = ReviewId::new(review_id);
+    
+    println!("Restoring review {}...", review_id);
+    adapter.reopen_review(&review_id).await?;
+    if let Some(reason) = &reason {
+        adapter.post_comment(&review_id, &format!("Restored: {}", reason)).await?;
+    }
+    
+    println!("✓ Restored review {}", review_id);
+    
+    Ok(())
+}
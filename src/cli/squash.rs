@@ -10,11 +10,15 @@ pub async fn execute() -> Result<()> {
     let repo = GitRepository::open_current()?;
     let current_branch = repo.get_current_branch()?;
     
-    // Load config to get base branch
+    // Squash onto the branch the review merges into; without a review, or
+    // without a platform to ask, onto the main branch
     let config = Config::load_with_env().unwrap_or_default();
-    let base = &config.git.squash_base;
+    let base = match super::create_adapter(&config) {
+        Ok(adapter) => super::review_base(&config, adapter.as_ref(), &current_branch).await?,
+        Err(_) => config.base_for(""),
+    };
     
-    let count = repo.get_commit_count(base)?;
+    let count = repo.get_commit_count(&base)?;
     
     if count == 0 {
         println!("✓ No commits to squash (branch is up to date with {})", base);
@@ -27,7 +31,7 @@ pub async fn execute() -> Result<()> {
     }
     
     println!("Squashing {} commits on branch '{}'...", count, current_branch);
-    repo.squash_commits(base)?;
+    repo.squash_commits(&base)?;
     
     println!("✓ Successfully squashed {} commits into one", count);
     println!("  Your changes are now in a single clean commit");
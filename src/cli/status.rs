@@ -0,0 +1,132 @@
+use crate::utils::error::Result;
+use crate::utils::config::Config;
+use crate::git::GitRepository;
+use crate::bot::queue::QueueManager;
+use crate::cli::queue::{self, QueuePositions};
+use crate::review::CiStatus;
+use crate::review::owners;
+use crate::review::policy::ApprovalPolicy;
+use tracing::info;
+
+/// Execute the status command - shows where the current change stands
+pub async fn execute() -> Result<()> {
+    info!("Showing status of current branch");
+    
+    // Load config
+    let config = Config::load_with_env()?;
+    
+    // Local branch state
+    let repo = GitRepository::open_current()?;
+    let branch = repo.get_current_branch()?;
+    let remote = &config.git.default_remote;
+    
+    // The review, if any, says which branch the changes are based on
+    let adapter = super::create_adapter(&config)?;
+    let review_name = adapter.review_name().to_string();
+    let review = match adapter.find_review_for_branch(&branch).await? {
+        Some((review_id, review_url)) => {
+            let status = adapter.get_review_status(&review_id).await?;
+            Some((review_id, review_url, status))
+        },
+        None => None,
+    };
+    let base = &config.base_for(review.as_ref().map_or("", |(_, _, status)| status.target_branch.as_str()));
+    
+    println!("Branch: {}", branch);
+    match repo.remote_sync_state(&branch, remote)? {
+        Some(state) => println!("  vs {}/{}: {}", remote, branch, state),
+        None => println!("  vs {}/{}: not pushed", remote, branch),
+    }
+    println!("  vs {}: {}", base, repo.base_sync_state(base)?);
+    
+    // Review state
+    let Some((review_id, review_url, mut status)) = review else {
+        println!();
+        println!("No open {} for this branch", review_name);
+        println!("  Run 'garry upload' to create one");
+        return Ok(());
+    };
+    let checks = adapter.get_ci_checks(&review_id).await?;
+    
+    // Ownership comes from the base branch and applies to the local diff
+    let code_owners = repo.code_owners(base)?;
+    let team_members = owners::resolve_team_members(adapter.as_ref(), &code_owners).await;
+    status.changed_files = repo.changed_files(base)?;
+    
+    let policy = ApprovalPolicy::new(config.policy.clone()).with_owners(code_owners, team_members);
+    let decision = policy.evaluate(&status);
+    
+    println!();
+    println!("{} #{}: {}", review_name, review_id, review_url);
+    println!("  State: {:?}", decision.state);
+    if decision.approvals.is_empty() {
+        println!("  Approvals: none");
+    } else {
+        let reviewers: Vec<&str> = decision.approvals.iter().map(|a| a.reviewer.as_str()).collect();
+        println!("  Approvals: {}", reviewers.join(", "));
+    }
+    for problem in &decision.problems {
+        println!("    - {}", problem);
+    }
+    for (label, reviewers) in &decision.labels {
+        let votes: Vec<String> = reviewers.iter().map(|(reviewer, value)| format!("{:+} {}", value, reviewer)).collect();
+        println!("  {}: {}", label, votes.join(", "));
+    }
+    if !decision.missing_owners.is_empty() {
+        println!("  Missing code owner approval:");
+        for group in &decision.missing_owners {
+            println!("    - {}: {}", group, group.paths.join(", "));
+        }
+    }
+    println!("  Mergeable: {}", if status.mergeable { "yes" } else { "no" });
+    
+    println!("  CI: {}", ci_label(&status.ci_status));
+    for check in &checks {
+        println!("    {} {}", ci_symbol(&check.status), check.name);
+    }
+    
+    // The queue lives in the bot: ask it if we can, otherwise estimate
+    // the position the way it discovers reviews
+    let target = if status.target_branch.is_empty() { config.bot.main_branch.clone() } else { status.target_branch.clone() };
+    let positions = match QueuePositions::from_bot(&config).await? {
+        Some(positions) => Some(positions),
+        None if QueueManager::is_queueable(&policy, &status) => {
+            let policies = queue::policy_queue(&config, adapter).await?;
+            Some(queue::positions(&config, &policies).await?)
+        },
+        None => None,
+    };
+    match positions {
+        Some(positions) => match positions.position(&review_id) {
+            Some(position) if positions.is_estimated() => println!(
+                "  Merge queue: position {} into {} (estimated; set api.url to ask the bot)", position, target
+            ),
+            Some(position) => println!("  Merge queue: position {} into {}", position, target),
+            None => println!("  Merge queue: not queued"),
+        },
+        None => println!("  Merge queue: not queued (needs approval and passing CI)"),
+    }
+    
+    Ok(())
+}
+
+/// Short label for an overall CI status
+pub(super) fn ci_label(status: &CiStatus) -> &'static str {
+    match status {
+        CiStatus::Pending => "pending",
+        CiStatus::Running => "running",
+        CiStatus::Success => "passed",
+        CiStatus::Failed(_) => "failed",
+        CiStatus::Cancelled => "cancelled",
+    }
+}
+
+/// Symbol for a single check's status
+fn ci_symbol(status: &CiStatus) -> &'static str {
+    match status {
+        CiStatus::Success => "✓",
+        CiStatus::Failed(_) => "✗",
+        CiStatus::Cancelled => "-",
+        CiStatus::Pending | CiStatus::Running => "…",
+    }
+}
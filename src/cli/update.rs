@@ -1,7 +1,7 @@
-use crate::utils::error::{GarryError, Result};
+use crate::utils::error::Result;
 use crate::utils::config::Config;
 use crate::git::GitRepository;
-use tracing::info;
+use tracing::{info, warn};
 
 /// Execute the update command - updates an existing review
 pub async fn execute() -> Result<()> {
@@ -36,5 +36,38 @@ pub async fn execute() -> Result<()> {
     println!("  Branch: {}", branch);
     println!("  CI checks will run automatically");
     
+    // Keep the revision as a patchset and tell reviewers what changed
+    let adapter = super::create_adapter(&config)?;
+    let Some((review_id, _)) = adapter.find_review_for_branch(&branch).await? else {
+        println!("  No open {} for this branch, so no patchset was recorded", adapter.review_name());
+        return Ok(());
+    };
+    
+    let remote = &config.git.default_remote;
+    let number = match repo.fetch_patchsets(remote, review_id.as_str())
+        .and_then(|_| repo.record_patchset(remote, review_id.as_str()))
+    {
+        Ok(number) => number,
+        Err(e) => {
+            warn!("Could not record patchset for review {}: {}", review_id, e);
+            return Ok(());
+        }
+    };
+    println!("  Patchset: {}", number);
+    
+    // The push already went through, so a failed summary shouldn't fail the update
+    if number > 1 {
+        let interdiff = adapter.get_review_status(&review_id).await
+            .and_then(|status| repo.interdiff(review_id.as_str(), &config.base_for(&status.target_branch), number - 1, number));
+        let posted = match interdiff {
+            Ok(interdiff) => adapter.post_comment(&review_id, &interdiff.summary()).await,
+            Err(e) => Err(e),
+        };
+        match posted {
+            Ok(_) => println!("  Posted changes since patchset {}", number - 1),
+            Err(e) => warn!("Could not post changes since patchset {} on review {}: {}", number - 1, review_id, e),
+        }
+    }
+    
     Ok(())
 }
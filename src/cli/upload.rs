@@ -1,12 +1,27 @@
 use crate::utils::error::{GarryError, Result};
 use crate::utils::config::Config;
 use crate::git::GitRepository;
-use crate::bot::adapters::GithubAdapter;
-use crate::bot::adapter::VcsAdapter;
-use tracing::info;
+use crate::presubmit::{self, Presubmit};
+use crate::bot::VcsAdapter;
+use crate::review::owners;
+use crate::review::suggest::{ReviewerSuggester, Suggestion};
+use tracing::{info, warn};
+
+/// Options for `garry upload`
+pub struct UploadOptions {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub no_verify: bool,
+    pub reviewers: Vec<String>,
+    pub suggest_reviewers: bool,
+    pub draft: bool,
+    /// Branch to merge into (default: `bot.main_branch`)
+    pub target: Option<String>,
+}
 
 /// Execute the upload command - uploads changes for review
-pub async fn execute(title: Option<String>, description: Option<String>) -> Result<()> {
+pub async fn execute(options: UploadOptions) -> Result<()> {
+    let UploadOptions { title, description, no_verify, mut reviewers, suggest_reviewers, draft, target } = options;
     info!("Uploading changes for review");
     
     // Load config
@@ -16,33 +31,111 @@ pub async fn execute(title: Option<String>, description: Option<String>) -> Resu
     let repo = GitRepository::open_current()?;
     let branch = repo.get_current_branch()?;
     
+    // Checks and suggestions compare against the branch the review merges into
+    let target = target.unwrap_or_else(|| config.bot.main_branch.clone());
+    let base = config.base_for(&target);
+    if target == branch {
+        return Err(GarryError::VcsError(format!("Cannot upload {} for review into itself", branch)));
+    }
+    
+    // Run presubmit checks before anything leaves the machine
+    let presubmit_section = if no_verify {
+        println!("Skipping presubmit checks (--no-verify)");
+        Some("### Presubmit\n\nSkipped (`--no-verify`)\n".to_string())
+    } else {
+        let results = Presubmit::new(&config.presubmit, &repo, &base).run().await?;
+        for result in &results {
+            if result.passed {
+                println!("  ✓ {}", result.name);
+            } else {
+                println!("  ✗ {}: {}", result.name, result.details);
+            }
+        }
+        
+        let failed: Vec<String> = results.iter().filter(|r| !r.passed).map(|r| r.name.clone()).collect();
+        if !failed.is_empty() {
+            println!("Presubmit failed; fix the issues or rerun with --no-verify");
+            return Err(GarryError::PresubmitFailed(failed));
+        }
+        
+        (!results.is_empty()).then(|| presubmit::format_results(&results))
+    };
+    
     // Push branch to remote
     println!("Pushing branch '{}' to remote...", branch);
     repo.push_branch(&branch, &config.git.default_remote)?;
     
     // Create adapter based on platform
-    let adapter: Box<dyn VcsAdapter> = match config.vcs.platform.as_str() {
-        "github" => Box::new(GithubAdapter::new(
-            config.vcs.host.clone(),
-            config.vcs.token.clone(),
-            config.vcs.repository.clone(),
-        )?),
-        _ => return Err(GarryError::VcsError(format!("Unsupported platform: {}", config.vcs.platform))),
-    };
+    let adapter = super::create_adapter(&config)?;
     
     // Generate title and description
     let review_title = title.unwrap_or_else(|| format!("Review: {}", branch));
-    let review_description = description.unwrap_or_else(|| format!("Changes from branch {}", branch));
+    let mut review_description = description.unwrap_or_else(|| format!("Changes from branch {}", branch));
+    if let Some(section) = presubmit_section {
+        review_description.push_str("\n\n");
+        review_description.push_str(&section);
+    }
     
     // Create review
     let review_name = adapter.review_name();
-    println!("Creating {}...", review_name);
-    let (review_id, review_url) = adapter.create_review(&branch, &review_title, &review_description).await?;
+    let (review_id, review_url) = if draft {
+        println!("Creating draft {} into {}...", review_name, target);
+        adapter.create_draft_review(&branch, &target, &review_title, &review_description).await?
+    } else {
+        println!("Creating {} into {}...", review_name, target);
+        adapter.create_review(&branch, &target, &review_title, &review_description).await?
+    };
     
-    println!("✓ Successfully created {}!", review_name);
+    println!("✓ Successfully created {}{}!", if draft { "draft " } else { "" }, review_name);
     println!("  Branch: {}", branch);
+    println!("  Target: {}", target);
     println!("  {} ID: #{}", review_name, review_id);
     println!("  {} URL: {}", review_name, review_url);
     
+    // The review exists either way, so a failure here only loses history
+    match repo.record_patchset(&config.git.default_remote, review_id.as_str()) {
+        Ok(number) => println!("  Patchset: {}", number),
+        Err(e) => warn!("Could not record patchset for review {}: {}", review_id, e),
+    }
+    
+    // Suggest reviewers when asked, or by default if configured and none were given
+    if suggest_reviewers || (config.reviewers.auto_suggest && reviewers.is_empty()) {
+        match suggest(&config, &repo, adapter.as_ref(), &base).await {
+            Ok(suggestions) => {
+                for suggestion in suggestions {
+                    println!("  Suggested reviewer: {} ({})", suggestion.reviewer, suggestion.reasons.join(", "));
+                    if !reviewers.contains(&suggestion.reviewer) {
+                        reviewers.push(suggestion.reviewer);
+                    }
+                }
+            },
+            Err(e) => warn!("Could not suggest reviewers: {}", e),
+        }
+    }
+    
+    if !reviewers.is_empty() {
+        adapter.request_reviewers(&review_id, &reviewers).await?;
+        println!("  Reviewers: {}", reviewers.join(", "));
+    }
+    
+    if draft {
+        println!("  The merge queue ignores it until you run 'garry ready'");
+    }
+    
     Ok(())
 }
+
+/// Suggest reviewers for the current branch's changes
+async fn suggest(config: &Config, repo: &GitRepository, adapter: &dyn VcsAdapter, base: &str) -> Result<Vec<Suggestion>> {
+    let changed_files = repo.changed_files(base)?;
+    let code_owners = repo.code_owners(base)?;
+    let team_members = owners::resolve_team_members(adapter, &code_owners).await;
+    let open_reviews = adapter.list_open_reviews().await?;
+    let author = adapter.current_user().await?;
+    
+    let suggester = ReviewerSuggester::new(config.reviewers.clone())
+        .with_owners(code_owners, team_members)
+        .with_blame(repo.blame_authors(base, &changed_files)?)
+        .with_open_reviews(&open_reviews);
+    Ok(suggester.suggest(&author, &changed_files))
+}
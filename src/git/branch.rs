@@ -1,6 +1,26 @@
-use git2::Repository;
+use git2::{Oid, Repository};
 use crate::utils::error::{GarryError, Result};
 
+/// How a local branch relates to another ref (its remote or base)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncState {
+    UpToDate,
+    Ahead(usize),
+    Behind(usize),
+    Diverged { ahead: usize, behind: usize },
+}
+
+impl std::fmt::Display for SyncState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SyncState::UpToDate => write!(f, "up to date"),
+            SyncState::Ahead(n) => write!(f, "{} ahead", n),
+            SyncState::Behind(n) => write!(f, "{} behind", n),
+            SyncState::Diverged { ahead, behind } => write!(f, "diverged ({} ahead, {} behind)", ahead, behind),
+        }
+    }
+}
+
 /// Branch-related operations
 pub struct BranchOps;
 
@@ -31,4 +51,69 @@ impl BranchOps {
             Err(GarryError::GitError(git2::Error::from_str("Not on a branch")))
         }
     }
+    
+    /// Compare a local commit against another, counting commits on each side
+    pub fn sync_state(repo: &Repository, local: Oid, other: Oid) -> Result<SyncState> {
+        let (ahead, behind) = repo.graph_ahead_behind(local, other)?;
+        
+        Ok(match (ahead, behind) {
+            (0, 0) => SyncState::UpToDate,
+            (ahead, 0) => SyncState::Ahead(ahead),
+            (0, behind) => SyncState::Behind(behind),
+            (ahead, behind) => SyncState::Diverged { ahead, behind },
+        })
+    }
+    
+    /// Check out a commit with a detached HEAD
+    ///
+    /// Uses a safe checkout, so uncommitted changes that would be overwritten
+    /// abort the checkout instead of being lost.
+    pub fn checkout_detached(repo: &Repository, oid: Oid) -> Result<()> {
+        let commit = repo.find_commit(oid)?;
+        repo.checkout_tree(commit.as_object(), Some(git2::build::CheckoutBuilder::new().safe()))?;
+        repo.set_head_detached(oid)?;
+        Ok(())
+    }
+    
+    /// Point a local branch at a commit and check it out
+    ///
+    /// An existing branch is only fast-forwarded, never rewound, so local work
+    /// on it can't be lost. That includes the checked-out branch, whose working
+    /// tree is updated before the branch moves. `upstream` (e.g. "origin/feature")
+    /// is set as the branch's tracking branch when given.
+    pub fn checkout_branch_at(repo: &Repository, name: &str, oid: Oid, upstream: Option<&str>) -> Result<()> {
+        let commit = repo.find_commit(oid)?;
+        
+        let mut branch = match repo.find_branch(name, git2::BranchType::Local) {
+            Ok(mut branch) => {
+                let tip = branch.get().peel_to_commit()?.id();
+                if tip != oid && !repo.graph_descendant_of(oid, tip)? {
+                    return Err(GarryError::InvalidBranchName(format!(
+                        "Local branch '{}' has diverged from the review; use --detach or delete the branch",
+                        name
+                    )));
+                }
+                if tip == oid {
+                    branch
+                } else if branch.is_head() {
+                    // libgit2 won't force-update the current branch, so move it by hand
+                    repo.checkout_tree(commit.as_object(), Some(git2::build::CheckoutBuilder::new().safe()))?;
+                    branch.get_mut().set_target(oid, &format!("garry: fast-forward {}", name))?;
+                    branch
+                } else {
+                    repo.branch(name, &commit, true)?
+                }
+            },
+            Err(e) if e.code() == git2::ErrorCode::NotFound => repo.branch(name, &commit, false)?,
+            Err(e) => return Err(e.into()),
+        };
+        
+        if let Some(upstream) = upstream {
+            branch.set_upstream(Some(upstream))?;
+        }
+        
+        repo.checkout_tree(commit.as_object(), Some(git2::build::CheckoutBuilder::new().safe()))?;
+        repo.set_head(&format!("refs/heads/{}", name))?;
+        Ok(())
+    }
 }
@@ -0,0 +1,62 @@
+use git2::{Oid, Repository, Signature};
+use crate::utils::error::Result;
+use tracing::info;
+
+/// Result of cherry-picking a commit
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CherryPick {
+    /// The commit applied cleanly, as this new commit
+    Picked(Oid),
+    /// The commit's changes are already on the branch
+    Empty,
+    /// The commit conflicts in these paths
+    Conflicts(Vec<String>),
+}
+
+/// Cherry-pick operations
+pub struct CherryPickOps;
+
+impl CherryPickOps {
+    /// Cherry-pick `commit` onto `onto`, without touching the working tree
+    ///
+    /// The new commit keeps the original author and message, noting where it
+    /// was picked from as `git cherry-pick -x` does. Merge commits are picked
+    /// against their first parent.
+    pub fn cherry_pick(repo: &Repository, commit: Oid, onto: Oid) -> Result<CherryPick> {
+        let commit = repo.find_commit(commit)?;
+        let onto = repo.find_commit(onto)?;
+        let mainline = if commit.parent_count() > 1 { 1 } else { 0 };
+        
+        let mut index = repo.cherrypick_commit(&commit, &onto, mainline, None)?;
+        if index.has_conflicts() {
+            let mut paths = Vec::new();
+            for conflict in index.conflicts()? {
+                let conflict = conflict?;
+                let entry = conflict.our.or(conflict.their).or(conflict.ancestor);
+                if let Some(entry) = entry {
+                    paths.push(String::from_utf8_lossy(&entry.path).into_owned());
+                }
+            }
+            paths.sort();
+            paths.dedup();
+            return Ok(CherryPick::Conflicts(paths));
+        }
+        
+        let tree = repo.find_tree(index.write_tree_to(repo)?)?;
+        if tree.id() == onto.tree_id() {
+            return Ok(CherryPick::Empty);
+        }
+        
+        let committer = repo.signature()
+            .or_else(|_| Signature::now("Garry", "garry@organisely.com"))?;
+        let message = format!(
+            "{}\n\n(cherry picked from commit {})\n",
+            commit.message().unwrap_or_default().trim_end(),
+            commit.id()
+        );
+        let picked = repo.commit(None, &commit.author(), &committer, &message, &tree, &[&onto])?;
+        
+        info!("Cherry-picked {} onto {} as {}", commit.id(), onto.id(), picked);
+        Ok(CherryPick::Picked(picked))
+    }
+}
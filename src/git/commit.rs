@@ -1,4 +1,5 @@
-use git2::{Repository, Signature};
+use std::collections::HashMap;
+use git2::{BlameOptions, Commit, Oid, Repository, Signature};
 use crate::utils::error::{GarryError, Result};
 use tracing::info;
 
@@ -6,17 +7,41 @@ use tracing::info;
 pub struct CommitOps;
 
 impl CommitOps {
+    /// Resolve the tip of the base branch
+    ///
+    /// The remote-tracking branch is preferred over the local one, since a
+    /// local `main` that hasn't been pulled is usually stale. Anything else
+    /// git can parse (e.g. "origin/main" or a SHA) is accepted as a fallback.
+    pub fn find_base_commit<'r>(repo: &'r Repository, base: &str) -> Result<Commit<'r>> {
+        let base_ref = repo.find_reference(&format!("refs/remotes/origin/{}", base))
+            .or_else(|_| repo.find_reference(&format!("refs/heads/{}", base)));
+        
+        let commit = match base_ref {
+            Ok(reference) => reference.peel_to_commit()?,
+            Err(_) => repo.revparse_single(base)
+                .map_err(|_| GarryError::GitError(git2::Error::from_str(
+                    &format!("Base branch '{}' not found", base)
+                )))?
+                .peel_to_commit()?,
+        };
+        
+        Ok(commit)
+    }
+    
+    /// Find the merge-base between current HEAD and the base branch
+    pub fn find_merge_base(repo: &Repository, base: &str) -> Result<Oid> {
+        let head_commit = repo.head()?.peel_to_commit()?;
+        let base_commit = Self::find_base_commit(repo, base)?;
+        
+        Ok(repo.merge_base(head_commit.id(), base_commit.id())?)
+    }
+    
     /// Get the number of commits between current HEAD and base branch
     pub fn get_commit_count(repo: &Repository, base: &str) -> Result<usize> {
-        let head = repo.head()?;
-        let head_commit = head.peel_to_commit()?;
+        let head_commit = repo.head()?.peel_to_commit()?;
+        let base_commit = Self::find_base_commit(repo, base)?;
         
-        // Try to find base branch
-        let base_ref = repo.find_reference(&format!("refs/heads/{}", base))
-            .or_else(|_| repo.find_reference(&format!("refs/remotes/origin/{}", base)))?;
-        let base_commit = base_ref.peel_to_commit()?;
-        
-        // Count commits
+        // Count commits reachable from HEAD but not from the base branch
         let mut revwalk = repo.revwalk()?;
         revwalk.push(head_commit.id())?;
         revwalk.hide(base_commit.id())?;
@@ -24,6 +49,57 @@ impl CommitOps {
         Ok(revwalk.count())
     }
     
+    /// Get the paths changed between the merge-base and HEAD
+    pub fn changed_files(repo: &Repository, base: &str) -> Result<Vec<String>> {
+        let diff = Self::diff_from_merge_base(repo, base)?;
+        
+        Ok(diff.deltas()
+            .filter_map(|delta| delta.new_file().path().or_else(|| delta.old_file().path()))
+            .map(|path| path.to_string_lossy().into_owned())
+            .collect())
+    }
+    
+    /// Get the number of lines added plus removed between the merge-base and HEAD
+    pub fn diff_line_count(repo: &Repository, base: &str) -> Result<usize> {
+        let stats = Self::diff_from_merge_base(repo, base)?.stats()?;
+        Ok(stats.insertions() + stats.deletions())
+    }
+    
+    /// Count lines per author email in the given files as of the merge-base
+    ///
+    /// Files that don't exist before the branch (i.e. new files) are skipped.
+    pub fn blame_authors(repo: &Repository, base: &str, files: &[String]) -> Result<HashMap<String, usize>> {
+        let merge_base = repo.find_commit(Self::find_merge_base(repo, base)?)?;
+        let tree = merge_base.tree()?;
+        let mut authors = HashMap::new();
+        
+        for file in files {
+            let path = std::path::Path::new(file);
+            if tree.get_path(path).is_err() {
+                continue;
+            }
+            
+            let mut options = BlameOptions::new();
+            options.newest_commit(merge_base.id());
+            let blame = repo.blame_file(path, Some(&mut options))?;
+            for hunk in blame.iter() {
+                if let Some(email) = hunk.final_signature().email() {
+                    *authors.entry(email.to_string()).or_insert(0) += hunk.lines_in_hunk();
+                }
+            }
+        }
+        
+        Ok(authors)
+    }
+    
+    /// Diff the branch's changes, i.e. merge-base against HEAD
+    fn diff_from_merge_base<'r>(repo: &'r Repository, base: &str) -> Result<git2::Diff<'r>> {
+        let merge_base = repo.find_commit(Self::find_merge_base(repo, base)?)?;
+        let head_commit = repo.head()?.peel_to_commit()?;
+        
+        Ok(repo.diff_tree_to_tree(Some(&merge_base.tree()?), Some(&head_commit.tree()?), None)?)
+    }
+    
     /// Squash commits on current branch into a single commit
     pub fn squash_commits(repo: &Repository, base: &str) -> Result<()> {
         let count = Self::get_commit_count(repo, base)?;
@@ -45,10 +121,11 @@ impl CommitOps {
         let branch_name = head.shorthand()
             .ok_or_else(|| GarryError::GitError(git2::Error::from_str("Not on a branch")))?;
         
-        // Get base commit
-        let base_ref = repo.find_reference(&format!("refs/heads/{}", base))
-            .or_else(|_| repo.find_reference(&format!("refs/remotes/origin/{}", base)))?;
-        let base_commit = base_ref.peel_to_commit()?;
+        // The squashed commit is parented on the merge-base rather than the
+        // base tip: its tree is HEAD's, so parenting it on a newer base would
+        // make the diff silently revert everything the base gained since.
+        let base_commit = Self::find_base_commit(repo, base)?;
+        let merge_base = repo.find_commit(Self::find_merge_base(repo, base)?)?;
         
         // Get current HEAD commit
         let head_commit = head.peel_to_commit()?;
@@ -89,7 +166,7 @@ impl CommitOps {
             &signature,
             &combined_message,
             &tree,
-            &[&base_commit],
+            &[&merge_base],
         )?;
         
         info!("Created squashed commit: {}", new_commit_oid);
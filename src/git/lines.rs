@@ -0,0 +1,46 @@
+use git2::{DiffOptions, Oid, Patch, Repository};
+use crate::utils::error::{GarryError, Result};
+use std::path::Path;
+
+/// Line-position operations
+pub struct LineOps;
+
+impl LineOps {
+    /// Map `line` of `path` as of `commit` to the same line in the working tree
+    ///
+    /// Returns `None` if the line was changed or removed locally, or the
+    /// file no longer exists.
+    pub fn map_to_workdir(repo: &Repository, commit: Oid, path: &str, line: u32) -> Result<Option<u32>> {
+        let workdir = repo.workdir()
+            .ok_or_else(|| GarryError::Other("Repository has no working directory".to_string()))?;
+        let local = match std::fs::read(workdir.join(path)) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        
+        let tree = repo.find_commit(commit)?.tree()?;
+        let blob = repo.find_blob(tree.get_path(Path::new(path))?.id())?;
+        
+        // Without context, every hunk covers exactly the lines that changed
+        let mut options = DiffOptions::new();
+        options.context_lines(0);
+        let patch = Patch::from_blob_and_buffer(&blob, Some(Path::new(path)), &local, Some(Path::new(path)), Some(&mut options))?;
+        
+        let mut offset: i64 = 0;
+        for index in 0..patch.num_hunks() {
+            let (hunk, _) = patch.hunk(index)?;
+            // A pure insertion's old_start is the line it follows
+            let first_changed = if hunk.old_lines() == 0 { hunk.old_start() + 1 } else { hunk.old_start() };
+            if line < first_changed {
+                break;
+            }
+            if line < first_changed + hunk.old_lines() {
+                return Ok(None);
+            }
+            offset += hunk.new_lines() as i64 - hunk.old_lines() as i64;
+        }
+        
+        Ok(Some((line as i64 + offset) as u32))
+    }
+}
@@ -2,5 +2,8 @@ pub mod repo;
 pub mod branch;
 pub mod commit;
 pub mod remote;
+pub mod patchset;
+pub mod lines;
+pub mod cherrypick;
 
 pub use repo::GitRepository;
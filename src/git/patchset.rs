@@ -0,0 +1,179 @@
+use git2::{Diff, DiffFormat, Oid, Patch, Repository};
+use crate::utils::error::Result;
+use tracing::{debug, warn};
+
+/// Changes to a single file between two patchsets
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileDelta {
+    pub path: String,
+    pub insertions: usize,
+    pub deletions: usize,
+}
+
+/// What changed between two patchsets of a review
+#[derive(Debug, Clone)]
+pub struct Interdiff {
+    pub from: u32,
+    pub to: u32,
+    /// The newer patchset sits on a different base than the older one
+    pub rebased: bool,
+    /// The older patchset didn't apply cleanly to the newer base, so the
+    /// diff includes changes that came in with the rebase
+    pub conflicted: bool,
+    pub files: Vec<FileDelta>,
+    /// Unified diff of the changes
+    pub patch: String,
+}
+
+impl Interdiff {
+    /// Markdown summary for review comments
+    pub fn summary(&self) -> String {
+        let mut summary = format!("### Patchset {}\n\n", self.to);
+        
+        if self.files.is_empty() {
+            summary.push_str(&format!("No changes since patchset {}", self.from));
+            summary.push_str(if self.rebased { " other than a rebase.\n" } else { ".\n" });
+            return summary;
+        }
+        
+        summary.push_str(&format!("Changes since patchset {}", self.from));
+        summary.push_str(if self.rebased { " (rebased):\n\n" } else { ":\n\n" });
+        for file in &self.files {
+            summary.push_str(&format!("- `{}` (+{} -{})\n", file.path, file.insertions, file.deletions));
+        }
+        if self.conflicted {
+            summary.push_str("\nThe rebase touched the same lines, so this includes upstream changes.\n");
+        }
+        summary
+    }
+}
+
+/// Patchset-related operations
+///
+/// Each uploaded revision of a review is kept under
+/// `refs/garry/patchsets/<review>/<n>` so earlier revisions stay reachable
+/// after the review branch is force-pushed.
+pub struct PatchsetOps;
+
+impl PatchsetOps {
+    /// Ref name under which a review's patchset is stored
+    pub fn ref_name(review_id: &str, number: u32) -> String {
+        format!("refs/garry/patchsets/{}/{}", review_id, number)
+    }
+    
+    /// Refspec that fetches every patchset of a review
+    pub fn fetch_refspec(review_id: &str) -> String {
+        let refs = format!("refs/garry/patchsets/{}/*", review_id);
+        format!("+{}:{}", refs, refs)
+    }
+    
+    /// List a review's local patchsets, oldest first
+    pub fn list(repo: &Repository, review_id: &str) -> Result<Vec<(u32, Oid)>> {
+        let prefix = format!("refs/garry/patchsets/{}/", review_id);
+        let mut patchsets = Vec::new();
+        
+        for reference in repo.references_glob(&format!("{}*", prefix))? {
+            let reference = reference?;
+            let number = reference.name().and_then(|name| name.strip_prefix(&prefix)?.parse().ok());
+            if let (Some(number), Some(oid)) = (number, reference.target()) {
+                patchsets.push((number, oid));
+            }
+        }
+        
+        patchsets.sort_by_key(|(number, _)| *number);
+        Ok(patchsets)
+    }
+    
+    /// Record a commit as the review's next patchset, returning its number
+    ///
+    /// Recording the latest patchset's commit again is a no-op.
+    pub fn record(repo: &Repository, review_id: &str, oid: Oid) -> Result<u32> {
+        let latest = Self::list(repo, review_id)?.pop();
+        if let Some((number, latest_oid)) = latest {
+            if latest_oid == oid {
+                debug!("Patchset {} of review {} is already {}", number, review_id, oid);
+                return Ok(number);
+            }
+        }
+        
+        let number = latest.map(|(number, _)| number + 1).unwrap_or(1);
+        repo.reference(
+            &Self::ref_name(review_id, number),
+            oid,
+            false,
+            &format!("garry: patchset {} of review {}", number, review_id),
+        )?;
+        Ok(number)
+    }
+    
+    /// Diff two patchsets, ignoring changes that came in from rebasing onto `base`
+    ///
+    /// If the newer patchset was rebased, the older one is replayed onto the
+    /// newer base first, so only the author's own changes show up.
+    pub fn interdiff(repo: &Repository, base: Oid, from: (u32, Oid), to: (u32, Oid)) -> Result<Interdiff> {
+        let old = repo.find_commit(from.1)?;
+        let new = repo.find_commit(to.1)?;
+        let old_base = repo.merge_base(old.id(), base)?;
+        let new_base = repo.merge_base(new.id(), base)?;
+        let rebased = old_base != new_base;
+        
+        let mut conflicted = false;
+        let old_tree = if rebased {
+            let ancestor = repo.find_commit(old_base)?.tree()?;
+            let ours = repo.find_commit(new_base)?.tree()?;
+            let mut index = repo.merge_trees(&ancestor, &ours, &old.tree()?, None)?;
+            if index.has_conflicts() {
+                warn!("Patchset {} doesn't apply cleanly to the base of patchset {}", from.0, to.0);
+                conflicted = true;
+                old.tree()?
+            } else {
+                repo.find_tree(index.write_tree_to(repo)?)?
+            }
+        } else {
+            old.tree()?
+        };
+        
+        let diff = repo.diff_tree_to_tree(Some(&old_tree), Some(&new.tree()?), None)?;
+        
+        Ok(Interdiff {
+            from: from.0,
+            to: to.0,
+            rebased,
+            conflicted,
+            files: Self::file_deltas(&diff)?,
+            patch: Self::patch_text(&diff)?,
+        })
+    }
+    
+    /// Per-file line counts of a diff
+    fn file_deltas(diff: &Diff) -> Result<Vec<FileDelta>> {
+        let mut files = Vec::new();
+        for (index, delta) in diff.deltas().enumerate() {
+            let path = delta.new_file().path().or_else(|| delta.old_file().path())
+                .map(|p| p.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            let (insertions, deletions) = match Patch::from_diff(diff, index)? {
+                Some(patch) => {
+                    let (_, insertions, deletions) = patch.line_stats()?;
+                    (insertions, deletions)
+                },
+                None => (0, 0),
+            };
+            files.push(FileDelta { path, insertions, deletions });
+        }
+        Ok(files)
+    }
+    
+    /// Render a diff as a unified patch
+    fn patch_text(diff: &Diff) -> Result<String> {
+        let mut text = String::new();
+        diff.print(DiffFormat::Patch, |_, _, line| {
+            if matches!(line.origin(), '+' | '-' | ' ') {
+                text.push(line.origin());
+            }
+            text.push_str(&String::from_utf8_lossy(line.content()));
+            true
+        })?;
+        Ok(text)
+    }
+}
@@ -1,4 +1,4 @@
-use git2::{Repository, PushOptions, RemoteCallbacks, Cred};
+use git2::{Direction, Repository, PushOptions, FetchOptions, RemoteCallbacks, Cred};
 use crate::utils::error::{GarryError, Result};
 use tracing::info;
 
@@ -26,7 +26,72 @@ impl RemoteOps {
         
         let mut remote = repo.find_remote(remote_name)?;
         
-        // Set up callbacks for authentication
+        let mut push_options = PushOptions::new();
+        push_options.remote_callbacks(Self::callbacks(repo));
+        
+        // Push the branch (with force if needed)
+        let refspec = if force {
+            format!("+refs/heads/{}:refs/heads/{}", branch, branch)
+        } else {
+            format!("refs/heads/{}:refs/heads/{}", branch, branch)
+        };
+        
+        remote.push(&[&refspec], Some(&mut push_options))
+            .map_err(GarryError::GitError)?;
+        
+        info!("Successfully pushed branch '{}'", branch);
+        Ok(())
+    }
+    
+    /// Push arbitrary refspecs to a named remote
+    pub fn push_refs(repo: &Repository, remote_name: &str, refspecs: &[&str]) -> Result<()> {
+        info!("Pushing {:?} to remote '{}'", refspecs, remote_name);
+        
+        let mut remote = repo.find_remote(remote_name)?;
+        let mut push_options = PushOptions::new();
+        push_options.remote_callbacks(Self::callbacks(repo));
+        
+        remote.push(refspecs, Some(&mut push_options))?;
+        Ok(())
+    }
+    
+    /// Fetch refspecs from a named remote
+    pub fn fetch(repo: &Repository, remote_name: &str, refspecs: &[&str]) -> Result<()> {
+        info!("Fetching {:?} from remote '{}'", refspecs, remote_name);
+        
+        let mut remote = repo.find_remote(remote_name)?;
+        let mut fetch_options = FetchOptions::new();
+        fetch_options.remote_callbacks(Self::callbacks(repo));
+        
+        remote.fetch(refspecs, Some(&mut fetch_options), None)?;
+        Ok(())
+    }
+    
+    /// Fetch refspecs from a URL that isn't configured as a remote (e.g. a fork)
+    pub fn fetch_url(repo: &Repository, url: &str, refspecs: &[&str]) -> Result<()> {
+        info!("Fetching {:?} from {}", refspecs, url);
+        
+        let mut remote = repo.remote_anonymous(url)?;
+        let mut fetch_options = FetchOptions::new();
+        fetch_options.remote_callbacks(Self::callbacks(repo));
+        
+        remote.fetch(refspecs, Some(&mut fetch_options), None)?;
+        Ok(())
+    }
+    
+    /// List the branches on a named remote, as `git ls-remote --heads` would
+    pub fn list_branches(repo: &Repository, remote_name: &str) -> Result<Vec<String>> {
+        let mut remote = repo.find_remote(remote_name)?;
+        let connection = remote.connect_auth(Direction::Fetch, Some(Self::callbacks(repo)), None)?;
+        
+        Ok(connection.list()?.iter()
+            .filter_map(|head| head.name().strip_prefix("refs/heads/"))
+            .map(str::to_string)
+            .collect())
+    }
+    
+    /// Set up callbacks for authentication
+    fn callbacks(repo: &Repository) -> RemoteCallbacks<'_> {
         let mut callbacks = RemoteCallbacks::new();
         callbacks.credentials(|url, username_from_url, allowed_types| {
             let username = username_from_url.unwrap_or("git");
@@ -69,20 +134,6 @@ impl RemoteOps {
             Cred::default()
         });
         
-        let mut push_options = PushOptions::new();
-        push_options.remote_callbacks(callbacks);
-        
-        // Push the branch (with force if needed)
-        let refspec = if force {
-            format!("+refs/heads/{}:refs/heads/{}", branch, branch)
-        } else {
-            format!("refs/heads/{}:refs/heads/{}", branch, branch)
-        };
-        
-        remote.push(&[&refspec], Some(&mut push_options))
-            .map_err(|e| GarryError::GitError(e))?;
-        
-        info!("Successfully pushed branch '{}'", branch);
-        Ok(())
+        callbacks
     }
 }
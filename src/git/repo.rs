@@ -1,8 +1,12 @@
-use git2::Repository;
+use git2::{Oid, Repository};
 use crate::utils::error::{GarryError, Result};
-use crate::git::branch::BranchOps;
+use crate::git::branch::{BranchOps, SyncState};
 use crate::git::commit::CommitOps;
 use crate::git::remote::RemoteOps;
+use crate::git::patchset::{Interdiff, PatchsetOps};
+use crate::git::lines::LineOps;
+use crate::git::cherrypick::{CherryPick, CherryPickOps};
+use crate::review::owners::CodeOwners;
 use std::path::Path;
 use tracing::info;
 
@@ -29,6 +33,11 @@ impl GitRepository {
         &self.repo
     }
     
+    /// Get the working directory, if the repository isn't bare
+    pub fn workdir(&self) -> Option<&Path> {
+        self.repo.workdir()
+    }
+    
     /// Create a new branch
     pub fn create_branch(&self, name: &str) -> Result<()> {
         BranchOps::validate_branch_name(name)?;
@@ -63,6 +72,33 @@ impl GitRepository {
         CommitOps::get_commit_count(&self.repo, base)
     }
     
+    /// Get the message of the HEAD commit
+    pub fn head_message(&self) -> Result<String> {
+        let head = self.repo.head()?.peel_to_commit()?;
+        Ok(head.message().unwrap_or_default().to_string())
+    }
+    
+    /// Get the paths changed on this branch relative to the base branch
+    pub fn changed_files(&self, base: &str) -> Result<Vec<String>> {
+        CommitOps::changed_files(&self.repo, base)
+    }
+    
+    /// Get the number of changed lines on this branch relative to the base branch
+    pub fn diff_line_count(&self, base: &str) -> Result<usize> {
+        CommitOps::diff_line_count(&self.repo, base)
+    }
+    
+    /// Load code ownership as defined on the base branch
+    pub fn code_owners(&self, base: &str) -> Result<CodeOwners> {
+        let base_commit = CommitOps::find_base_commit(&self.repo, base)?;
+        CodeOwners::from_commit(&self.repo, &base_commit)
+    }
+    
+    /// Count lines per author email in the given files before this branch's changes
+    pub fn blame_authors(&self, base: &str, files: &[String]) -> Result<std::collections::HashMap<String, usize>> {
+        CommitOps::blame_authors(&self.repo, base, files)
+    }
+    
     /// Squash commits on current branch
     pub fn squash_commits(&self, base: &str) -> Result<()> {
         CommitOps::squash_commits(&self.repo, base)
@@ -77,4 +113,118 @@ impl GitRepository {
     pub fn force_push_branch(&self, branch: &str, remote: &str) -> Result<()> {
         RemoteOps::force_push_branch(&self.repo, branch, remote)
     }
+    
+    /// Compare HEAD with the branch's remote-tracking ref
+    ///
+    /// Returns `None` if the branch has never been pushed.
+    pub fn remote_sync_state(&self, branch: &str, remote: &str) -> Result<Option<SyncState>> {
+        let head = self.repo.head()?.peel_to_commit()?;
+        let remote_ref = match self.repo.find_reference(&format!("refs/remotes/{}/{}", remote, branch)) {
+            Ok(reference) => reference,
+            Err(e) if e.code() == git2::ErrorCode::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let remote_commit = remote_ref.peel_to_commit()?;
+        
+        BranchOps::sync_state(&self.repo, head.id(), remote_commit.id()).map(Some)
+    }
+    
+    /// Compare HEAD with the base branch
+    pub fn base_sync_state(&self, base: &str) -> Result<SyncState> {
+        let head = self.repo.head()?.peel_to_commit()?;
+        let base_commit = CommitOps::find_base_commit(&self.repo, base)?;
+        
+        BranchOps::sync_state(&self.repo, head.id(), base_commit.id())
+    }
+    
+    /// Fetch refspecs from a configured remote
+    pub fn fetch(&self, remote: &str, refspecs: &[&str]) -> Result<()> {
+        RemoteOps::fetch(&self.repo, remote, refspecs)
+    }
+    
+    /// Fetch refspecs from a URL, such as a fork
+    pub fn fetch_url(&self, url: &str, refspecs: &[&str]) -> Result<()> {
+        RemoteOps::fetch_url(&self.repo, url, refspecs)
+    }
+    
+    /// List the branches on a remote
+    pub fn remote_branches(&self, remote: &str) -> Result<Vec<String>> {
+        RemoteOps::list_branches(&self.repo, remote)
+    }
+    
+    /// Get the first line of a commit's message
+    pub fn commit_summary(&self, sha: &str) -> Result<String> {
+        let commit = self.repo.find_commit(Oid::from_str(sha)?)?;
+        Ok(commit.summary().unwrap_or_default().to_string())
+    }
+    
+    /// Cherry-pick a commit onto `onto` as local branch `branch`
+    ///
+    /// The branch is created, or moved if it exists, only when the commit
+    /// applies; the working tree isn't touched, so this works in bare clones.
+    pub fn cherry_pick_to_branch(&self, commit: &str, onto: Oid, branch: &str) -> Result<CherryPick> {
+        BranchOps::validate_branch_name(branch)?;
+        let result = CherryPickOps::cherry_pick(&self.repo, Oid::from_str(commit)?, onto)?;
+        if let CherryPick::Picked(oid) = result {
+            self.repo.reference(&format!("refs/heads/{}", branch), oid, true, "garry backport")?;
+        }
+        Ok(result)
+    }
+    
+    /// Resolve a full ref name to the commit it points at
+    pub fn resolve_ref(&self, name: &str) -> Result<Oid> {
+        Ok(self.repo.find_reference(name)?.peel_to_commit()?.id())
+    }
+    
+    /// Check out a commit with a detached HEAD
+    pub fn checkout_detached(&self, oid: Oid) -> Result<()> {
+        BranchOps::checkout_detached(&self.repo, oid)
+    }
+    
+    /// Point a local branch at a commit and check it out
+    pub fn checkout_branch_at(&self, name: &str, oid: Oid, upstream: Option<&str>) -> Result<()> {
+        BranchOps::checkout_branch_at(&self.repo, name, oid, upstream)
+    }
+    
+    /// Fetch a review's patchset from the remote, returning its commit
+    pub fn fetch_patchset(&self, remote: &str, review_id: &str, number: u32) -> Result<Oid> {
+        let patchset_ref = PatchsetOps::ref_name(review_id, number);
+        self.fetch(remote, &[&format!("+{}:{}", patchset_ref, patchset_ref)])?;
+        self.resolve_ref(&patchset_ref)
+    }
+    
+    /// Fetch every patchset of a review, returning them oldest first
+    pub fn fetch_patchsets(&self, remote: &str, review_id: &str) -> Result<Vec<(u32, Oid)>> {
+        self.fetch(remote, &[&PatchsetOps::fetch_refspec(review_id)])?;
+        PatchsetOps::list(&self.repo, review_id)
+    }
+    
+    /// Record HEAD as the review's next patchset and push it, returning its number
+    ///
+    /// Fetch the review's patchsets first so the numbering continues from
+    /// revisions uploaded elsewhere.
+    pub fn record_patchset(&self, remote: &str, review_id: &str) -> Result<u32> {
+        let head = self.repo.head()?.peel_to_commit()?.id();
+        let number = PatchsetOps::record(&self.repo, review_id, head)?;
+        let patchset_ref = PatchsetOps::ref_name(review_id, number);
+        RemoteOps::push_refs(&self.repo, remote, &[&format!("{}:{}", patchset_ref, patchset_ref)])?;
+        Ok(number)
+    }
+    
+    /// Diff two local patchsets of a review, ignoring rebases onto `base`
+    pub fn interdiff(&self, review_id: &str, base: &str, from: u32, to: u32) -> Result<Interdiff> {
+        let patchsets = PatchsetOps::list(&self.repo, review_id)?;
+        let find = |number: u32| {
+            patchsets.iter().find(|(n, _)| *n == number).copied().ok_or_else(|| {
+                GarryError::ReviewNotFound(format!("patchset {} of review {}", number, review_id))
+            })
+        };
+        let base_commit = CommitOps::find_base_commit(&self.repo, base)?;
+        PatchsetOps::interdiff(&self.repo, base_commit.id(), find(from)?, find(to)?)
+    }
+    
+    /// Map a line of a file at `commit` to the working tree, if it's unchanged
+    pub fn map_line(&self, commit: &str, path: &str, line: u32) -> Result<Option<u32>> {
+        LineOps::map_to_workdir(&self.repo, Oid::from_str(commit)?, path, line)
+    }
 }
@@ -2,5 +2,6 @@
 
 pub mod bot;
 pub mod git;
+pub mod presubmit;
 pub mod review;
 pub mod utils;
@@ -1,11 +1,9 @@
 mod cli;
-mod git;
-mod review;
-mod utils;
-mod bot;
+
+// Share the library modules so `crate::` paths in the CLI resolve to them
+use garry::{bot, git, presubmit, review, utils};
 
 use clap::Parser;
-use tracing_subscriber;
 use utils::error::Result;
 
 #[derive(Parser)]
@@ -0,0 +1,176 @@
+use crate::git::GitRepository;
+use crate::utils::config::{PresubmitCommand, PresubmitConfig};
+use crate::utils::error::Result;
+use tokio::process::Command;
+use tracing::{info, debug};
+
+/// Result of a single presubmit check
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheckResult {
+    pub name: String,
+    pub passed: bool,
+    /// Explanation shown when the check fails
+    pub details: String,
+}
+
+impl CheckResult {
+    fn pass(name: &str) -> Self {
+        Self { name: name.to_string(), passed: true, details: String::new() }
+    }
+    
+    fn fail(name: &str, details: String) -> Self {
+        Self { name: name.to_string(), passed: false, details }
+    }
+}
+
+/// Runs the configured presubmit checks against the current branch
+pub struct Presubmit<'a> {
+    config: &'a PresubmitConfig,
+    repo: &'a GitRepository,
+    base: &'a str,
+}
+
+impl<'a> Presubmit<'a> {
+    /// Create a presubmit runner for the branch's changes relative to `base`
+    pub fn new(config: &'a PresubmitConfig, repo: &'a GitRepository, base: &'a str) -> Self {
+        Self { config, repo, base }
+    }
+    
+    /// Run all built-in checks, then all configured commands
+    pub async fn run(&self) -> Result<Vec<CheckResult>> {
+        let mut results = self.run_builtin_checks()?;
+        
+        for command in &self.config.commands {
+            results.push(self.run_command(command).await?);
+        }
+        
+        Ok(results)
+    }
+    
+    /// Run the built-in checks that are enabled in the config
+    pub fn run_builtin_checks(&self) -> Result<Vec<CheckResult>> {
+        let mut results = Vec::new();
+        
+        if self.config.require_single_commit {
+            let count = self.repo.get_commit_count(self.base)?;
+            results.push(if count == 1 {
+                CheckResult::pass("single-commit")
+            } else {
+                CheckResult::fail("single-commit", format!(
+                    "Branch has {} commits; run 'garry squash' first", count
+                ))
+            });
+        }
+        
+        if self.config.max_subject_length > 0 || !self.config.subject_prefixes.is_empty() {
+            let message = self.repo.head_message()?;
+            results.push(self.check_message(&message));
+        }
+        
+        if self.config.max_diff_lines > 0 {
+            let lines = self.repo.diff_line_count(self.base)?;
+            results.push(if lines <= self.config.max_diff_lines {
+                CheckResult::pass("diff-size")
+            } else {
+                CheckResult::fail("diff-size", format!(
+                    "{} lines changed, limit is {}", lines, self.config.max_diff_lines
+                ))
+            });
+        }
+        
+        if !self.config.forbidden_files.is_empty() {
+            let files = self.repo.changed_files(self.base)?;
+            results.push(self.check_forbidden_files(&files)?);
+        }
+        
+        Ok(results)
+    }
+    
+    /// Check the commit message's subject line
+    pub fn check_message(&self, message: &str) -> CheckResult {
+        let subject = message.lines().next().unwrap_or_default().trim();
+        let mut problems = Vec::new();
+        
+        if subject.is_empty() {
+            problems.push("subject line is empty".to_string());
+        }
+        let length = subject.chars().count();
+        if self.config.max_subject_length > 0 && length > self.config.max_subject_length {
+            problems.push(format!(
+                "subject is {} characters, limit is {}", length, self.config.max_subject_length
+            ));
+        }
+        if !self.config.subject_prefixes.is_empty()
+            && !self.config.subject_prefixes.iter().any(|p| subject.starts_with(p.as_str()))
+        {
+            problems.push(format!(
+                "subject must start with one of: {}", self.config.subject_prefixes.join(", ")
+            ));
+        }
+        
+        if problems.is_empty() {
+            CheckResult::pass("commit-message")
+        } else {
+            CheckResult::fail("commit-message", problems.join("; "))
+        }
+    }
+    
+    /// Check that no changed file matches a forbidden pattern
+    pub fn check_forbidden_files(&self, files: &[String]) -> Result<CheckResult> {
+        let pathspec = git2::Pathspec::new(self.config.forbidden_files.iter())?;
+        let forbidden: Vec<&str> = files
+            .iter()
+            .map(String::as_str)
+            .filter(|file| pathspec.matches_path(std::path::Path::new(file), git2::PathspecFlags::DEFAULT))
+            .collect();
+        
+        Ok(if forbidden.is_empty() {
+            CheckResult::pass("forbidden-files")
+        } else {
+            CheckResult::fail("forbidden-files", format!("Forbidden files changed: {}", forbidden.join(", ")))
+        })
+    }
+    
+    /// Run a configured command from the repository root
+    async fn run_command(&self, command: &PresubmitCommand) -> Result<CheckResult> {
+        info!("Running presubmit command '{}': {}", command.name, command.run);
+        
+        let mut process = Command::new("sh");
+        process.arg("-c").arg(&command.run);
+        if let Some(workdir) = self.repo.workdir() {
+            process.current_dir(workdir);
+        }
+        
+        let output = process.output().await?;
+        debug!("Presubmit command '{}' exited with {}", command.name, output.status);
+        
+        if output.status.success() {
+            return Ok(CheckResult::pass(&command.name));
+        }
+        
+        // Keep the tail of the output; that's where tools report what broke
+        let combined = format!(
+            "{}{}",
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        );
+        let lines: Vec<&str> = combined.lines().collect();
+        let tail = lines[lines.len().saturating_sub(20)..].join("\n");
+        
+        Ok(CheckResult::fail(&command.name, format!("`{}` failed ({})\n{}", command.run, output.status, tail)))
+    }
+}
+
+/// Render check results as a Markdown section for the review description
+pub fn format_results(results: &[CheckResult]) -> String {
+    let mut section = String::from("### Presubmit\n\n");
+    for result in results {
+        if result.passed {
+            section.push_str(&format!("- ✅ {}\n", result.name));
+        } else {
+            let summary = result.details.lines().next().unwrap_or_default();
+            section.push_str(&format!("- ❌ {}: {}\n", result.name, summary));
+        }
+    }
+    section
+}
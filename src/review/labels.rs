@@ -0,0 +1,172 @@
+use std::collections::BTreeMap;
+use serde::{Deserialize, Serialize};
+use crate::review::ReviewDecision;
+use crate::utils::error::{GarryError, Result};
+
+/// Prefix of the lines that carry label votes in platforms without native labels
+pub const LABEL_TRAILER: &str = "Label:";
+
+/// A named label and the range of values reviewers can vote
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LabelDefinition {
+    pub name: String,
+    pub min: i8,
+    pub max: i8,
+}
+
+impl LabelDefinition {
+    pub fn new(name: &str, min: i8, max: i8) -> Self {
+        Self { name: name.to_string(), min, max }
+    }
+}
+
+/// Vote a label rule needs to see
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LabelThreshold {
+    /// Someone voted the label's maximum
+    Max,
+    /// Someone voted at least this value
+    AtLeast(i8),
+    /// No positive vote needed
+    Any,
+}
+
+/// A requirement on one label, e.g. "Code-Review needs max with no min"
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LabelRule {
+    pub label: String,
+    pub threshold: LabelThreshold,
+    /// A vote of the label's minimum blocks the review
+    pub block_on_min: bool,
+}
+
+/// Votes per label, per reviewer
+pub type LabelVotes = BTreeMap<String, BTreeMap<String, i8>>;
+
+/// Parse a comma-separated rule list
+///
+/// Each rule is a label name followed by what it needs:
+/// `needs max`, `needs +1` (or just `+1`), `no min`, or a threshold followed
+/// by `with no min`. For example: "Code-Review needs max with no min, Verified +1".
+pub fn parse_rules(rules: &str) -> Result<Vec<LabelRule>> {
+    rules
+        .split(',')
+        .map(str::trim)
+        .filter(|rule| !rule.is_empty())
+        .map(parse_rule)
+        .collect()
+}
+
+/// Parse a single rule
+fn parse_rule(rule: &str) -> Result<LabelRule> {
+    let invalid = |reason: &str| GarryError::ConfigError(format!("Invalid label rule '{}': {}", rule, reason));
+    
+    let mut words = rule.split_whitespace();
+    let label = words.next().ok_or_else(|| invalid("missing label"))?.to_string();
+    let mut rest: Vec<&str> = words.collect();
+    
+    let block_on_min = rest.ends_with(&["no", "min"]);
+    if block_on_min {
+        rest.truncate(rest.len() - 2);
+        if rest.last() == Some(&"with") {
+            rest.pop();
+        }
+    }
+    if rest.first() == Some(&"needs") {
+        rest.remove(0);
+    }
+    
+    let threshold = match rest.as_slice() {
+        [] if block_on_min => LabelThreshold::Any,
+        ["max"] => LabelThreshold::Max,
+        [value] => LabelThreshold::AtLeast(parse_value(value).map_err(|_| invalid("expected 'max' or a number"))?),
+        _ => return Err(invalid("expected 'needs max', 'needs +N' or 'no min'")),
+    };
+    
+    Ok(LabelRule { label, threshold, block_on_min })
+}
+
+/// Parse a vote like "Code-Review=+2"
+pub fn parse_vote(vote: &str) -> Result<(String, i8)> {
+    let (label, value) = vote
+        .split_once('=')
+        .ok_or_else(|| GarryError::InvalidVote(format!("'{}', expected Label=+N", vote)))?;
+    let value = parse_value(value.trim())
+        .map_err(|_| GarryError::InvalidVote(format!("'{}' doesn't have a number for a value", vote)))?;
+    Ok((label.trim().to_string(), value))
+}
+
+/// Parse a vote value, allowing an explicit "+"
+fn parse_value(value: &str) -> std::result::Result<i8, std::num::ParseIntError> {
+    value.trim_start_matches('+').parse()
+}
+
+/// Render label votes as trailer lines, e.g. "Label: Code-Review=+2"
+pub fn format_trailers(labels: &BTreeMap<String, i8>) -> String {
+    labels
+        .iter()
+        .map(|(label, value)| format!("{} {}={:+}", LABEL_TRAILER, label, value))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Read label votes back out of trailer lines
+pub fn parse_trailers(body: &str) -> BTreeMap<String, i8> {
+    body.lines()
+        .filter_map(|line| line.trim().strip_prefix(LABEL_TRAILER))
+        .filter_map(|vote| parse_vote(vote.trim()).ok())
+        .collect()
+}
+
+/// Map label votes to the platform-level review outcome
+///
+/// Any veto requests changes and the review label's maximum approves;
+/// anything else is just a comment.
+pub fn decision_for(definitions: &[LabelDefinition], review_label: &str, votes: &BTreeMap<String, i8>) -> ReviewDecision {
+    let definition = |label: &str| definitions.iter().find(|d| d.name == label);
+    
+    if votes.iter().any(|(label, value)| *value < 0 && definition(label).is_some_and(|d| d.min == *value)) {
+        ReviewDecision::ChangesRequested
+    } else if votes.get(review_label).is_some_and(|value| definition(review_label).is_some_and(|d| d.max == *value)) {
+        ReviewDecision::Approved
+    } else {
+        ReviewDecision::Commented
+    }
+}
+
+/// Check votes against the rules, returning what's still missing
+pub fn unmet_rules(rules: &[LabelRule], definitions: &[LabelDefinition], votes: &LabelVotes) -> Vec<String> {
+    let mut problems = Vec::new();
+    
+    for rule in rules {
+        let Some(definition) = definitions.iter().find(|d| d.name == rule.label) else {
+            problems.push(format!("Label {} is not defined", rule.label));
+            continue;
+        };
+        let label_votes = votes.get(&rule.label);
+        let values = || label_votes.into_iter().flat_map(|v| v.iter());
+        
+        if rule.block_on_min {
+            let vetoes: Vec<&str> = values()
+                .filter(|(_, value)| **value == definition.min)
+                .map(|(reviewer, _)| reviewer.as_str())
+                .collect();
+            if !vetoes.is_empty() {
+                problems.push(format!("{} {:+} from {}", rule.label, definition.min, vetoes.join(", ")));
+            }
+        }
+        
+        let needed = match rule.threshold {
+            LabelThreshold::Max => Some(definition.max),
+            LabelThreshold::AtLeast(value) => Some(value),
+            LabelThreshold::Any => None,
+        };
+        if let Some(needed) = needed {
+            if !values().any(|(_, value)| *value >= needed) {
+                problems.push(format!("{} needs {:+}", rule.label, needed));
+            }
+        }
+    }
+    
+    problems
+}
@@ -1,3 +1,10 @@
+pub mod labels;
+pub mod owners;
+pub mod policy;
+pub mod roles;
+pub mod suggest;
+
+use std::collections::BTreeMap;
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
 
@@ -29,12 +36,75 @@ pub struct ReviewStatus {
     pub approvals: Vec<Approval>,
     pub ci_status: CiStatus,
     pub mergeable: bool,
+    /// Author of the review
+    #[serde(default)]
+    pub author: String,
+    /// Every review submitted so far, oldest first
+    #[serde(default)]
+    pub votes: Vec<ReviewVote>,
+    /// SHA of the review's head commit
+    #[serde(default)]
+    pub head_sha: String,
+    /// When the head was last pushed, if known
+    #[serde(default)]
+    pub pushed_at: Option<DateTime<Utc>>,
+    /// Paths changed by the review
+    #[serde(default)]
+    pub changed_files: Vec<String>,
+    /// Whether the platform listed only some of the changed paths
+    #[serde(default)]
+    pub changed_files_truncated: bool,
+    /// Inline comment threads on the review's diff
+    #[serde(default)]
+    pub threads: Vec<CommentThread>,
+    /// Branch the review merges from
+    #[serde(default)]
+    pub head_branch: String,
+    /// Branch the review merges into
+    #[serde(default)]
+    pub target_branch: String,
+    /// Labels on the review, e.g. "backport/release-2.3"
+    #[serde(default)]
+    pub labels: Vec<String>,
+    /// SHA of the commit the review merged as, once it's merged
+    #[serde(default)]
+    pub merge_commit: Option<String>,
+}
+
+/// Summary of an open review, as shown in listings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReviewSummary {
+    pub id: ReviewId,
+    pub title: String,
+    pub author: String,
+    pub branch: String,
+    pub url: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    /// Reviewers whose review has been requested but not yet given
+    pub requested_reviewers: Vec<String>,
+}
+
+/// Where a review's changes live, for fetching them locally
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReviewHead {
+    /// Source branch name
+    pub branch: String,
+    /// Commit SHA at the head of the review
+    pub sha: String,
+    /// Ref on the target repository that points at the head, if the platform
+    /// exposes one (e.g. "refs/pull/123/head" on GitHub)
+    pub review_ref: Option<String>,
+    /// Clone URL of the fork the review comes from, if it isn't the target repository
+    pub fork_url: Option<String>,
 }
 
 /// State of a review
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ReviewState {
     Open,
+    /// Open for CI and early feedback, but never merged until marked ready
+    Draft,
     Approved,
     ChangesRequested,
     Merged,
@@ -48,6 +118,105 @@ pub struct Approval {
     pub approved_at: DateTime<Utc>,
 }
 
+/// A review submitted by a reviewer
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReviewVote {
+    pub reviewer: String,
+    pub decision: ReviewDecision,
+    pub submitted_at: DateTime<Utc>,
+    /// Commit the review was submitted against, if the platform records it
+    pub commit_id: Option<String>,
+    /// Label votes cast with the review, e.g. Code-Review=+2
+    #[serde(default)]
+    pub labels: BTreeMap<String, i8>,
+}
+
+/// Outcome of a submitted review
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ReviewDecision {
+    Approved,
+    ChangesRequested,
+    Commented,
+    Dismissed,
+}
+
+/// Side of the diff an inline comment is attached to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DiffSide {
+    /// The base version of the file (removed lines)
+    Old,
+    /// The review's version of the file (added and context lines)
+    New,
+}
+
+/// A single comment on a review, either top-level or in an inline thread
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReviewComment {
+    pub id: String,
+    pub author: String,
+    pub body: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A thread of inline comments on a line of a review's diff
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommentThread {
+    pub id: String,
+    pub file: String,
+    /// Line in the file as of `commit_id`, if the thread is on a line
+    pub line: Option<u32>,
+    pub side: DiffSide,
+    /// Commit the line number refers to
+    pub commit_id: Option<String>,
+    pub resolved: bool,
+    /// The lines the thread was on have since changed
+    pub outdated: bool,
+    /// Comments in the thread, oldest first
+    pub comments: Vec<ReviewComment>,
+}
+
+impl CommentThread {
+    /// Who started the thread
+    pub fn author(&self) -> &str {
+        self.comments.first().map(|c| c.author.as_str()).unwrap_or_default()
+    }
+}
+
+/// An inline comment to post on a review
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InlineComment {
+    pub file: String,
+    pub line: u32,
+    pub side: DiffSide,
+    pub body: String,
+}
+
+/// Access a user has to the repository, lowest first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RepoPermission {
+    None,
+    Read,
+    Triage,
+    Write,
+    Maintain,
+    Admin,
+}
+
+impl std::fmt::Display for RepoPermission {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let name = match self {
+            RepoPermission::None => "none",
+            RepoPermission::Read => "read",
+            RepoPermission::Triage => "triage",
+            RepoPermission::Write => "write",
+            RepoPermission::Maintain => "maintain",
+            RepoPermission::Admin => "admin",
+        };
+        write!(f, "{}", name)
+    }
+}
+
 /// CI/CD status
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum CiStatus {
@@ -57,3 +226,39 @@ pub enum CiStatus {
     Failed(Vec<String>),
     Cancelled,
 }
+
+impl CiStatus {
+    /// Combine individual check results into an overall status
+    pub fn from_checks(checks: &[CiCheck]) -> Self {
+        if checks.is_empty() {
+            return CiStatus::Pending;
+        }
+        
+        let mut has_running = false;
+        let mut failures = Vec::new();
+        
+        for check in checks {
+            match &check.status {
+                CiStatus::Success => {},
+                CiStatus::Failed(_) => failures.push(check.name.clone()),
+                CiStatus::Cancelled => return CiStatus::Cancelled,
+                CiStatus::Pending | CiStatus::Running => has_running = true,
+            }
+        }
+        
+        if !failures.is_empty() {
+            CiStatus::Failed(failures)
+        } else if has_running {
+            CiStatus::Running
+        } else {
+            CiStatus::Success
+        }
+    }
+}
+
+/// Result of a single CI check
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CiCheck {
+    pub name: String,
+    pub status: CiStatus,
+}
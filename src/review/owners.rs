@@ -0,0 +1,423 @@
+use std::collections::HashMap;
+use git2::{Commit, ObjectType, Repository, TreeWalkMode, TreeWalkResult};
+use crate::bot::adapter::VcsAdapter;
+use crate::utils::error::Result;
+use tracing::{debug, warn};
+
+/// Locations searched for a CODEOWNERS file, in order of precedence
+pub const CODEOWNERS_PATHS: &[&str] = &[".github/CODEOWNERS", "CODEOWNERS", ".gitlab/CODEOWNERS", "docs/CODEOWNERS"];
+
+/// A pattern and the owners of the paths it matches
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OwnerRule {
+    /// Gitignore-style path pattern
+    pub pattern: String,
+    /// Users, teams ("org/team") or emails; "*" means anyone
+    pub owners: Vec<String>,
+}
+
+/// A group of rules evaluated together, where the last matching rule wins
+///
+/// GitHub files have a single unnamed section. GitLab files can have named
+/// sections, each of which needs its own approval unless it's optional.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OwnerSection {
+    pub name: Option<String>,
+    /// Optional sections never require approval
+    pub optional: bool,
+    pub rules: Vec<OwnerRule>,
+}
+
+/// Paths in a change that need approval from the same owners
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OwnerGroup {
+    pub section: Option<String>,
+    pub pattern: String,
+    pub owners: Vec<String>,
+    pub paths: Vec<String>,
+}
+
+impl std::fmt::Display for OwnerGroup {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(section) = &self.section {
+            write!(f, "[{}] ", section)?;
+        }
+        write!(f, "{} ({})", self.pattern, self.owners.join(", "))
+    }
+}
+
+/// Code ownership parsed from CODEOWNERS and OWNERS files
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CodeOwners {
+    sections: Vec<OwnerSection>,
+}
+
+impl CodeOwners {
+    /// Parse a GitHub or GitLab CODEOWNERS file
+    pub fn parse_codeowners(content: &str) -> Self {
+        let mut sections = vec![OwnerSection::default()];
+        let mut default_owners: Vec<String> = Vec::new();
+        
+        for line in content.lines() {
+            let line = strip_comment(line);
+            if line.is_empty() {
+                continue;
+            }
+            
+            // GitLab section header: [Name], ^[Name] (optional), [Name][2] @default-owner
+            if let Some(header) = parse_section_header(line) {
+                let (section, owners) = header;
+                default_owners = owners;
+                sections.push(section);
+                continue;
+            }
+            
+            let mut parts = line.split_whitespace();
+            let pattern = match parts.next() {
+                Some(pattern) => pattern.replace("\\ ", " "),
+                None => continue,
+            };
+            let mut owners: Vec<String> = parts.map(normalize_owner).collect();
+            if owners.is_empty() {
+                owners = default_owners.clone();
+            }
+            
+            sections.last_mut().unwrap().rules.push(OwnerRule { pattern, owners });
+        }
+        
+        sections.retain(|s| !s.rules.is_empty());
+        Self { sections }
+    }
+    
+    /// Parse Gerrit-style OWNERS files, given as (directory, content) pairs
+    ///
+    /// Owners of a directory also own everything beneath it, unless a deeper
+    /// OWNERS file says `set noparent`.
+    pub fn parse_gerrit(files: &[(String, String)]) -> Self {
+        let mut files: Vec<&(String, String)> = files.iter().collect();
+        files.sort_by_key(|(dir, _)| {
+            let dir = dir.trim_matches('/');
+            dir.matches('/').count() + usize::from(!dir.is_empty())
+        });
+        
+        let mut effective: HashMap<String, Vec<String>> = HashMap::new();
+        let mut rules = Vec::new();
+        
+        for (dir, content) in files {
+            let dir = dir.trim_matches('/').to_string();
+            let mut owners = Vec::new();
+            let mut per_file = Vec::new();
+            let mut noparent = false;
+            
+            for line in content.lines() {
+                let line = strip_comment(line);
+                if line.is_empty() {
+                    continue;
+                }
+                if line == "set noparent" {
+                    noparent = true;
+                } else if let Some(rest) = line.strip_prefix("per-file ") {
+                    if let Some((globs, file_owners)) = rest.split_once('=') {
+                        let file_owners: Vec<String> = file_owners
+                            .split(',')
+                            .map(str::trim)
+                            .filter(|o| !o.is_empty() && *o != "set noparent")
+                            .map(normalize_owner)
+                            .collect();
+                        for glob in globs.split(',') {
+                            per_file.push((glob.trim().to_string(), file_owners.clone()));
+                        }
+                    }
+                } else if line.starts_with("include ") || line.starts_with("file:") {
+                    debug!("Ignoring unsupported OWNERS directive in '{}': {}", dir, line);
+                } else {
+                    owners.push(normalize_owner(line));
+                }
+            }
+            
+            if !noparent {
+                if let Some(parent) = nearest_ancestor(&effective, &dir) {
+                    for owner in parent {
+                        if !owners.contains(owner) {
+                            owners.push(owner.clone());
+                        }
+                    }
+                }
+            }
+            
+            let dir_pattern = if dir.is_empty() { "*".to_string() } else { format!("/{}/", dir) };
+            rules.push(OwnerRule { pattern: dir_pattern, owners: owners.clone() });
+            for (glob, mut file_owners) in per_file {
+                for owner in &owners {
+                    if !file_owners.contains(owner) {
+                        file_owners.push(owner.clone());
+                    }
+                }
+                let pattern = if dir.is_empty() { format!("/{}", glob) } else { format!("/{}/{}", dir, glob) };
+                rules.push(OwnerRule { pattern, owners: file_owners });
+            }
+            effective.insert(dir, owners);
+        }
+        
+        if rules.is_empty() {
+            return Self::default();
+        }
+        Self { sections: vec![OwnerSection { name: None, optional: false, rules }] }
+    }
+    
+    /// Load ownership from a commit's tree
+    ///
+    /// Reads the first CODEOWNERS file found and every OWNERS file. Ownership
+    /// should come from the base branch so a change can't grant itself owners.
+    pub fn from_commit(repo: &Repository, commit: &Commit) -> Result<Self> {
+        let tree = commit.tree()?;
+        let mut owners = Self::default();
+        
+        for path in CODEOWNERS_PATHS {
+            if let Ok(entry) = tree.get_path(std::path::Path::new(path)) {
+                let blob = repo.find_blob(entry.id())?;
+                owners.merge(Self::parse_codeowners(&String::from_utf8_lossy(blob.content())));
+                break;
+            }
+        }
+        
+        let mut gerrit_files = Vec::new();
+        tree.walk(TreeWalkMode::PreOrder, |dir, entry| {
+            if entry.name() == Some("OWNERS") && entry.kind() == Some(ObjectType::Blob) {
+                match repo.find_blob(entry.id()) {
+                    Ok(blob) => gerrit_files.push((
+                        dir.trim_end_matches('/').to_string(),
+                        String::from_utf8_lossy(blob.content()).into_owned(),
+                    )),
+                    Err(e) => warn!("Could not read {}OWNERS: {}", dir, e),
+                }
+            }
+            TreeWalkResult::Ok
+        })?;
+        owners.merge(Self::parse_gerrit(&gerrit_files));
+        
+        Ok(owners)
+    }
+    
+    /// Load ownership from a branch through the adapter
+    ///
+    /// Reads the same files as [`CodeOwners::from_commit`].
+    pub async fn from_adapter(adapter: &dyn VcsAdapter, branch: &str) -> Result<Self> {
+        let mut owners = Self::default();
+        
+        for path in CODEOWNERS_PATHS {
+            if let Some(content) = adapter.get_file_contents(path, branch).await? {
+                owners.merge(Self::parse_codeowners(&content));
+                break;
+            }
+        }
+        
+        let mut gerrit_files = Vec::new();
+        for path in adapter.find_files("OWNERS", branch).await? {
+            let dir = path.strip_suffix("OWNERS").unwrap_or_default().trim_end_matches('/').to_string();
+            if let Some(content) = adapter.get_file_contents(&path, branch).await? {
+                gerrit_files.push((dir, content));
+            }
+        }
+        owners.merge(Self::parse_gerrit(&gerrit_files));
+        
+        Ok(owners)
+    }
+    
+    /// Add another file's sections
+    pub fn merge(&mut self, other: CodeOwners) {
+        self.sections.extend(other.sections);
+    }
+    
+    /// Whether no ownership rules are defined
+    pub fn is_empty(&self) -> bool {
+        self.sections.is_empty()
+    }
+    
+    /// Every team ("org/team") named as an owner
+    pub fn teams(&self) -> Vec<String> {
+        let mut teams: Vec<String> = self.sections
+            .iter()
+            .flat_map(|s| s.rules.iter())
+            .flat_map(|r| r.owners.iter())
+            .filter(|o| o.contains('/') && !o.contains('@'))
+            .cloned()
+            .collect();
+        teams.sort();
+        teams.dedup();
+        teams
+    }
+    
+    /// Group changed paths by the owners whose approval they need
+    pub fn required_groups(&self, paths: &[String]) -> Vec<OwnerGroup> {
+        // Keyed by (section index, rule index) so identical patterns in
+        // different files stay separate requirements
+        let mut groups: Vec<((usize, usize), OwnerGroup)> = Vec::new();
+        
+        for path in paths {
+            for (section_index, section) in self.sections.iter().enumerate().filter(|(_, s)| !s.optional) {
+                let (rule_index, rule) = match section.rules.iter().enumerate().rev().find(|(_, r)| pattern_matches(&r.pattern, path)) {
+                    Some((index, rule)) if !rule.owners.is_empty() => (index, rule),
+                    _ => continue,
+                };
+                
+                let key = (section_index, rule_index);
+                match groups.iter_mut().find(|(k, _)| *k == key) {
+                    Some((_, group)) => group.paths.push(path.clone()),
+                    None => groups.push((key, OwnerGroup {
+                        section: section.name.clone(),
+                        pattern: rule.pattern.clone(),
+                        owners: rule.owners.clone(),
+                        paths: vec![path.clone()],
+                    })),
+                }
+            }
+        }
+        
+        groups.into_iter().map(|(_, group)| group).collect()
+    }
+    
+    /// Groups that none of the approvers can satisfy
+    ///
+    /// `team_members` maps team owners ("org/team") to their members.
+    pub fn missing_groups(
+        &self,
+        paths: &[String],
+        approvers: &[String],
+        team_members: &HashMap<String, Vec<String>>,
+    ) -> Vec<OwnerGroup> {
+        self.required_groups(paths)
+            .into_iter()
+            .filter(|group| !approvers.iter().any(|approver| {
+                group.owners.iter().any(|owner| owner_includes(owner, approver, team_members))
+            }))
+            .collect()
+    }
+}
+
+/// Look up the members of every team named in the ownership rules
+pub async fn resolve_team_members(adapter: &dyn VcsAdapter, owners: &CodeOwners) -> HashMap<String, Vec<String>> {
+    let mut members = HashMap::new();
+    for team in owners.teams() {
+        match adapter.get_team_members(&team).await {
+            Ok(logins) => {
+                members.insert(team, logins);
+            },
+            Err(e) => warn!("Could not resolve members of team {}: {}", team, e),
+        }
+    }
+    members
+}
+
+/// Whether an approver counts as the given owner
+fn owner_includes(owner: &str, approver: &str, team_members: &HashMap<String, Vec<String>>) -> bool {
+    if owner == "*" || owner.eq_ignore_ascii_case(approver) {
+        return true;
+    }
+    team_members
+        .get(owner)
+        .map(|members| members.iter().any(|m| m.eq_ignore_ascii_case(approver)))
+        .unwrap_or(false)
+}
+
+/// Drop a trailing comment and surrounding whitespace
+fn strip_comment(line: &str) -> &str {
+    line.split_once('#').map(|(before, _)| before).unwrap_or(line).trim()
+}
+
+/// Strip the "@" prefix used for users and teams
+fn normalize_owner(owner: &str) -> String {
+    owner.trim().trim_start_matches('@').to_string()
+}
+
+/// Parse a GitLab section header, returning the section and its default owners
+fn parse_section_header(line: &str) -> Option<(OwnerSection, Vec<String>)> {
+    let (optional, rest) = match line.strip_prefix('^') {
+        Some(rest) => (true, rest),
+        None => (false, line),
+    };
+    let rest = rest.strip_prefix('[')?;
+    let (name, mut rest) = rest.split_once(']')?;
+    
+    // Skip an approval count like "[2]"
+    if let Some(count) = rest.strip_prefix('[') {
+        rest = count.split_once(']').map(|(_, after)| after).unwrap_or("");
+    }
+    
+    let section = OwnerSection { name: Some(name.trim().to_string()), optional, rules: vec![] };
+    Some((section, rest.split_whitespace().map(normalize_owner).collect()))
+}
+
+/// Owners of the closest ancestor directory that has an OWNERS file
+fn nearest_ancestor<'a>(effective: &'a HashMap<String, Vec<String>>, dir: &str) -> Option<&'a Vec<String>> {
+    if dir.is_empty() {
+        return None;
+    }
+    let mut current = dir;
+    while let Some((parent, _)) = current.rsplit_once('/') {
+        if let Some(owners) = effective.get(parent) {
+            return Some(owners);
+        }
+        current = parent;
+    }
+    effective.get("")
+}
+
+/// Match a path against a gitignore-style ownership pattern
+pub fn pattern_matches(pattern: &str, path: &str) -> bool {
+    let dir_only = pattern.ends_with('/');
+    let trimmed = pattern.trim_end_matches('/');
+    let anchored = trimmed.starts_with('/') || trimmed.contains('/');
+    let trimmed = trimmed.trim_start_matches('/');
+    let glob = if anchored { trimmed.to_string() } else { format!("**/{}", trimmed) };
+    
+    if !dir_only && glob_match(glob.as_bytes(), path.as_bytes()) {
+        return true;
+    }
+    
+    // A pattern naming a directory owns everything beneath it; a wildcard in
+    // the last segment (e.g. "docs/*") only matches that directory's files
+    let last_segment = glob.rsplit('/').next().unwrap_or_default();
+    if dir_only || !last_segment.contains(['*', '?']) {
+        let mut ancestor = path;
+        while let Some((parent, _)) = ancestor.rsplit_once('/') {
+            if glob_match(glob.as_bytes(), parent.as_bytes()) {
+                return true;
+            }
+            ancestor = parent;
+        }
+    }
+    
+    false
+}
+
+/// Glob match where `*` and `?` stay within a path segment and `**` spans segments
+pub(crate) fn glob_match(pattern: &[u8], path: &[u8]) -> bool {
+    match (pattern.first(), path.first()) {
+        (None, None) => true,
+        (None, Some(_)) => false,
+        (Some(b'*'), _) if pattern.get(1) == Some(&b'*') => {
+            let rest = &pattern[2..];
+            // "**/" can also match no directories at all
+            if rest.first() == Some(&b'/') && glob_match(&rest[1..], path) {
+                return true;
+            }
+            (0..=path.len()).any(|i| glob_match(rest, &path[i..]))
+        },
+        (Some(b'*'), _) => {
+            let rest = &pattern[1..];
+            for i in 0..=path.len() {
+                if glob_match(rest, &path[i..]) {
+                    return true;
+                }
+                if i < path.len() && path[i] == b'/' {
+                    break;
+                }
+            }
+            false
+        },
+        (Some(b'?'), Some(c)) if *c != b'/' => glob_match(&pattern[1..], &path[1..]),
+        (Some(p), Some(c)) if p == c => glob_match(&pattern[1..], &path[1..]),
+        _ => false,
+    }
+}
@@ -0,0 +1,248 @@
+use std::collections::HashMap;
+use crate::review::{Approval, ReviewDecision, ReviewState, ReviewStatus, ReviewVote};
+use crate::review::labels::{self, LabelRule, LabelVotes};
+use crate::review::owners::{CodeOwners, OwnerGroup};
+use crate::utils::config::PolicyConfig;
+use tracing::warn;
+
+/// Result of evaluating a review against the approval policy
+#[derive(Debug, Clone)]
+pub struct PolicyDecision {
+    /// State after applying the policy
+    pub state: ReviewState,
+    /// Approvals that count towards the requirement
+    pub approvals: Vec<Approval>,
+    /// Why the review isn't approved yet
+    pub problems: Vec<String>,
+    /// Ownership groups that still need an owner's approval
+    pub missing_owners: Vec<OwnerGroup>,
+    /// Current label votes, by label and reviewer
+    pub labels: LabelVotes,
+}
+
+impl PolicyDecision {
+    /// Whether the policy considers the review approved
+    pub fn is_approved(&self) -> bool {
+        self.state == ReviewState::Approved
+    }
+}
+
+/// Decides whether a review is approved from its individual votes, rather
+/// than trusting the platform's summary state
+#[derive(Debug, Clone, Default)]
+pub struct ApprovalPolicy {
+    config: PolicyConfig,
+    owners: CodeOwners,
+    team_members: HashMap<String, Vec<String>>,
+    label_rules: Vec<LabelRule>,
+}
+
+impl ApprovalPolicy {
+    /// Create a policy from configuration
+    pub fn new(config: PolicyConfig) -> Self {
+        // Config::validate rejects bad rules, so this only trips on hand-built configs
+        let label_rules = labels::parse_rules(&config.label_rules).unwrap_or_else(|e| {
+            warn!("Ignoring label rules: {}", e);
+            vec![]
+        });
+        Self {
+            config,
+            owners: CodeOwners::default(),
+            team_members: HashMap::new(),
+            label_rules,
+        }
+    }
+    
+    /// Require code owner approval, resolving team owners with `team_members`
+    pub fn with_owners(mut self, owners: CodeOwners, team_members: HashMap<String, Vec<String>>) -> Self {
+        self.owners = owners;
+        self.team_members = team_members;
+        self
+    }
+    
+    /// Evaluate a review's votes against the policy
+    pub fn evaluate(&self, status: &ReviewStatus) -> PolicyDecision {
+        // Merged and closed reviews are final whatever the votes say
+        if matches!(status.state, ReviewState::Merged | ReviewState::Closed) {
+            return PolicyDecision {
+                state: status.state.clone(),
+                approvals: vec![],
+                problems: vec![],
+                missing_owners: vec![],
+                labels: LabelVotes::new(),
+            };
+        }
+        
+        let mut approvals = Vec::new();
+        let mut problems = Vec::new();
+        let mut changes_requested = Vec::new();
+        
+        for vote in Self::latest_votes(&status.votes) {
+            if !self.config.allow_self_approval && vote.reviewer == status.author {
+                continue;
+            }
+            if self.is_bot(&vote.reviewer) {
+                continue;
+            }
+            
+            match vote.decision {
+                ReviewDecision::Approved => {
+                    if self.config.dismiss_stale_approvals && Self::is_stale(vote, status) {
+                        problems.push(format!("Approval from {} predates the latest push", vote.reviewer));
+                    } else {
+                        approvals.push(Approval {
+                            reviewer: vote.reviewer.clone(),
+                            approved_at: vote.submitted_at,
+                        });
+                    }
+                },
+                ReviewDecision::ChangesRequested => changes_requested.push(vote.reviewer.clone()),
+                ReviewDecision::Commented | ReviewDecision::Dismissed => {},
+            }
+        }
+        
+        // Every owned path needs at least one approval from its owners
+        let missing_owners = if self.config.require_code_owner_approval {
+            let approvers: Vec<String> = approvals.iter().map(|a| a.reviewer.clone()).collect();
+            self.owners.missing_groups(&status.changed_files, &approvers, &self.team_members)
+        } else {
+            vec![]
+        };
+        if !missing_owners.is_empty() {
+            problems.push(format!("{} ownership group(s) still need an owner's approval", missing_owners.len()));
+        }
+        
+        let labels = self.label_votes(status);
+        let mut blocking = labels::unmet_rules(&self.label_rules, &self.config.labels, &labels);
+        
+        // Owners of the paths the platform didn't list can't be checked
+        if self.config.require_code_owner_approval && status.changed_files_truncated && !self.owners.is_empty() {
+            blocking.push("Too many changed files to check code ownership".to_string());
+        }
+        
+        if self.config.block_on_unresolved_threads {
+            let unresolved = status.threads.iter().filter(|t| !t.resolved).count();
+            if unresolved > 0 {
+                blocking.push(format!("{} unresolved comment thread(s)", unresolved));
+            }
+        }
+        
+        let state = if !changes_requested.is_empty() {
+            problems.push(format!("Changes requested by {}", changes_requested.join(", ")));
+            problems.extend(blocking);
+            ReviewState::ChangesRequested
+        } else if !blocking.is_empty() {
+            problems.extend(blocking);
+            ReviewState::Open
+        } else if approvals.len() >= self.config.required_approvals && missing_owners.is_empty() {
+            ReviewState::Approved
+        } else {
+            if approvals.len() < self.config.required_approvals {
+                problems.push(format!(
+                    "{} of {} required approvals",
+                    approvals.len(),
+                    self.config.required_approvals
+                ));
+            }
+            ReviewState::Open
+        };
+        
+        // Drafts are reviewed like any other review but never approved
+        let state = if status.state == ReviewState::Draft {
+            problems.insert(0, "Review is a draft".to_string());
+            ReviewState::Draft
+        } else {
+            state
+        };
+        
+        PolicyDecision { state, approvals, problems, missing_owners, labels }
+    }
+    
+    /// Collect each reviewer's current vote on every label
+    ///
+    /// Approvals and change requests vote the review label's maximum and
+    /// minimum unless the review voted on it explicitly. Stale positive votes
+    /// don't count, but vetoes stand until the reviewer changes them.
+    fn label_votes(&self, status: &ReviewStatus) -> LabelVotes {
+        let review_label = self.config.labels.iter().find(|l| l.name == self.config.review_label);
+        let mut votes: Vec<&ReviewVote> = status.votes.iter().collect();
+        votes.sort_by_key(|vote| vote.submitted_at);
+        
+        let mut current = LabelVotes::new();
+        for vote in votes {
+            if !self.config.allow_self_approval && vote.reviewer == status.author {
+                continue;
+            }
+            if self.is_bot(&vote.reviewer) || vote.decision == ReviewDecision::Dismissed {
+                continue;
+            }
+            
+            let mut values = vote.labels.clone();
+            if let Some(label) = review_label {
+                match vote.decision {
+                    ReviewDecision::Approved => {
+                        values.entry(label.name.clone()).or_insert(label.max);
+                    },
+                    ReviewDecision::ChangesRequested => {
+                        values.entry(label.name.clone()).or_insert(label.min);
+                    },
+                    ReviewDecision::Commented | ReviewDecision::Dismissed => {},
+                }
+            }
+            
+            let stale = self.config.dismiss_stale_approvals && Self::is_stale(vote, status);
+            for (label, value) in values {
+                let reviewers = current.entry(label).or_default();
+                if stale && value > 0 {
+                    reviewers.remove(&vote.reviewer);
+                } else {
+                    reviewers.insert(vote.reviewer.clone(), value);
+                }
+            }
+        }
+        
+        current.retain(|_, reviewers| !reviewers.is_empty());
+        current
+    }
+    
+    /// Keep only each reviewer's latest decisive vote, in first-vote order
+    ///
+    /// Comments don't replace an earlier approval or change request.
+    fn latest_votes(votes: &[ReviewVote]) -> Vec<&ReviewVote> {
+        let mut order = Vec::new();
+        let mut latest: HashMap<&str, &ReviewVote> = HashMap::new();
+        
+        for vote in votes {
+            if vote.decision == ReviewDecision::Commented && latest.contains_key(vote.reviewer.as_str()) {
+                continue;
+            }
+            match latest.get(vote.reviewer.as_str()) {
+                Some(existing) if existing.submitted_at > vote.submitted_at => {},
+                Some(_) => {
+                    latest.insert(&vote.reviewer, vote);
+                },
+                None => {
+                    order.push(vote.reviewer.as_str());
+                    latest.insert(&vote.reviewer, vote);
+                },
+            }
+        }
+        
+        order.into_iter().map(|reviewer| latest[reviewer]).collect()
+    }
+    
+    /// Whether a vote was cast before the review's latest push
+    fn is_stale(vote: &ReviewVote, status: &ReviewStatus) -> bool {
+        if let Some(commit_id) = &vote.commit_id {
+            if !status.head_sha.is_empty() {
+                return *commit_id != status.head_sha;
+            }
+        }
+        status.pushed_at.map(|pushed| vote.submitted_at < pushed).unwrap_or(false)
+    }
+    
+    /// Whether an account is a bot whose approval never counts
+    fn is_bot(&self, reviewer: &str) -> bool {
+        reviewer.ends_with("[bot]") || self.config.bot_users.iter().any(|bot| bot == reviewer)
+    }
+}
@@ -0,0 +1,185 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use crate::bot::adapter::VcsAdapter;
+use crate::review::{ReviewDecision, ReviewStatus};
+use crate::utils::config::RolesConfig;
+use crate::utils::error::{GarryError, Result};
+use tracing::{debug, warn};
+
+/// What someone is allowed to do in Garry
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Role {
+    Developer,
+    Maintainer,
+    Bot,
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Role::Developer => "developer",
+            Role::Maintainer => "maintainer",
+            Role::Bot => "bot",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// An action that needs more than the developer role
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    /// Approve a review, or vote in its favour on a label
+    Approve,
+    /// Add, remove, hold or retry reviews in the merge queue
+    ControlQueue,
+    /// Move a review ahead of others in the merge queue
+    SetPriority,
+    /// Undo a decision someone else made, such as releasing an author's hold
+    Override,
+}
+
+impl Action {
+    /// Roles that may take the action
+    pub fn allowed_roles(&self) -> &'static [Role] {
+        match self {
+            Action::Approve => &[Role::Maintainer],
+            Action::ControlQueue => &[Role::Maintainer, Role::Bot],
+            Action::SetPriority => &[Role::Maintainer],
+            Action::Override => &[Role::Maintainer],
+        }
+    }
+    
+    /// Whether `role` may take the action
+    pub fn allows(&self, role: Role) -> bool {
+        self.allowed_roles().contains(&role)
+    }
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Action::Approve => "approve reviews",
+            Action::ControlQueue => "control the merge queue",
+            Action::SetPriority => "change merge queue priority",
+            Action::Override => "override other people's decisions",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Decides users' roles from `[roles]` configuration and, optionally, their
+/// access on the forge
+#[derive(Debug, Clone, Default)]
+pub struct Roles {
+    config: RolesConfig,
+    team_members: HashMap<String, Vec<String>>,
+}
+
+impl Roles {
+    /// Create roles from configuration, without resolving team maintainers
+    pub fn new(config: RolesConfig) -> Self {
+        Self { config, team_members: HashMap::new() }
+    }
+    
+    /// Resolve team maintainers ("org/team") with `team_members`
+    pub fn with_team_members(mut self, team_members: HashMap<String, Vec<String>>) -> Self {
+        self.team_members = team_members;
+        self
+    }
+    
+    /// Create roles from configuration, looking up team maintainers on the forge
+    pub async fn load(config: RolesConfig, adapter: &dyn VcsAdapter) -> Self {
+        let mut team_members = HashMap::new();
+        for team in config.maintainers.iter().filter(|m| m.contains('/')) {
+            match adapter.get_team_members(team).await {
+                Ok(logins) => {
+                    team_members.insert(team.clone(), logins);
+                },
+                Err(e) => warn!("Could not resolve members of team {}: {}", team, e),
+            }
+        }
+        Self::new(config).with_team_members(team_members)
+    }
+    
+    /// Whether a user is one of the configured bot accounts
+    ///
+    /// Anyone can register a GitHub App, so a "[bot]" login alone doesn't make a bot.
+    pub fn is_bot(&self, user: &str) -> bool {
+        self.config.bots.iter().any(|bot| bot.eq_ignore_ascii_case(user))
+    }
+    
+    /// Whether a user is a maintainer by configuration alone
+    pub fn is_configured_maintainer(&self, user: &str) -> bool {
+        self.config.maintainers.iter().any(|maintainer| {
+            maintainer.eq_ignore_ascii_case(user)
+                || self.team_members
+                    .get(maintainer)
+                    .is_some_and(|members| members.iter().any(|m| m.eq_ignore_ascii_case(user)))
+        })
+    }
+    
+    /// Get a user's role, asking the forge for their access if configured to
+    pub async fn role_of(&self, adapter: &dyn VcsAdapter, user: &str) -> Result<Role> {
+        if self.is_bot(user) {
+            return Ok(Role::Bot);
+        }
+        if self.is_configured_maintainer(user) {
+            return Ok(Role::Maintainer);
+        }
+        if self.config.sync_forge_permissions {
+            let permission = adapter.get_permission(user).await?;
+            debug!("{} has {} access on the forge", user, permission);
+            if permission >= self.config.maintainer_permission {
+                return Ok(Role::Maintainer);
+            }
+        }
+        Ok(Role::Developer)
+    }
+    
+    /// Check `user` may take `action`, returning their role
+    pub async fn check(&self, adapter: &dyn VcsAdapter, user: &str, action: Action) -> Result<Role> {
+        let role = self.role_of(adapter, user).await?;
+        if !action.allows(role) {
+            let allowed: Vec<String> = action.allowed_roles().iter().map(Role::to_string).collect();
+            return Err(GarryError::PermissionDenied(format!(
+                "@{} is a {}, and only a {} can {}",
+                user,
+                role,
+                allowed.join(" or "),
+                action
+            )));
+        }
+        Ok(role)
+    }
+    
+    /// Downgrade votes in a review's favour from users who may not approve
+    ///
+    /// Their approvals count as comments and their positive label votes are
+    /// dropped. Votes against the review stand whoever cast them.
+    pub async fn discard_unauthorized_votes(&self, adapter: &dyn VcsAdapter, status: &mut ReviewStatus) -> Result<()> {
+        let voters: HashSet<String> = status.votes.iter()
+            .filter(|vote| vote.decision == ReviewDecision::Approved || vote.labels.values().any(|&v| v > 0))
+            .map(|vote| vote.reviewer.clone())
+            .collect();
+        
+        let mut unauthorized = HashSet::new();
+        for voter in voters {
+            if !Action::Approve.allows(self.role_of(adapter, &voter).await?) {
+                unauthorized.insert(voter);
+            }
+        }
+        if unauthorized.is_empty() {
+            return Ok(());
+        }
+        
+        debug!("Ignoring approvals on review {} from {:?}", status.id, unauthorized);
+        for vote in status.votes.iter_mut().filter(|vote| unauthorized.contains(&vote.reviewer)) {
+            if vote.decision == ReviewDecision::Approved {
+                vote.decision = ReviewDecision::Commented;
+            }
+            vote.labels.retain(|_, value| *value <= 0);
+        }
+        status.approvals.retain(|approval| !unauthorized.contains(&approval.reviewer));
+        Ok(())
+    }
+}
@@ -0,0 +1,121 @@
+use std::collections::HashMap;
+use crate::review::ReviewSummary;
+use crate::review::owners::CodeOwners;
+use crate::utils::config::ReviewersConfig;
+
+/// A proposed reviewer and why they were picked
+#[derive(Debug, Clone, PartialEq)]
+pub struct Suggestion {
+    pub reviewer: String,
+    /// Relevance after load balancing; higher is better
+    pub score: f64,
+    /// Open reviews already waiting on the reviewer
+    pub open_reviews: usize,
+    pub reasons: Vec<String>,
+}
+
+/// Suggests reviewers from code ownership and blame history, preferring
+/// reviewers with fewer open review requests
+#[derive(Debug, Clone, Default)]
+pub struct ReviewerSuggester {
+    config: ReviewersConfig,
+    owners: CodeOwners,
+    team_members: HashMap<String, Vec<String>>,
+    blame: HashMap<String, usize>,
+    open_reviews: HashMap<String, usize>,
+}
+
+impl ReviewerSuggester {
+    /// Create a suggester from configuration
+    pub fn new(config: ReviewersConfig) -> Self {
+        Self { config, ..Self::default() }
+    }
+    
+    /// Score owners of the touched paths, resolving team owners with `team_members`
+    pub fn with_owners(mut self, owners: CodeOwners, team_members: HashMap<String, Vec<String>>) -> Self {
+        self.owners = owners;
+        self.team_members = team_members;
+        self
+    }
+    
+    /// Score authors by how many lines of the touched files they wrote
+    pub fn with_blame(mut self, lines_by_email: HashMap<String, usize>) -> Self {
+        self.blame = lines_by_email;
+        self
+    }
+    
+    /// Count how many open reviews are already waiting on each reviewer
+    pub fn with_open_reviews(mut self, reviews: &[ReviewSummary]) -> Self {
+        self.open_reviews.clear();
+        for reviewer in reviews.iter().flat_map(|r| r.requested_reviewers.iter()) {
+            *self.open_reviews.entry(reviewer.to_lowercase()).or_insert(0) += 1;
+        }
+        self
+    }
+    
+    /// Suggest reviewers for `author`'s change to `changed_files`, best first
+    ///
+    /// Each ownership group a reviewer belongs to is worth one point, and
+    /// blame adds their share of the touched lines. The total is divided by
+    /// one plus their open review count.
+    pub fn suggest(&self, author: &str, changed_files: &[String]) -> Vec<Suggestion> {
+        let mut scores: HashMap<String, (f64, Vec<String>)> = HashMap::new();
+        
+        for group in self.owners.required_groups(changed_files) {
+            for owner in &group.owners {
+                for user in self.expand_owner(owner) {
+                    let entry = scores.entry(user).or_default();
+                    entry.0 += 1.0;
+                    entry.1.push(format!("owns {}", group.pattern));
+                }
+            }
+        }
+        
+        let total_lines: usize = self.blame.values().sum();
+        for (email, lines) in &self.blame {
+            let entry = scores.entry(self.resolve(email)).or_default();
+            entry.0 += *lines as f64 / total_lines as f64;
+            entry.1.push(format!("wrote {} lines of the touched files", lines));
+        }
+        
+        let mut suggestions: Vec<Suggestion> = scores
+            .into_iter()
+            .filter(|(reviewer, _)| self.is_candidate(reviewer, author))
+            .map(|(reviewer, (score, reasons))| {
+                let open_reviews = self.open_reviews.get(&reviewer.to_lowercase()).copied().unwrap_or(0);
+                Suggestion { score: score / (1 + open_reviews) as f64, open_reviews, reasons, reviewer }
+            })
+            .collect();
+        
+        suggestions.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.reviewer.cmp(&b.reviewer)));
+        suggestions.truncate(self.config.count);
+        suggestions
+    }
+    
+    /// Individual usernames behind an owner entry
+    fn expand_owner(&self, owner: &str) -> Vec<String> {
+        if owner == "*" {
+            return vec![];
+        }
+        match self.team_members.get(owner) {
+            Some(members) => members.clone(),
+            None => vec![self.resolve(owner)],
+        }
+    }
+    
+    /// Map an email to a username through the configured aliases
+    fn resolve(&self, identity: &str) -> String {
+        self.config.aliases.get(identity).cloned().unwrap_or_else(|| identity.to_string())
+    }
+    
+    /// Whether someone can be suggested for the author's review
+    ///
+    /// Unresolved emails and unexpanded teams can't be requested as reviewers.
+    fn is_candidate(&self, reviewer: &str, author: &str) -> bool {
+        !reviewer.eq_ignore_ascii_case(author)
+            && !reviewer.contains('@')
+            && !reviewer.contains('/')
+            && !reviewer.ends_with("[bot]")
+            && !self.config.exclude.iter().any(|e| e.eq_ignore_ascii_case(reviewer))
+    }
+}
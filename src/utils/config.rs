@@ -1,7 +1,12 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::Path;
 use std::fs;
+use chrono::Weekday;
 use crate::utils::error::{GarryError, Result};
+use crate::bot::templates::{MessageFormat, DEFAULT_LOCALE};
+use crate::review::RepoPermission;
+use crate::review::labels::{self, LabelDefinition};
 
 /// Main configuration structure
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -9,6 +14,28 @@ pub struct Config {
     pub vcs: VcsConfig,
     pub bot: BotConfig,
     pub git: GitConfig,
+    #[serde(default)]
+    pub presubmit: PresubmitConfig,
+    #[serde(default)]
+    pub policy: PolicyConfig,
+    #[serde(default)]
+    pub reviewers: ReviewersConfig,
+    #[serde(default)]
+    pub roles: RolesConfig,
+    #[serde(default)]
+    pub notifications: NotificationsConfig,
+    #[serde(default)]
+    pub digest: DigestConfig,
+    #[serde(default)]
+    pub api: ApiConfig,
+    /// Repositories the bot manages; empty means just `vcs.repository`
+    #[serde(default)]
+    pub repositories: Vec<RepositoryConfig>,
+    /// Branches besides `bot.main_branch` the queue merges into
+    #[serde(default)]
+    pub targets: Vec<TargetConfig>,
+    #[serde(default)]
+    pub backport: BackportConfig,
 }
 
 impl Config {
@@ -64,6 +91,9 @@ impl Config {
         if let Ok(repo) = std::env::var("GARRY_VCS_REPOSITORY") {
             config.vcs.repository = repo;
         }
+        if let Ok(token) = std::env::var("GARRY_API_TOKEN") {
+            config.api.token = Some(token);
+        }
         
         config.validate()?;
         Ok(config)
@@ -71,6 +101,19 @@ impl Config {
     
     /// Validate configuration
     pub fn validate(&self) -> Result<()> {
+        if !self.repositories.is_empty() {
+            let mut seen = std::collections::HashSet::new();
+            for config in self.repository_configs() {
+                if !seen.insert(config.vcs.repository.clone()) {
+                    return Err(GarryError::ConfigError(format!(
+                        "Repository {} is listed more than once", config.vcs.repository
+                    )));
+                }
+                config.validate()?;
+            }
+            return Ok(());
+        }
+        
         if self.vcs.token.is_empty() {
             return Err(GarryError::ConfigError("VCS token is required".to_string()));
         }
@@ -82,8 +125,75 @@ impl Config {
                 "Repository must be in format 'owner/repo'".to_string()
             ));
         }
+        labels::parse_rules(&self.policy.label_rules)?;
+        for target in &self.targets {
+            if let Some(policy) = &target.policy {
+                labels::parse_rules(&policy.label_rules)?;
+            }
+        }
+        if self.digest.hour > 23 {
+            return Err(GarryError::ConfigError(format!(
+                "Digest hour must be between 0 and 23, got {}", self.digest.hour
+            )));
+        }
         Ok(())
     }
+    
+    /// The configuration for each repository the bot manages
+    ///
+    /// Each `[[repositories]]` entry overrides the top-level settings it
+    /// sets. Without entries, this is just the top-level configuration.
+    pub fn repository_configs(&self) -> Vec<Config> {
+        if self.repositories.is_empty() {
+            return vec![self.clone()];
+        }
+        self.repositories.iter().map(|repository| {
+            let mut config = self.clone();
+            config.repositories = vec![];
+            config.vcs.repository = repository.repository.clone();
+            if let Some(platform) = &repository.platform {
+                config.vcs.platform = platform.clone();
+            }
+            if let Some(host) = &repository.host {
+                config.vcs.host = host.clone();
+            }
+            if let Some(token) = &repository.token {
+                config.vcs.token = token.clone();
+            }
+            if let Some(main_branch) = &repository.main_branch {
+                config.bot.main_branch = main_branch.clone();
+            }
+            if let Some(policy) = &repository.policy {
+                config.policy = policy.clone();
+            }
+            if let Some(roles) = &repository.roles {
+                config.roles = roles.clone();
+            }
+            if let Some(targets) = &repository.targets {
+                config.targets = targets.clone();
+            }
+            if let Some(backport) = &repository.backport {
+                config.backport = backport.clone();
+            }
+            config
+        }).collect()
+    }
+    
+    /// Whether any branch's policy blocks reviews on unresolved comment threads
+    pub fn blocks_on_unresolved_threads(&self) -> bool {
+        self.policy.block_on_unresolved_threads
+            || self.targets.iter().any(|t| t.policy.as_ref().is_some_and(|p| p.block_on_unresolved_threads))
+    }
+    
+    /// Ref changes into `target` are compared against: `git.squash_base` for
+    /// the main branch (or no target), `<remote>/<target>` for others
+    pub fn base_for(&self, target: &str) -> String {
+        if target.is_empty() || target == self.bot.main_branch {
+            self.git.squash_base.clone()
+        } else {
+            format!("{}/{}", self.git.default_remote, target)
+        }
+    }
 }
 
 /// VCS platform configuration
@@ -99,6 +209,66 @@ pub struct VcsConfig {
     pub repository: String,
 }
 
+/// A repository managed by the bot, with settings that differ from the top level
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RepositoryConfig {
+    /// Repository in format "owner/repo"
+    pub repository: String,
+    /// Platform type (default: `vcs.platform`)
+    #[serde(default)]
+    pub platform: Option<String>,
+    /// VCS host (default: `vcs.host`)
+    #[serde(default)]
+    pub host: Option<String>,
+    /// Authentication token (default: `vcs.token`)
+    #[serde(default)]
+    pub token: Option<String>,
+    /// Main branch name (default: `bot.main_branch`)
+    #[serde(default)]
+    pub main_branch: Option<String>,
+    /// Approval policy, replacing `[policy]` as a whole
+    #[serde(default)]
+    pub policy: Option<PolicyConfig>,
+    /// Roles, replacing `[roles]` as a whole
+    #[serde(default)]
+    pub roles: Option<RolesConfig>,
+    /// Target branches, replacing `[[targets]]` as a whole
+    #[serde(default)]
+    pub targets: Option<Vec<TargetConfig>>,
+    /// Backports, replacing `[backport]` as a whole
+    #[serde(default)]
+    pub backport: Option<BackportConfig>,
+}
+
+/// Branches, besides the main branch, that reviews may target and the queue merges into
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TargetConfig {
+    /// Branch name or pattern, e.g. "release/*"
+    pub branch: String,
+    /// Approval policy for reviews into these branches (default: `[policy]`)
+    #[serde(default)]
+    pub policy: Option<PolicyConfig>,
+}
+
+/// Backports of merged reviews to other branches
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct BackportConfig {
+    /// Labels starting with this ask the bot to backport a review, e.g. "backport/release-2.3"
+    pub label_prefix: String,
+    /// Clone of the repository the bot cherry-picks in; without one the bot doesn't backport
+    pub checkout: Option<String>,
+}
+
+impl Default for BackportConfig {
+    fn default() -> Self {
+        Self {
+            label_prefix: "backport/".to_string(),
+            checkout: None,
+        }
+    }
+}
+
 /// Bot service configuration
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct BotConfig {
@@ -110,6 +280,23 @@ pub struct BotConfig {
     pub ci_timeout: u64,
     /// Main branch name (usually "main" or "master")
     pub main_branch: String,
+    /// Abandon reviews with no activity for this many days (0 = never)
+    #[serde(default)]
+    pub abandon_after_days: u64,
+    /// Days before abandoning to warn on the review
+    #[serde(default = "default_abandon_warning_days")]
+    pub abandon_warning_days: u64,
+    /// Run `/garry` commands posted in review comments
+    #[serde(default = "default_chat_commands")]
+    pub chat_commands: bool,
+}
+
+fn default_abandon_warning_days() -> u64 {
+    7
+}
+
+fn default_chat_commands() -> bool {
+    true
 }
 
 /// Git configuration
@@ -117,10 +304,320 @@ pub struct BotConfig {
 pub struct GitConfig {
     /// Default remote name (usually "origin")
     pub default_remote: String,
-    /// Base branch for squashing (usually "main" or "origin/main")
+    /// Base for squashing and comparing changes into `bot.main_branch`
+    /// (usually "main" or "origin/main"); changes into other branches use
+    /// `<default_remote>/<branch>`
     pub squash_base: String,
 }
 
+/// Presubmit checks run by `garry upload` before pushing
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct PresubmitConfig {
+    /// Commands to run (e.g. formatters, linters, tests)
+    pub commands: Vec<PresubmitCommand>,
+    /// Require the branch to be squashed into a single commit
+    pub require_single_commit: bool,
+    /// Maximum length of the commit subject line (0 = no limit)
+    pub max_subject_length: usize,
+    /// Allowed commit subject prefixes (e.g. "fix:"); empty allows any
+    pub subject_prefixes: Vec<String>,
+    /// Maximum number of changed lines (0 = no limit)
+    pub max_diff_lines: usize,
+    /// Path patterns that must not be changed (e.g. "*.pem", ".env")
+    pub forbidden_files: Vec<String>,
+}
+
+/// A command run as a presubmit check
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct PresubmitCommand {
+    /// Name shown in check results
+    pub name: String,
+    /// Shell command to run from the repository root
+    pub run: String,
+}
+
+/// Approval policy the merge queue enforces
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct PolicyConfig {
+    /// Number of valid approvals required
+    pub required_approvals: usize,
+    /// Ignore approvals given before the latest push
+    pub dismiss_stale_approvals: bool,
+    /// Count the author's own approval
+    pub allow_self_approval: bool,
+    /// Accounts whose approvals never count (logins ending in "[bot]" are always ignored)
+    pub bot_users: Vec<String>,
+    /// Require an owner's approval for every path covered by CODEOWNERS/OWNERS files
+    pub require_code_owner_approval: bool,
+    /// Labels reviewers can vote on, with their value ranges
+    pub labels: Vec<LabelDefinition>,
+    /// Label that platform approvals and change requests vote on
+    pub review_label: String,
+    /// Label requirements, e.g. "Code-Review needs max with no min, Verified +1"
+    /// (empty = no label requirements)
+    pub label_rules: String,
+    /// Keep reviews out of the queue while any inline comment thread is unresolved
+    pub block_on_unresolved_threads: bool,
+}
+
+impl Default for PolicyConfig {
+    fn default() -> Self {
+        Self {
+            required_approvals: 1,
+            dismiss_stale_approvals: true,
+            allow_self_approval: false,
+            bot_users: vec!["garry-bot".to_string()],
+            require_code_owner_approval: true,
+            labels: vec![
+                LabelDefinition::new("Code-Review", -2, 2),
+                LabelDefinition::new("Verified", -1, 1),
+            ],
+            review_label: "Code-Review".to_string(),
+            label_rules: String::new(),
+            block_on_unresolved_threads: false,
+        }
+    }
+}
+
+/// Reviewer suggestion settings
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ReviewersConfig {
+    /// Request suggested reviewers on upload when none are given
+    pub auto_suggest: bool,
+    /// How many reviewers to suggest
+    pub count: usize,
+    /// Accounts never suggested
+    pub exclude: Vec<String>,
+    /// Maps commit emails (and email owners) to platform usernames, so
+    /// blame history can suggest reviewers
+    pub aliases: HashMap<String, String>,
+}
+
+impl Default for ReviewersConfig {
+    fn default() -> Self {
+        Self {
+            auto_suggest: false,
+            count: 2,
+            exclude: vec![],
+            aliases: HashMap::new(),
+        }
+    }
+}
+
+/// Who holds the maintainer and bot roles
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct RolesConfig {
+    /// Maintainers, as usernames or teams ("org/team")
+    pub maintainers: Vec<String>,
+    /// Bot accounts, which may control the queue but not approve
+    pub bots: Vec<String>,
+    /// Also treat users with `maintainer_permission` on the forge as maintainers
+    pub sync_forge_permissions: bool,
+    /// Lowest forge permission that makes someone a maintainer
+    pub maintainer_permission: RepoPermission,
+}
+
+impl Default for RolesConfig {
+    fn default() -> Self {
+        Self {
+            maintainers: vec![],
+            bots: vec!["garry-bot".to_string()],
+            sync_forge_permissions: true,
+            maintainer_permission: RepoPermission::Write,
+        }
+    }
+}
+
+/// Where the bot sends notifications besides review comments
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct NotificationsConfig {
+    /// Post notifications as comments on the review
+    pub review_comments: bool,
+    /// Chat, webhook and email channels
+    pub channels: Vec<ChannelConfig>,
+    /// TOML file of message templates by locale (default: built-in English)
+    pub templates: Option<String>,
+    /// Locale to pick templates for, e.g. "de" or "pt-BR"
+    pub locale: String,
+}
+
+impl Default for NotificationsConfig {
+    fn default() -> Self {
+        Self {
+            review_comments: true,
+            channels: vec![],
+            templates: None,
+            locale: DEFAULT_LOCALE.to_string(),
+        }
+    }
+}
+
+/// A notification channel and the notifications routed to it
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ChannelConfig {
+    #[serde(flatten)]
+    pub kind: ChannelKind,
+    /// Events to send, e.g. "merge_success" (empty = all)
+    #[serde(default)]
+    pub events: Vec<String>,
+    /// Repositories to send events for, as "owner/repo" (empty = all)
+    #[serde(default)]
+    pub repositories: Vec<String>,
+    /// Digest reports to send, by name (empty = all)
+    #[serde(default)]
+    pub digests: Vec<String>,
+    /// Message format (default: Slack blocks for Slack, plain text for email,
+    /// Markdown otherwise)
+    #[serde(default)]
+    pub format: Option<MessageFormat>,
+}
+
+/// Channel type and its connection settings
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum ChannelKind {
+    /// Slack-compatible incoming webhook (Slack, Mattermost, Rocket.Chat)
+    Slack {
+        webhook_url: String,
+    },
+    /// Matrix room, posted to through the client-server API
+    Matrix {
+        /// Homeserver URL, e.g. "https://matrix.org"
+        homeserver: String,
+        /// Room ID, e.g. "!abc123:matrix.org"
+        room_id: String,
+        access_token: String,
+    },
+    /// Generic JSON webhook
+    Webhook {
+        url: String,
+        /// Extra headers sent with every request
+        #[serde(default)]
+        headers: HashMap<String, String>,
+    },
+    /// Email sent through an SMTP server
+    Email {
+        smtp_host: String,
+        #[serde(default = "default_smtp_port")]
+        smtp_port: u16,
+        #[serde(default)]
+        security: SmtpSecurity,
+        #[serde(default)]
+        username: Option<String>,
+        #[serde(default)]
+        password: Option<String>,
+        from: String,
+        to: Vec<String>,
+    },
+}
+
+/// How the connection to an SMTP server is secured
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SmtpSecurity {
+    /// Upgrade a plain connection with STARTTLS
+    #[default]
+    Starttls,
+    /// Connect over TLS
+    Tls,
+    /// No encryption, for local relays only
+    None,
+}
+
+fn default_smtp_port() -> u16 {
+    587
+}
+
+/// Scheduled digests of reviews that need attention
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct DigestConfig {
+    /// How often the bot sends digests
+    pub schedule: DigestSchedule,
+    /// Hour of the day (UTC) digests go out
+    pub hour: u32,
+    /// Day of the week weekly digests go out, e.g. "mon"
+    pub weekday: Weekday,
+    /// Hours in the merge queue after which a review counts as stuck
+    pub stuck_after_hours: u64,
+    /// Most reviews listed per section
+    pub limit: usize,
+    /// Reports to send, each to the channels routed to it (default: one for everyone)
+    pub reports: Vec<DigestReportConfig>,
+}
+
+impl Default for DigestConfig {
+    fn default() -> Self {
+        Self {
+            schedule: DigestSchedule::Off,
+            hour: 9,
+            weekday: Weekday::Mon,
+            stuck_after_hours: 4,
+            limit: 10,
+            reports: vec![],
+        }
+    }
+}
+
+/// How often digests are sent
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DigestSchedule {
+    #[default]
+    Off,
+    Daily,
+    Weekly,
+}
+
+/// A digest for a team or person
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct DigestReportConfig {
+    /// Name channels route the report by, e.g. "Core team"
+    pub name: String,
+    /// Users and teams ("org/team") the report covers (empty = everyone)
+    #[serde(default)]
+    pub members: Vec<String>,
+}
+
+/// The bot's HTTP server: health checks and the admin API
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ApiConfig {
+    /// Run the HTTP server
+    pub enabled: bool,
+    /// Address to listen on
+    pub bind: String,
+    /// Port to listen on (default: the bot's `webhook_port`)
+    pub port: Option<u16>,
+    /// Bearer token for the admin API; without one only health checks are served
+    pub token: Option<String>,
+    /// Serve the read-only HTML dashboard on `/`
+    pub dashboard: bool,
+    /// How often dashboard pages reload themselves, in seconds
+    pub dashboard_refresh_seconds: u64,
+    /// Where the CLI reaches the bot's HTTP server, for the real queue positions
+    pub url: Option<String>,
+}
+
+impl Default for ApiConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            bind: "0.0.0.0".to_string(),
+            port: None,
+            token: None,
+            dashboard: true,
+            dashboard_refresh_seconds: 15,
+            url: None,
+        }
+    }
+}
+
 impl Default for Config {
     fn default() -> Self {
         Self {
@@ -135,11 +632,24 @@ impl Default for Config {
                 queue_check_interval: 30,
                 ci_timeout: 3600,
                 main_branch: "main".to_string(),
+                abandon_after_days: 0,
+                abandon_warning_days: default_abandon_warning_days(),
+                chat_commands: default_chat_commands(),
             },
             git: GitConfig {
                 default_remote: "origin".to_string(),
                 squash_base: "main".to_string(),
             },
+            presubmit: PresubmitConfig::default(),
+            policy: PolicyConfig::default(),
+            reviewers: ReviewersConfig::default(),
+            roles: RolesConfig::default(),
+            notifications: NotificationsConfig::default(),
+            digest: DigestConfig::default(),
+            api: ApiConfig::default(),
+            repositories: vec![],
+            targets: vec![],
+            backport: BackportConfig::default(),
         }
     }
 }
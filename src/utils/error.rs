@@ -21,6 +21,9 @@ pub enum GarryError {
     #[error("CI check failed: {0:?}")]
     CiFailed(Vec<String>),
     
+    #[error("Presubmit checks failed: {0:?}")]
+    PresubmitFailed(Vec<String>),
+    
     #[error("Invalid branch name: {0}")]
     InvalidBranchName(String),
     
@@ -33,6 +36,15 @@ pub enum GarryError {
     #[error("Serialization error: {0}")]
     SerializationError(#[from] serde_json::Error),
     
+    #[error("Notification failed: {0}")]
+    NotificationError(String),
+    
+    #[error("Permission denied: {0}")]
+    PermissionDenied(String),
+    
+    #[error("Invalid label vote: {0}")]
+    InvalidVote(String),
+    
     #[error("{0}")]
     Other(String),
 }
@@ -10,7 +10,7 @@ async fn test_mock_adapter_workflow() {
     
     // Create a review
     let (review_id, review_url) = adapter
-        .create_review("feature/test", "Test PR", "Test description")
+        .create_review("feature/test", "main", "Test PR", "Test description")
         .await
         .expect("Failed to create review");
     
@@ -52,12 +52,12 @@ async fn test_list_pending_reviews() {
     
     // Create multiple reviews
     let (_review1, _) = adapter
-        .create_review("feature/test1", "Test PR 1", "Description 1")
+        .create_review("feature/test1", "main", "Test PR 1", "Description 1")
         .await
         .expect("Failed to create review 1");
     
     let (_review2, _) = adapter
-        .create_review("feature/test2", "Test PR 2", "Description 2")
+        .create_review("feature/test2", "main", "Test PR 2", "Description 2")
         .await
         .expect("Failed to create review 2");
     
@@ -78,6 +78,11 @@ fn test_config_validation() {
     
     // Default config should fail validation (empty token and repo)
     assert!(config.validate().is_err());
+    
+    // Changes are compared against the branch their review merges into
+    assert_eq!(config.base_for(""), "main");
+    assert_eq!(config.base_for("main"), "main");
+    assert_eq!(config.base_for("release/2.3"), "origin/release/2.3");
 }
 
 #[test]
@@ -132,4 +137,1893 @@ fn test_error_types() {
     assert!(git_error.to_string().contains("Git operation failed"));
     assert!(vcs_error.to_string().contains("VCS adapter error"));
     assert!(config_error.to_string().contains("Configuration error"));
+    assert!(GarryError::InvalidVote("test".to_string()).to_string().contains("Invalid label vote"));
+}
+
+/// Commit a single file on top of HEAD (or as the root commit) and return its id
+fn commit_file(repo: &git2::Repository, path: &str, content: &str, message: &str) -> git2::Oid {
+    let workdir = repo.workdir().unwrap().to_path_buf();
+    std::fs::write(workdir.join(path), content).unwrap();
+    
+    let mut index = repo.index().unwrap();
+    index.add_path(std::path::Path::new(path)).unwrap();
+    index.write().unwrap();
+    let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+    
+    let signature = git2::Signature::now("Test", "test@example.com").unwrap();
+    let parents = match repo.head().ok().and_then(|h| h.peel_to_commit().ok()) {
+        Some(parent) => vec![parent],
+        None => vec![],
+    };
+    let parent_refs: Vec<&git2::Commit> = parents.iter().collect();
+    
+    repo.commit(Some("HEAD"), &signature, &signature, message, &tree, &parent_refs).unwrap()
+}
+
+/// Check out a branch (creating it at `at` if given)
+fn checkout(repo: &git2::Repository, branch: &str, at: Option<git2::Oid>) {
+    if let Some(oid) = at {
+        repo.branch(branch, &repo.find_commit(oid).unwrap(), true).unwrap();
+    }
+    repo.set_head(&format!("refs/heads/{}", branch)).unwrap();
+    repo.checkout_head(Some(git2::build::CheckoutBuilder::default().force())).unwrap();
+}
+
+/// Initialise a repository whose first commit lives on `main`
+fn init_repo() -> (tempfile::TempDir, git2::Repository, git2::Oid) {
+    let dir = tempfile::tempdir().unwrap();
+    let repo = git2::Repository::init(dir.path()).unwrap();
+    repo.set_head("refs/heads/main").unwrap();
+    let root = commit_file(&repo, "README.md", "hello\n", "Initial commit");
+    (dir, repo, root)
+}
+
+#[test]
+fn test_squash_on_diverged_main_keeps_base_changes() {
+    use garry::git::commit::CommitOps;
+    
+    let (_dir, repo, root) = init_repo();
+    
+    // Feature branch with two commits
+    checkout(&repo, "feature", Some(root));
+    commit_file(&repo, "feature.txt", "one\n", "Feature part 1");
+    commit_file(&repo, "feature.txt", "two\n", "Feature part 2");
+    
+    // Main moves on after branching
+    checkout(&repo, "main", None);
+    commit_file(&repo, "main.txt", "newer\n", "Main moved");
+    checkout(&repo, "feature", None);
+    
+    assert_eq!(CommitOps::get_commit_count(&repo, "main").unwrap(), 2);
+    CommitOps::squash_commits(&repo, "main").unwrap();
+    
+    let head = repo.head().unwrap().peel_to_commit().unwrap();
+    assert_eq!(head.parent_count(), 1);
+    assert_eq!(head.parent_id(0).unwrap(), root);
+    
+    // The squashed diff only touches the feature's file
+    let diff = repo
+        .diff_tree_to_tree(Some(&head.parent(0).unwrap().tree().unwrap()), Some(&head.tree().unwrap()), None)
+        .unwrap();
+    let paths: Vec<_> = diff
+        .deltas()
+        .map(|d| d.new_file().path().unwrap().to_string_lossy().into_owned())
+        .collect();
+    assert_eq!(paths, vec!["feature.txt".to_string()]);
+}
+
+#[test]
+fn test_squash_prefers_remote_tracking_base() {
+    use garry::git::commit::CommitOps;
+    
+    let (_dir, repo, root) = init_repo();
+    
+    // origin/main is ahead of the stale local main
+    let upstream = commit_file(&repo, "main.txt", "upstream\n", "Upstream change");
+    repo.reference("refs/remotes/origin/main", upstream, true, "test").unwrap();
+    repo.reference("refs/heads/main", root, true, "test").unwrap();
+    
+    // Feature branched from origin/main
+    checkout(&repo, "feature", Some(upstream));
+    commit_file(&repo, "feature.txt", "one\n", "Feature part 1");
+    commit_file(&repo, "feature.txt", "two\n", "Feature part 2");
+    
+    assert_eq!(CommitOps::get_commit_count(&repo, "main").unwrap(), 2);
+    CommitOps::squash_commits(&repo, "main").unwrap();
+    
+    let head = repo.head().unwrap().peel_to_commit().unwrap();
+    assert_eq!(head.parent_id(0).unwrap(), upstream);
+    assert!(head.message().unwrap().contains("Squashed 2 commits"));
+}
+
+#[test]
+fn test_ci_status_from_checks() {
+    use garry::review::{CiCheck, CiStatus};
+    
+    let check = |name: &str, status: CiStatus| CiCheck { name: name.to_string(), status };
+    
+    assert_eq!(CiStatus::from_checks(&[]), CiStatus::Pending);
+    assert_eq!(
+        CiStatus::from_checks(&[check("build", CiStatus::Success), check("test", CiStatus::Running)]),
+        CiStatus::Running
+    );
+    assert_eq!(
+        CiStatus::from_checks(&[check("build", CiStatus::Failed(vec![])), check("test", CiStatus::Running)]),
+        CiStatus::Failed(vec!["build".to_string()])
+    );
+    assert_eq!(CiStatus::from_checks(&[check("build", CiStatus::Success)]), CiStatus::Success);
+}
+
+#[test]
+fn test_branch_sync_state() {
+    use garry::git::branch::{BranchOps, SyncState};
+    
+    let (_dir, repo, root) = init_repo();
+    let ahead = commit_file(&repo, "a.txt", "a\n", "Local change");
+    checkout(&repo, "other", Some(root));
+    let other = commit_file(&repo, "b.txt", "b\n", "Other change");
+    
+    assert_eq!(BranchOps::sync_state(&repo, root, root).unwrap(), SyncState::UpToDate);
+    assert_eq!(BranchOps::sync_state(&repo, ahead, root).unwrap(), SyncState::Ahead(1));
+    assert_eq!(BranchOps::sync_state(&repo, root, ahead).unwrap(), SyncState::Behind(1));
+    assert_eq!(
+        BranchOps::sync_state(&repo, ahead, other).unwrap(),
+        SyncState::Diverged { ahead: 1, behind: 1 }
+    );
+}
+
+#[tokio::test]
+async fn test_find_review_for_branch() {
+    let adapter = MockVcsAdapter::new();
+    
+    let (review_id, review_url) = adapter
+        .create_review("feature/status", "main", "Status", "Description")
+        .await
+        .expect("Failed to create review");
+    
+    let found = adapter.find_review_for_branch("feature/status").await.unwrap();
+    assert_eq!(found, Some((review_id, review_url)));
+    assert_eq!(adapter.find_review_for_branch("feature/missing").await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn test_list_open_reviews_summaries() {
+    use garry::bot::adapters::mock::MOCK_USER;
+    
+    let adapter = MockVcsAdapter::new();
+    
+    let (review1, _) = adapter
+        .create_review("feature/one", "main", "First change", "Description")
+        .await
+        .expect("Failed to create review 1");
+    let (review2, _) = adapter
+        .create_review("feature/two", "main", "Second change", "Description")
+        .await
+        .expect("Failed to create review 2");
+    adapter.merge_review(&review1).await.expect("Failed to merge review");
+    
+    let open = adapter.list_open_reviews().await.expect("Failed to list reviews");
+    assert_eq!(open.len(), 1);
+    assert_eq!(open[0].id, review2);
+    assert_eq!(open[0].title, "Second change");
+    assert_eq!(open[0].branch, "feature/two");
+    assert_eq!(open[0].author, MOCK_USER);
+}
+
+#[tokio::test]
+async fn test_is_queueable_requires_approval_and_ci() {
+    use garry::bot::queue::QueueManager;
+    use garry::review::policy::ApprovalPolicy;
+    
+    let policy = ApprovalPolicy::default();
+    let adapter = MockVcsAdapter::new();
+    let (review_id, _) = adapter
+        .create_review("feature/queue", "main", "Queue me", "Description")
+        .await
+        .expect("Failed to create review");
+    
+    adapter.approve_review(&review_id, None).await.unwrap();
+    let status = adapter.get_review_status(&review_id).await.unwrap();
+    assert!(!QueueManager::is_queueable(&policy, &status));
+    
+    adapter.set_ci_status(&review_id, CiStatus::Success);
+    let status = adapter.get_review_status(&review_id).await.unwrap();
+    assert!(QueueManager::is_queueable(&policy, &status));
+}
+
+#[tokio::test]
+async fn test_drafts_are_never_queued() {
+    use garry::bot::queue::QueueManager;
+    
+    let adapter = MockVcsAdapter::new();
+    let (review_id, _) = adapter
+        .create_draft_review("feature/wip", "main", "WIP", "Description")
+        .await
+        .expect("Failed to create draft review");
+    adapter.approve_review(&review_id, None).await.unwrap();
+    adapter.set_ci_status(&review_id, CiStatus::Success);
+    
+    let status = adapter.get_review_status(&review_id).await.unwrap();
+    assert_eq!(status.state, ReviewState::Draft);
+    
+    let mut queue = QueueManager::new(Box::new(adapter));
+    queue.discover_and_queue_reviews().await.unwrap();
+    assert!(queue.get_queue().is_empty());
+    assert!(queue.add_to_queue(review_id.clone()).await.is_err());
+    
+    // Once it's ready, the existing approval counts
+    queue.adapter().mark_ready_for_review(&review_id).await.unwrap();
+    assert!(queue.adapter().mark_ready_for_review(&review_id).await.is_err());
+    queue.discover_and_queue_reviews().await.unwrap();
+    assert_eq!(queue.get_queue_position(&review_id), Some(0));
+}
+
+#[tokio::test]
+async fn test_close_and_reopen_review() {
+    use garry::review::ReviewId;
+    
+    let adapter = MockVcsAdapter::new();
+    let (review_id, _) = adapter
+        .create_review("feature/old", "main", "Old", "Description")
+        .await
+        .expect("Failed to create review");
+    
+    adapter.close_review(&review_id).await.unwrap();
+    assert_eq!(adapter.get_review_status(&review_id).await.unwrap().state, ReviewState::Closed);
+    // Closed reviews drop out of the queue's discovery walk
+    assert!(adapter.list_pending_reviews().await.unwrap().is_empty());
+    assert!(adapter.close_review(&ReviewId::new("404".to_string())).await.is_err());
+    
+    adapter.reopen_review(&review_id).await.unwrap();
+    assert_eq!(adapter.list_pending_reviews().await.unwrap(), vec![review_id.clone()]);
+    assert!(adapter.reopen_review(&review_id).await.is_err());
+}
+
+#[tokio::test]
+async fn test_stale_reviews_are_warned_then_abandoned() {
+    use garry::bot::stale::StaleReviewSweeper;
+    use garry::utils::config::Config;
+    use chrono::{Duration, Utc};
+    
+    let mut config = Config::default().bot;
+    config.abandon_after_days = 30;
+    config.abandon_warning_days = 7;
+    assert!(StaleReviewSweeper::new(&Config::default().bot).is_none());
+    let mut sweeper = StaleReviewSweeper::new(&config).unwrap();
+    
+    let adapter = MockVcsAdapter::new();
+    let now = Utc::now();
+    let mut ids = Vec::new();
+    for branch in ["feature/stale", "feature/revived", "feature/queued", "feature/fresh"] {
+        let (id, _) = adapter.create_review(branch, "main", branch, "Description").await.unwrap();
+        adapter.set_updated_at(&id, now - Duration::days(25));
+        ids.push(id);
+    }
+    adapter.set_updated_at(&ids[3], now - Duration::days(1));
+    let queued = vec![ids[2].clone()];
+    
+    let result = sweeper.sweep(&adapter, &queued, now).await.unwrap();
+    assert_eq!(result.warned, vec![ids[0].clone(), ids[1].clone()]);
+    assert!(result.abandoned.is_empty());
+    assert!(adapter.comments(&ids[0])[0].contains("will be abandoned in 7 days"));
+    
+    // Activity after the warning keeps a review open
+    adapter.set_updated_at(&ids[1], now + Duration::days(2));
+    let result = sweeper.sweep(&adapter, &queued, now + Duration::days(7)).await.unwrap();
+    assert_eq!(result.abandoned, vec![ids[0].clone()]);
+    assert_eq!(adapter.get_review_status(&ids[0]).await.unwrap().state, ReviewState::Closed);
+    assert_eq!(adapter.get_review_status(&ids[1]).await.unwrap().state, ReviewState::Open);
+    assert!(adapter.comments(&ids[2]).is_empty());
+}
+
+#[test]
+fn test_fetch_and_checkout_review_branch() {
+    use garry::git::GitRepository;
+    use garry::git::patchset::PatchsetOps;
+    
+    // "Remote" repository with a review branch and a stored patchset
+    let (origin_dir, origin, root) = init_repo();
+    checkout(&origin, "feature", Some(root));
+    let first = commit_file(&origin, "feature.txt", "one\n", "Feature v1");
+    origin.reference(&PatchsetOps::ref_name("7", 1), first, true, "test").unwrap();
+    let second = commit_file(&origin, "feature.txt", "two\n", "Feature v2");
+    
+    // Local clone that only knows the root commit
+    let (local_dir, local, _) = init_repo();
+    local.remote("origin", origin_dir.path().to_str().unwrap()).unwrap();
+    let repo = GitRepository::open(local_dir.path()).unwrap();
+    
+    repo.fetch("origin", &["+refs/heads/feature:refs/remotes/origin/feature"]).unwrap();
+    let oid = repo.resolve_ref("refs/remotes/origin/feature").unwrap();
+    assert_eq!(oid, second);
+    
+    repo.checkout_branch_at("feature", oid, Some("origin/feature")).unwrap();
+    assert_eq!(repo.get_current_branch().unwrap(), "feature");
+    let branch = local.find_branch("feature", git2::BranchType::Local).unwrap();
+    assert_eq!(branch.upstream().unwrap().name().unwrap(), Some("origin/feature"));
+    
+    // Earlier revisions come back detached
+    let patchset = repo.fetch_patchset("origin", "7", 1).unwrap();
+    assert_eq!(patchset, first);
+    repo.checkout_detached(patchset).unwrap();
+    assert!(local.head_detached().unwrap());
+    
+    // A local branch that isn't behind the review is never rewound
+    assert!(repo.checkout_branch_at("feature", first, None).is_err());
+    
+    // The checked-out branch is fast-forwarded in place, or left alone when it's already there
+    repo.checkout_branch_at("feature", second, None).unwrap();
+    repo.checkout_branch_at("feature", second, None).unwrap();
+    let third = commit_file(&origin, "feature.txt", "three\n", "Feature v3");
+    repo.fetch("origin", &["+refs/heads/feature:refs/remotes/origin/feature"]).unwrap();
+    repo.checkout_branch_at("feature", third, None).unwrap();
+    assert_eq!(repo.resolve_ref("refs/heads/feature").unwrap(), third);
+    assert_eq!(std::fs::read_to_string(local_dir.path().join("feature.txt")).unwrap(), "three\n");
+}
+
+#[test]
+fn test_patchset_history_and_interdiff() {
+    use garry::git::GitRepository;
+    use garry::git::patchset::FileDelta;
+    
+    // Pushing needs a bare remote
+    let origin_dir = tempfile::tempdir().unwrap();
+    git2::Repository::init_bare(origin_dir.path()).unwrap();
+    let origin_path = origin_dir.path().to_str().unwrap();
+    let (dir, local, root) = init_repo();
+    local.remote("origin", origin_path).unwrap();
+    let repo = GitRepository::open(dir.path()).unwrap();
+    
+    checkout(&local, "feature", Some(root));
+    commit_file(&local, "feature.txt", "one\n", "Feature");
+    assert_eq!(repo.record_patchset("origin", "7").unwrap(), 1);
+    // Recording the same revision again doesn't add a patchset
+    assert_eq!(repo.record_patchset("origin", "7").unwrap(), 1);
+    
+    // Main moves on, and the second patchset is rebased with one more line
+    checkout(&local, "main", None);
+    let upstream = commit_file(&local, "main.txt", "newer\n", "Main moved");
+    checkout(&local, "feature", Some(upstream));
+    commit_file(&local, "feature.txt", "one\ntwo\n", "Feature");
+    assert_eq!(repo.record_patchset("origin", "7").unwrap(), 2);
+    
+    // The rebase's main.txt change isn't part of the interdiff
+    let interdiff = repo.interdiff("7", "main", 1, 2).unwrap();
+    assert!(interdiff.rebased);
+    assert!(!interdiff.conflicted);
+    assert_eq!(interdiff.files, vec![FileDelta { path: "feature.txt".to_string(), insertions: 1, deletions: 0 }]);
+    assert!(interdiff.patch.contains("+two"));
+    assert!(interdiff.summary().contains("- `feature.txt` (+1 -0)"));
+    
+    // Patchsets are pushed, so another clone sees the whole history
+    let (clone_dir, clone, _) = init_repo();
+    clone.remote("origin", origin_path).unwrap();
+    let clone = GitRepository::open(clone_dir.path()).unwrap();
+    let patchsets = clone.fetch_patchsets("origin", "7").unwrap();
+    assert_eq!(patchsets.iter().map(|(n, _)| *n).collect::<Vec<_>>(), vec![1, 2]);
+}
+
+#[tokio::test]
+async fn test_presubmit_checks() {
+    use garry::git::GitRepository;
+    use garry::presubmit::Presubmit;
+    use garry::utils::config::{PresubmitCommand, PresubmitConfig};
+    
+    let (dir, repo, root) = init_repo();
+    checkout(&repo, "feature", Some(root));
+    std::fs::create_dir(dir.path().join("certs")).unwrap();
+    commit_file(&repo, "certs/key.pem", "secret\n", "fix: add key");
+    commit_file(&repo, "feature.txt", "one\n", "A commit subject that is far too long to be accepted");
+    let repo = GitRepository::open(dir.path()).unwrap();
+    
+    let config = PresubmitConfig {
+        commands: vec![
+            PresubmitCommand { name: "ok".to_string(), run: "true".to_string() },
+            PresubmitCommand { name: "broken".to_string(), run: "echo boom; false".to_string() },
+        ],
+        require_single_commit: true,
+        max_subject_length: 20,
+        subject_prefixes: vec!["fix:".to_string()],
+        max_diff_lines: 1,
+        forbidden_files: vec!["*.pem".to_string()],
+    };
+    
+    let results = Presubmit::new(&config, &repo, "main").run().await.unwrap();
+    let outcome: Vec<(&str, bool)> = results.iter().map(|r| (r.name.as_str(), r.passed)).collect();
+    assert_eq!(outcome, vec![
+        ("single-commit", false),
+        ("commit-message", false),
+        ("diff-size", false),
+        ("forbidden-files", false),
+        ("ok", true),
+        ("broken", false),
+    ]);
+    assert!(results[3].details.contains("certs/key.pem"));
+    assert!(results[5].details.contains("boom"));
+    
+    // Nothing is checked unless configured
+    let results = Presubmit::new(&PresubmitConfig::default(), &repo, "main").run().await.unwrap();
+    assert!(results.is_empty());
+}
+
+#[tokio::test]
+async fn test_approval_policy() {
+    use garry::bot::adapters::mock::{MOCK_REVIEWER, MOCK_USER};
+    use garry::bot::queue::{QueueManager, QueueStatus};
+    use garry::review::ReviewDecision;
+    use garry::review::policy::ApprovalPolicy;
+    use garry::utils::config::PolicyConfig;
+    
+    let adapter = MockVcsAdapter::new();
+    let (review_id, _) = adapter
+        .create_review("feature/policy", "main", "Policy", "Description")
+        .await
+        .expect("Failed to create review");
+    let policy = ApprovalPolicy::new(PolicyConfig { required_approvals: 2, ..PolicyConfig::default() });
+    
+    // A reviewer who requested changes and later approved only counts once, as an approval
+    adapter.add_vote(&review_id, "alice", ReviewDecision::ChangesRequested);
+    adapter.add_vote(&review_id, "alice", ReviewDecision::Approved);
+    adapter.add_vote(&review_id, "alice", ReviewDecision::Commented);
+    // Author and bot approvals never count
+    adapter.add_vote(&review_id, MOCK_USER, ReviewDecision::Approved);
+    adapter.add_vote(&review_id, "ci[bot]", ReviewDecision::Approved);
+    
+    let status = adapter.get_review_status(&review_id).await.unwrap();
+    assert_eq!(status.state, ReviewState::Approved);
+    let decision = policy.evaluate(&status);
+    assert_eq!(decision.state, ReviewState::Open);
+    assert_eq!(decision.approvals.len(), 1);
+    
+    adapter.add_vote(&review_id, MOCK_REVIEWER, ReviewDecision::Approved);
+    let status = adapter.get_review_status(&review_id).await.unwrap();
+    assert!(policy.evaluate(&status).is_approved());
+    
+    // Pushing new commits dismisses the earlier approvals
+    adapter.push(&review_id);
+    let status = adapter.get_review_status(&review_id).await.unwrap();
+    let decision = policy.evaluate(&status);
+    assert!(!decision.is_approved());
+    assert!(decision.problems.iter().any(|p| p.contains("predates the latest push")));
+    
+    // The queue checks again right before merging, in case approvals went away while CI ran
+    adapter.add_vote(&review_id, "alice", ReviewDecision::Approved);
+    adapter.add_vote(&review_id, MOCK_REVIEWER, ReviewDecision::Approved);
+    adapter.set_ci_status(&review_id, CiStatus::Success);
+    let mut queue = QueueManager::new(Box::new(adapter.clone())).with_policy(policy);
+    queue.add_to_queue(review_id.clone()).await.unwrap();
+    queue.process_queue().await.unwrap();
+    assert_eq!(queue.get_queue()[0].status, QueueStatus::Merging);
+    adapter.push(&review_id);
+    queue.process_queue().await.unwrap();
+    assert!(matches!(&queue.get_queue()[0].status, QueueStatus::Failed(reason) if reason.contains("predates the latest push")));
+    assert_ne!(adapter.get_review_status(&review_id).await.unwrap().state, ReviewState::Merged);
+    assert!(queue.recent_outcomes()[0].failure.is_some());
+}
+
+#[test]
+fn test_codeowners_patterns_and_sections() {
+    use garry::review::owners::{pattern_matches, CodeOwners};
+    
+    assert!(pattern_matches("*.js", "web/app.js"));
+    assert!(pattern_matches("/payments/", "payments/api/charge.rs"));
+    assert!(pattern_matches("docs/*", "docs/intro.md"));
+    assert!(!pattern_matches("docs/*", "docs/guides/intro.md"));
+    assert!(pattern_matches("apps/**/test", "apps/web/unit/test"));
+    assert!(!pattern_matches("/build/", "src/build/main.rs"));
+    
+    let owners = CodeOwners::parse_codeowners(
+        "# Default owners\n\
+         *                @org/core\n\
+         /payments/       @org/payments alice@example.com\n\
+         /payments/README.md\n\
+         \n\
+         [Docs] @org/docs\n\
+         *.md\n\
+         ^[Optional]\n\
+         *.md @bob\n",
+    );
+    
+    let files = vec!["payments/api.rs".to_string(), "payments/README.md".to_string(), "src/lib.rs".to_string()];
+    let groups = owners.required_groups(&files);
+    let summary: Vec<(Option<&str>, &str, usize)> = groups
+        .iter()
+        .map(|g| (g.section.as_deref(), g.pattern.as_str(), g.paths.len()))
+        .collect();
+    // The unowned README rule wins for its file; the optional section never requires approval
+    assert_eq!(summary, vec![
+        (None, "/payments/", 1),
+        (Some("Docs"), "*.md", 1),
+        (None, "*", 1),
+    ]);
+    assert_eq!(owners.teams(), vec!["org/core", "org/docs", "org/payments"]);
+}
+
+#[test]
+fn test_gerrit_owners_inheritance() {
+    use garry::review::owners::CodeOwners;
+    use std::collections::HashMap;
+    
+    let owners = CodeOwners::parse_gerrit(&[
+        ("".to_string(), "root@example.com\n".to_string()),
+        ("payments".to_string(), "pay@example.com\nper-file *.sql=dba@example.com\n".to_string()),
+        ("payments/secure".to_string(), "set noparent\nsec@example.com\n".to_string()),
+    ]);
+    let none = HashMap::new();
+    let missing = |path: &str, approver: &str| {
+        !owners.missing_groups(&[path.to_string()], &[approver.to_string()], &none).is_empty()
+    };
+    
+    // Parent owners still own nested directories...
+    assert!(!missing("payments/api.rs", "root@example.com"));
+    assert!(!missing("payments/schema.sql", "dba@example.com"));
+    assert!(missing("payments/api.rs", "dba@example.com"));
+    // ...unless the nested OWNERS file opts out
+    assert!(missing("payments/secure/keys.rs", "root@example.com"));
+    assert!(!missing("payments/secure/keys.rs", "sec@example.com"));
+}
+
+#[tokio::test]
+async fn test_policy_requires_code_owner_approval() {
+    use garry::bot::adapters::mock::MOCK_REVIEWER;
+    use garry::review::ReviewDecision;
+    use garry::review::owners::{self, CodeOwners};
+    use garry::review::policy::ApprovalPolicy;
+    
+    let adapter = MockVcsAdapter::new();
+    adapter.set_file(".github/CODEOWNERS", "/payments/ @org/payments\n");
+    adapter.set_team_members("org/payments", vec!["carol".to_string()]);
+    adapter.set_file("docs/OWNERS", "dana\n");
+    
+    let code_owners = CodeOwners::from_adapter(&adapter, "main").await.unwrap();
+    let team_members = owners::resolve_team_members(&adapter, &code_owners).await;
+    let policy = ApprovalPolicy::default().with_owners(code_owners, team_members);
+    
+    let (review_id, _) = adapter
+        .create_review("feature/pay", "main", "Payments", "Description")
+        .await
+        .expect("Failed to create review");
+    adapter.set_changed_files(&review_id, vec!["payments/charge.rs".to_string(), "README.md".to_string()]);
+    adapter.add_vote(&review_id, MOCK_REVIEWER, ReviewDecision::Approved);
+    
+    let decision = policy.evaluate(&adapter.get_review_status(&review_id).await.unwrap());
+    assert!(!decision.is_approved());
+    assert_eq!(decision.missing_owners.len(), 1);
+    assert_eq!(decision.missing_owners[0].paths, vec!["payments/charge.rs".to_string()]);
+    
+    adapter.add_vote(&review_id, "carol", ReviewDecision::Approved);
+    let decision = policy.evaluate(&adapter.get_review_status(&review_id).await.unwrap());
+    assert!(decision.is_approved());
+    
+    // Gerrit OWNERS files count too
+    adapter.set_changed_files(&review_id, vec!["payments/charge.rs".to_string(), "docs/guide.md".to_string()]);
+    assert!(!policy.evaluate(&adapter.get_review_status(&review_id).await.unwrap()).is_approved());
+    adapter.add_vote(&review_id, "dana", ReviewDecision::Approved);
+    let mut status = adapter.get_review_status(&review_id).await.unwrap();
+    assert!(policy.evaluate(&status).is_approved());
+    
+    // Paths the platform didn't list might have other owners
+    status.changed_files_truncated = true;
+    let decision = policy.evaluate(&status);
+    assert!(!decision.is_approved());
+    assert!(decision.problems.iter().any(|p| p.contains("Too many changed files")));
+}
+
+#[tokio::test]
+async fn test_reviewer_suggestions() {
+    use garry::review::owners::CodeOwners;
+    use garry::review::suggest::ReviewerSuggester;
+    use garry::utils::config::ReviewersConfig;
+    use std::collections::HashMap;
+    
+    let adapter = MockVcsAdapter::new();
+    for _ in 0..2 {
+        let (id, _) = adapter.create_review("feature/busy", "main", "Busy", "Description").await.unwrap();
+        adapter.request_reviewers(&id, &["carol".to_string()]).await.unwrap();
+    }
+    let open_reviews = adapter.list_open_reviews().await.unwrap();
+    assert_eq!(open_reviews[0].requested_reviewers, vec!["carol".to_string()]);
+    
+    let config = ReviewersConfig {
+        count: 3,
+        aliases: HashMap::from([
+            ("erin@example.com".to_string(), "erin".to_string()),
+            ("me@example.com".to_string(), "alice".to_string()),
+        ]),
+        ..ReviewersConfig::default()
+    };
+    let owners = CodeOwners::parse_codeowners("/payments/ @org/payments\n");
+    let teams = HashMap::from([("org/payments".to_string(), vec!["carol".to_string(), "dave".to_string()])]);
+    let blame = HashMap::from([
+        ("erin@example.com".to_string(), 30),
+        ("me@example.com".to_string(), 100),
+        ("unmapped@example.com".to_string(), 15),
+    ]);
+    
+    let suggestions = ReviewerSuggester::new(config)
+        .with_owners(owners, teams)
+        .with_blame(blame)
+        .with_open_reviews(&open_reviews)
+        .suggest("alice", &["payments/charge.rs".to_string()]);
+    let reviewers: Vec<&str> = suggestions.iter().map(|s| s.reviewer.as_str()).collect();
+    // Carol owns the path as much as Dave, but already has two reviews waiting;
+    // the author and unmapped emails are never suggested
+    assert_eq!(reviewers, vec!["dave", "carol", "erin"]);
+    assert_eq!(suggestions[1].open_reviews, 2);
+    assert_eq!(suggestions[0].reasons, vec!["owns /payments/".to_string()]);
+}
+
+#[test]
+fn test_blame_authors_before_branch() {
+    use garry::git::GitRepository;
+    
+    let (dir, local, root) = init_repo();
+    checkout(&local, "feature", Some(root));
+    commit_file(&local, "README.md", "hello\nworld\n", "Extend readme");
+    commit_file(&local, "new.txt", "new\n", "Add file");
+    
+    // Only lines that existed before the branch count, and new files are skipped
+    let repo = GitRepository::open(dir.path()).unwrap();
+    let authors = repo.blame_authors("main", &["README.md".to_string(), "new.txt".to_string()]).unwrap();
+    assert_eq!(authors.into_iter().collect::<Vec<_>>(), vec![("test@example.com".to_string(), 1)]);
+}
+
+#[test]
+fn test_label_rule_language() {
+    use garry::review::ReviewDecision;
+    use garry::review::labels::{self, LabelDefinition, LabelRule, LabelThreshold};
+    use garry::utils::error::GarryError;
+    use std::collections::BTreeMap;
+    
+    let rules = labels::parse_rules("Code-Review needs max with no min, Verified +1, QA no min").unwrap();
+    assert_eq!(rules, vec![
+        LabelRule { label: "Code-Review".to_string(), threshold: LabelThreshold::Max, block_on_min: true },
+        LabelRule { label: "Verified".to_string(), threshold: LabelThreshold::AtLeast(1), block_on_min: false },
+        LabelRule { label: "QA".to_string(), threshold: LabelThreshold::Any, block_on_min: true },
+    ]);
+    assert!(labels::parse_rules("Code-Review wants lots").is_err());
+    
+    assert_eq!(labels::parse_vote("Code-Review=+2").unwrap(), ("Code-Review".to_string(), 2));
+    assert_eq!(labels::parse_vote("Verified=-1").unwrap(), ("Verified".to_string(), -1));
+    assert!(matches!(labels::parse_vote("Code-Review"), Err(GarryError::InvalidVote(_))));
+    
+    // Votes survive a round trip through a review body
+    let votes = BTreeMap::from([("Code-Review".to_string(), 1), ("Verified".to_string(), -1)]);
+    let body = format!("Looks fine\n\n{}", labels::format_trailers(&votes));
+    assert_eq!(labels::parse_trailers(&body), votes);
+    
+    let definitions = vec![LabelDefinition::new("Code-Review", -2, 2), LabelDefinition::new("Verified", -1, 1)];
+    assert_eq!(labels::decision_for(&definitions, "Code-Review", &votes), ReviewDecision::ChangesRequested);
+    let votes = BTreeMap::from([("Code-Review".to_string(), 2)]);
+    assert_eq!(labels::decision_for(&definitions, "Code-Review", &votes), ReviewDecision::Approved);
+    let votes = BTreeMap::from([("Code-Review".to_string(), -1)]);
+    assert_eq!(labels::decision_for(&definitions, "Code-Review", &votes), ReviewDecision::Commented);
+}
+
+#[tokio::test]
+async fn test_policy_label_rules() {
+    use garry::review::ReviewDecision;
+    use garry::review::policy::ApprovalPolicy;
+    use garry::utils::config::PolicyConfig;
+    use std::collections::BTreeMap;
+    
+    let adapter = MockVcsAdapter::new();
+    let (review_id, _) = adapter
+        .create_review("feature/labels", "main", "Labels", "Description")
+        .await
+        .expect("Failed to create review");
+    let policy = ApprovalPolicy::new(PolicyConfig {
+        label_rules: "Code-Review needs max with no min, Verified +1".to_string(),
+        ..PolicyConfig::default()
+    });
+    let vote = |reviewer: &str, decision, label: &str, value| {
+        adapter.add_label_vote(&review_id, reviewer, decision, BTreeMap::from([(label.to_string(), value)]));
+    };
+    
+    vote("alice", ReviewDecision::Commented, "Code-Review", 1);
+    let decision = policy.evaluate(&adapter.get_review_status(&review_id).await.unwrap());
+    assert!(!decision.is_approved());
+    assert!(decision.problems.contains(&"Code-Review needs +2".to_string()));
+    
+    // A platform approval counts as the review label's maximum
+    adapter.add_vote(&review_id, "bob", ReviewDecision::Approved);
+    let decision = policy.evaluate(&adapter.get_review_status(&review_id).await.unwrap());
+    assert_eq!(decision.labels["Code-Review"]["bob"], 2);
+    assert_eq!(decision.problems, vec!["Verified needs +1".to_string()]);
+    
+    vote("ci", ReviewDecision::Commented, "Verified", 1);
+    assert!(policy.evaluate(&adapter.get_review_status(&review_id).await.unwrap()).is_approved());
+    
+    // A minimum vote vetoes the review whatever else was voted
+    vote("dave", ReviewDecision::ChangesRequested, "Code-Review", -2);
+    let decision = policy.evaluate(&adapter.get_review_status(&review_id).await.unwrap());
+    assert_eq!(decision.state, ReviewState::ChangesRequested);
+    assert!(decision.problems.contains(&"Code-Review -2 from dave".to_string()));
+}
+
+#[test]
+fn test_code_owners_from_base_branch() {
+    use garry::git::GitRepository;
+    use std::collections::HashMap;
+    
+    let (dir, repo, _) = init_repo();
+    std::fs::create_dir_all(dir.path().join(".github")).unwrap();
+    std::fs::create_dir_all(dir.path().join("payments")).unwrap();
+    commit_file(&repo, ".github/CODEOWNERS", "/payments/ @alice\n", "Add owners");
+    let base = commit_file(&repo, "payments/OWNERS", "bob@example.com\n", "Add Gerrit owners");
+    
+    // The change can't grant itself ownership
+    checkout(&repo, "feature", Some(base));
+    commit_file(&repo, ".github/CODEOWNERS", "* @mallory\n", "Take over");
+    commit_file(&repo, "payments/charge.rs", "fn charge() {}\n", "Charge");
+    
+    let repo = GitRepository::open(dir.path()).unwrap();
+    let owners = repo.code_owners("main").unwrap();
+    let files = repo.changed_files("main").unwrap();
+    let approvers = vec!["mallory".to_string()];
+    
+    let missing = owners.missing_groups(&files, &approvers, &HashMap::new());
+    let patterns: Vec<&str> = missing.iter().map(|g| g.pattern.as_str()).collect();
+    assert_eq!(patterns, vec!["/payments/", "/payments/"]);
+}
+
+#[tokio::test]
+async fn test_unresolved_threads_block_approval() {
+    use garry::review::{DiffSide, InlineComment, ReviewDecision};
+    use garry::review::policy::ApprovalPolicy;
+    use garry::utils::config::PolicyConfig;
+    
+    let adapter = MockVcsAdapter::new();
+    let (review_id, _) = adapter
+        .create_review("feature/threads", "main", "Threads", "Description")
+        .await
+        .expect("Failed to create review");
+    adapter.add_vote(&review_id, "alice", ReviewDecision::Approved);
+    adapter.post_inline_comment(&review_id, &InlineComment {
+        file: "src/lib.rs".to_string(),
+        line: 12,
+        side: DiffSide::New,
+        body: "Needs a test".to_string(),
+    }).await.expect("Failed to post inline comment");
+    
+    let status = adapter.get_review_status(&review_id).await.unwrap();
+    assert_eq!(status.threads.len(), 1);
+    assert_eq!(status.threads[0].file, "src/lib.rs");
+    assert_eq!(status.threads[0].line, Some(12));
+    assert!(!status.threads[0].resolved);
+    
+    // Threads only block when the policy asks for it
+    assert!(ApprovalPolicy::new(PolicyConfig::default()).evaluate(&status).is_approved());
+    let policy = ApprovalPolicy::new(PolicyConfig {
+        block_on_unresolved_threads: true,
+        ..PolicyConfig::default()
+    });
+    let decision = policy.evaluate(&status);
+    assert_eq!(decision.state, ReviewState::Open);
+    assert_eq!(decision.problems, vec!["1 unresolved comment thread(s)".to_string()]);
+    
+    adapter.resolve_thread(&review_id, &status.threads[0].id);
+    assert!(policy.evaluate(&adapter.get_review_status(&review_id).await.unwrap()).is_approved());
+}
+
+#[test]
+fn test_map_comment_line_to_workdir() {
+    use garry::git::GitRepository;
+    
+    let (dir, repo, _) = init_repo();
+    let original = "one\ntwo\nthree\nfour\nfive\n";
+    let commented = commit_file(&repo, "notes.txt", original, "Add notes");
+    let repo = GitRepository::open(dir.path()).unwrap();
+    let commit = commented.to_string();
+    
+    // Two lines inserted above, "three" changed, "five" untouched
+    std::fs::write(dir.path().join("notes.txt"), "zero\nhalf\none\ntwo\nTHREE\nfour\nfive\n").unwrap();
+    assert_eq!(repo.map_line(&commit, "notes.txt", 1).unwrap(), Some(3));
+    assert_eq!(repo.map_line(&commit, "notes.txt", 3).unwrap(), None);
+    assert_eq!(repo.map_line(&commit, "notes.txt", 5).unwrap(), Some(7));
+    
+    std::fs::remove_file(dir.path().join("notes.txt")).unwrap();
+    assert_eq!(repo.map_line(&commit, "notes.txt", 5).unwrap(), None);
+}
+
+#[tokio::test]
+async fn test_chat_commands_control_the_queue() {
+    use garry::bot::adapters::mock::MOCK_USER;
+    use garry::bot::chatops::{ChatCommand, ChatOps};
+    use garry::bot::notifier::Notifier;
+    use garry::bot::queue::{Priority, QueueManager};
+    use garry::review::RepoPermission;
+    use garry::review::policy::ApprovalPolicy;
+    use garry::utils::config::PolicyConfig;
+    use std::collections::HashSet;
+    use tokio::sync::Mutex;
+    
+    assert_eq!(ChatCommand::parse("LGTM\n/garry priority high"), Some(Ok(ChatCommand::Priority(Priority::High))));
+    assert_eq!(ChatCommand::parse("/garrybot merge"), None);
+    assert!(matches!(ChatCommand::parse("/garry ship it"), Some(Err(_))));
+    
+    let adapter = MockVcsAdapter::new();
+    let mut reviews = Vec::new();
+    for branch in ["feature/one", "feature/two"] {
+        let (review_id, _) = adapter.create_review(branch, "main", "Change", "Description").await.unwrap();
+        adapter.approve_review(&review_id, None).await.unwrap();
+        adapter.set_ci_status(&review_id, CiStatus::Success);
+        reviews.push(review_id);
+    }
+    let (one, two) = (&reviews[0], &reviews[1]);
+    adapter.set_permission("alice", RepoPermission::Write);
+    adapter.set_permission("bob", RepoPermission::Maintain);
+    
+    let queue = QueueManager::new(Box::new(adapter.clone()))
+        .with_target("release/*".to_string(), ApprovalPolicy::new(PolicyConfig::default()));
+    let queue = Mutex::new(queue);
+    let notifier = Notifier::new(Box::new(adapter.clone()));
+    let mut chatops = ChatOps::new("garry-bot".to_string(), chrono::Utc::now());
+    macro_rules! poll {
+        () => { chatops.poll(&adapter, &queue, &notifier, chrono::Utc::now()).await.unwrap() };
+    }
+    
+    // Read access isn't enough to queue someone else's review
+    adapter.add_comment(one, "eve", "/garry queue");
+    assert_eq!(poll!(), 1);
+    assert!(queue.lock().await.get_queue().is_empty());
+    assert!(adapter.comments(one).last().unwrap().contains("Permission denied: @eve is a developer"));
+    
+    adapter.add_comment(one, "alice", "Looks good\n/garry queue");
+    adapter.add_comment(two, "alice", "/garry merge");
+    assert_eq!(poll!(), 2);
+    assert_eq!(queue.lock().await.get_queue_position(one), Some(0));
+    assert_eq!(queue.lock().await.get_queue_position(two), Some(1));
+    assert!(adapter.comments(two).last().unwrap().contains("position 2 of 2 into main"));
+    
+    // Commands are only run once
+    assert_eq!(poll!(), 0);
+    
+    adapter.add_comment(two, "alice", "/garry priority high");
+    adapter.add_comment(two, "bob", "/garry priority high");
+    assert_eq!(poll!(), 2);
+    assert_eq!(queue.lock().await.get_queue_position(two), Some(0));
+    
+    // Authors can hold their own review, and discovery leaves it alone,
+    // even when the review was found before the hold
+    let rules = queue.lock().await.rules().clone();
+    let found = rules.find_queueable(&adapter, &HashSet::new()).await.unwrap();
+    assert!(found.iter().any(|status| &status.id == one));
+    adapter.add_comment(one, MOCK_USER, "/garry hold");
+    poll!();
+    assert!(queue.lock().await.is_held(one));
+    queue.lock().await.add_discovered(found);
+    queue.lock().await.discover_and_queue_reviews().await.unwrap();
+    assert_eq!(queue.lock().await.get_queue_position(one), None);
+    
+    adapter.add_comment(one, "alice", "/garry merge");
+    poll!();
+    assert!(!queue.lock().await.is_held(one));
+    assert_eq!(queue.lock().await.get_queue_position(one), Some(1));
+    
+    // Replies give the place among reviews into the same target
+    let (fix, _) = adapter.create_review("feature/fix", "release/2.3", "Fix", "Description").await.unwrap();
+    adapter.approve_review(&fix, None).await.unwrap();
+    adapter.set_ci_status(&fix, CiStatus::Success);
+    adapter.add_comment(&fix, "alice", "/garry merge");
+    poll!();
+    assert_eq!(queue.lock().await.get_queue_position(&fix), Some(2));
+    assert!(adapter.comments(&fix).last().unwrap().contains("position 1 of 1 into release/2.3"));
+}
+
+#[tokio::test]
+async fn test_roles_gate_approvals_and_queue() {
+    use garry::bot::queue::QueueManager;
+    use garry::review::{RepoPermission, ReviewDecision};
+    use garry::review::roles::{Action, Role, Roles};
+    use garry::utils::config::RolesConfig;
+    use garry::utils::error::GarryError;
+    
+    let adapter = MockVcsAdapter::new();
+    adapter.set_team_members("org/core", vec!["carol".to_string()]);
+    adapter.set_permission("dave", RepoPermission::Admin);
+    let config = RolesConfig {
+        maintainers: vec!["alice".to_string(), "org/core".to_string()],
+        sync_forge_permissions: false,
+        ..RolesConfig::default()
+    };
+    let roles = Roles::load(config, &adapter).await;
+    
+    assert_eq!(roles.role_of(&adapter, "alice").await.unwrap(), Role::Maintainer);
+    assert_eq!(roles.role_of(&adapter, "carol").await.unwrap(), Role::Maintainer);
+    assert_eq!(roles.role_of(&adapter, "garry-bot").await.unwrap(), Role::Bot);
+    // App logins aren't bots unless configured as one
+    assert_eq!(roles.role_of(&adapter, "deps[bot]").await.unwrap(), Role::Developer);
+    // Forge access only counts when synced
+    assert_eq!(roles.role_of(&adapter, "dave").await.unwrap(), Role::Developer);
+    
+    assert!(roles.check(&adapter, "garry-bot", Action::ControlQueue).await.is_ok());
+    assert!(matches!(roles.check(&adapter, "garry-bot", Action::Approve).await, Err(GarryError::PermissionDenied(_))));
+    assert!(matches!(roles.check(&adapter, "dave", Action::SetPriority).await, Err(GarryError::PermissionDenied(_))));
+    
+    // The queue ignores approvals from developers
+    let (review_id, _) = adapter.create_review("feature/roles", "main", "Roles", "Description").await.unwrap();
+    adapter.set_ci_status(&review_id, CiStatus::Success);
+    adapter.add_vote(&review_id, "dave", ReviewDecision::Approved);
+    let mut queue = QueueManager::new(Box::new(adapter.clone())).with_roles(roles.clone());
+    assert!(queue.add_to_queue(review_id.clone()).await.is_err());
+    
+    adapter.add_vote(&review_id, "carol", ReviewDecision::Approved);
+    queue.add_to_queue(review_id.clone()).await.unwrap();
+    assert_eq!(queue.get_queue_position(&review_id), Some(0));
+}
+
+#[tokio::test]
+async fn test_notification_channels_and_routing() {
+    use std::collections::HashMap;
+    use garry::bot::channel::ChannelRoute;
+    use garry::bot::channels::{EmailChannel, MatrixChannel, SlackChannel, WebhookChannel};
+    use garry::bot::notifier::{NotificationType, Notifier};
+    use garry::utils::config::SmtpSecurity;
+    use mockito::Matcher;
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    
+    let mut server = mockito::Server::new_async().await;
+    let slack = server.mock("POST", "/slack")
+        .match_body(Matcher::Regex("merged".to_string()))
+        .with_status(200)
+        .expect(1)
+        .create_async()
+        .await;
+    let matrix = server.mock("PUT", Matcher::Regex("^/_matrix/client/v3/rooms/!room:example.org/send/m.room.message/garry-".to_string()))
+        .match_header("authorization", "Bearer matrix-token")
+        .match_body(Matcher::PartialJsonString(r#"{"msgtype": "m.text"}"#.to_string()))
+        .with_status(200)
+        .with_body("{}")
+        .expect(2)
+        .create_async()
+        .await;
+    let webhook = server.mock("POST", "/hook")
+        .match_header("x-token", "secret")
+        .match_body(Matcher::PartialJsonString(r#"{"event": "ci_failed", "repository": "org/repo", "review_id": "1"}"#.to_string()))
+        .with_status(204)
+        .expect(1)
+        .create_async()
+        .await;
+    let other_repo = server.mock("POST", "/other").expect(0).create_async().await;
+    
+    // A minimal SMTP server that records the message it's sent
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let smtp_port = listener.local_addr().unwrap().port();
+    let smtp = tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let (read, mut write) = stream.into_split();
+        let mut lines = BufReader::new(read).lines();
+        let mut data = String::new();
+        let mut in_data = false;
+        write.write_all(b"220 localhost ESMTP\r\n").await.unwrap();
+        while let Some(line) = lines.next_line().await.unwrap() {
+            let reply: &[u8] = if in_data {
+                if line != "." {
+                    data.push_str(&line);
+                    data.push('\n');
+                    continue;
+                }
+                in_data = false;
+                b"250 Queued\r\n"
+            } else if line.starts_with("EHLO") {
+                b"250-localhost\r\n250 8BITMIME\r\n"
+            } else if line == "DATA" {
+                in_data = true;
+                b"354 Go ahead\r\n"
+            } else if line == "QUIT" {
+                write.write_all(b"221 Bye\r\n").await.unwrap();
+                break;
+            } else {
+                b"250 OK\r\n"
+            };
+            write.write_all(reply).await.unwrap();
+        }
+        data
+    });
+    
+    let only = |events: &[&str]| ChannelRoute { events: events.iter().map(|e| e.to_string()).collect(), repositories: vec![], digests: vec![] };
+    let headers = HashMap::from([("X-Token".to_string(), "secret".to_string())]);
+    let email = EmailChannel::new(
+        "127.0.0.1",
+        smtp_port,
+        SmtpSecurity::None,
+        None,
+        "Garry <garry@example.com>",
+        &["team@example.com".to_string()],
+    ).unwrap();
+    
+    let adapter = MockVcsAdapter::new();
+    let (review_id, _) = adapter.create_review("feature/notify", "main", "Notify", "Description").await.unwrap();
+    let notifier = Notifier::new(Box::new(adapter.clone()))
+        .with_repository("org/repo".to_string())
+        .with_channel(only(&["merge_success"]), Box::new(SlackChannel::new(format!("{}/slack", server.url()))))
+        .with_channel(ChannelRoute::default(), Box::new(MatrixChannel::new(
+            server.url(),
+            "!room:example.org".to_string(),
+            "matrix-token".to_string(),
+        )))
+        .with_channel(only(&["ci_failed"]), Box::new(WebhookChannel::new(format!("{}/hook", server.url()), &headers).unwrap()))
+        .with_channel(
+            ChannelRoute { events: vec![], repositories: vec!["org/other".to_string()], digests: vec![] },
+            Box::new(WebhookChannel::new(format!("{}/other", server.url()), &HashMap::new()).unwrap()),
+        )
+        .with_channel(only(&["merge_success"]), Box::new(email));
+    
+    notifier.notify(&review_id, NotificationType::MergeSuccess).await.unwrap();
+    notifier.notify(&review_id, NotificationType::CiFailed(vec!["tests".to_string()])).await.unwrap();
+    
+    slack.assert_async().await;
+    matrix.assert_async().await;
+    webhook.assert_async().await;
+    other_repo.assert_async().await;
+    let email = smtp.await.unwrap();
+    assert!(email.contains("Subject: [org/repo] Review 1: merged"));
+    assert!(email.contains("To: team@example.com"));
+    // Review comments are still posted
+    assert_eq!(adapter.comments(&review_id).len(), 2);
+}
+
+#[tokio::test]
+async fn test_notification_templates_and_formats() {
+    use garry::bot::notifier::{NotificationType, Notifier};
+    use garry::bot::templates::{MessageFormat, MessageTemplates};
+    
+    let adapter = MockVcsAdapter::new();
+    let (review_id, _) = adapter.create_review("feature/templates", "main", "Templates", "Description").await.unwrap();
+    
+    // Built-in messages name the configured main branch
+    let notifier = Notifier::new(Box::new(adapter.clone())).with_main_branch("develop".to_string());
+    notifier.notify(&review_id, NotificationType::MergeSuccess).await.unwrap();
+    assert!(adapter.comments(&review_id).last().unwrap().contains("merged to develop"));
+    
+    let templates = MessageTemplates::parse(r#"
+        [en]
+        ci_failed = "**{checks}** failed on [{branch}]({url})"
+        
+        [de]
+        merge_success = "{review} von @{author} wurde in {main_branch} gemergt"
+    "#, "de-AT").unwrap();
+    let notifier = Notifier::new(Box::new(adapter.clone()))
+        .with_repository("org/repo".to_string())
+        .with_templates(templates);
+    
+    // The language's templates are used for a regional locale, and English fills the gaps
+    let (subject, message) = notifier.render(&review_id, &NotificationType::MergeSuccess).await;
+    assert_eq!(subject, "[org/repo] Review 1: merged");
+    assert_eq!(message, "1 von @mock-user wurde in main gemergt");
+    let (_, message) = notifier.render(&review_id, &NotificationType::CiFailed(vec!["lint".to_string()])).await;
+    assert_eq!(message, "**lint** failed on [feature/templates](https://mock-vcs.example.com/review/1)");
+    
+    assert_eq!(MessageFormat::Plain.render(&message), "lint failed on feature/templates (https://mock-vcs.example.com/review/1)");
+    assert_eq!(
+        MessageFormat::SlackBlocks.render(&message),
+        "*lint* failed on <https://mock-vcs.example.com/review/1|feature/templates>"
+    );
+    
+    assert!(MessageTemplates::parse("[en]\nmerge_success = \"{sha} merged\"", "en").is_err());
+    assert!(MessageTemplates::parse("[en]\nmerged = \"Merged\"", "en").is_err());
+    assert!(MessageTemplates::parse("[en]\nmerge_success = \"{{literal}} braces\"", "en").is_ok());
+}
+
+#[tokio::test]
+async fn test_status_comment_is_edited_in_place() {
+    use garry::bot::notifier::{NotificationType, Notifier, STATUS_MARKER};
+    use garry::bot::queue::QueueManager;
+    
+    let adapter = MockVcsAdapter::new();
+    let mut reviews = Vec::new();
+    for branch in ["feature/one", "feature/two", "feature/three"] {
+        let (review_id, _) = adapter.create_review(branch, "main", "Change", "Description").await.unwrap();
+        adapter.approve_review(&review_id, None).await.unwrap();
+        adapter.set_ci_status(&review_id, CiStatus::Success);
+        reviews.push(review_id);
+    }
+    let (one, two, three) = (&reviews[0], &reviews[1], &reviews[2]);
+    
+    let mut queue = QueueManager::new(Box::new(adapter.clone())).with_notifier(Notifier::new(Box::new(adapter.clone())));
+    for review_id in &reviews {
+        queue.add_to_queue(review_id.clone()).await.unwrap();
+    }
+    
+    // Every queued review gets a single status comment
+    queue.process_queue().await.unwrap();
+    for (position, review_id) in reviews.iter().enumerate() {
+        let comments = adapter.comments(review_id);
+        assert_eq!(comments.len(), 1);
+        assert!(comments[0].starts_with(STATUS_MARKER));
+        assert!(comments[0].contains(&format!("position: {} of 3", position + 1)));
+    }
+    assert!(adapter.comments(one)[0].contains("State: merging"));
+    assert!(adapter.comments(two)[0].contains("Estimated merge: unknown"));
+    
+    // Merging replaces the status comment with a terminal comment, and the
+    // rest of the queue is edited in place
+    queue.process_queue().await.unwrap();
+    let comments = adapter.comments(one);
+    assert_eq!(comments.len(), 1);
+    assert!(comments[0].contains("merged to main"));
+    let comments = adapter.comments(two);
+    assert_eq!(comments.len(), 1);
+    assert!(comments[0].contains("position: 1 of 2"));
+    assert!(!comments[0].contains("Estimated merge: unknown"));
+    
+    // Reviews that leave the queue lose their status comment
+    queue.hold(three);
+    queue.process_queue().await.unwrap();
+    assert!(adapter.comments(three).is_empty());
+    
+    // Repeated terminal notifications are only posted once
+    let notifier = Notifier::new(Box::new(adapter.clone()));
+    let failed = NotificationType::CiFailed(vec!["tests".to_string()]);
+    notifier.notify(three, failed.clone()).await.unwrap();
+    notifier.notify(three, failed).await.unwrap();
+    assert_eq!(adapter.comments(three).len(), 1);
+    
+    // A restarted notifier finds its status comment again
+    let notifier = Notifier::new(Box::new(adapter.clone()));
+    notifier.notify(two, NotificationType::QueuePosition(0)).await.unwrap();
+    let comments = adapter.comments(two);
+    assert_eq!(comments.len(), 1);
+    assert!(comments[0].contains("merge queue at position 1"));
+}
+
+#[tokio::test]
+async fn test_digest_reports() {
+    use chrono::{Duration, Timelike, Utc};
+    use garry::bot::channel::ChannelRoute;
+    use garry::bot::channels::WebhookChannel;
+    use garry::bot::digest::{DigestCollector, DigestScheduler};
+    use garry::bot::notifier::Notifier;
+    use garry::bot::queue::QueueManager;
+    use garry::utils::config::{DigestConfig, DigestReportConfig, DigestSchedule};
+    use mockito::Matcher;
+    use std::collections::HashMap;
+    
+    let adapter = MockVcsAdapter::new();
+    let start = Utc::now();
+    
+    // One review waiting on alice, one stuck in the queue, one that failed CI
+    let (waiting, _) = adapter.create_review("feature/waiting", "main", "Waiting", "Description").await.unwrap();
+    adapter.request_reviewers(&waiting, &["alice".to_string()]).await.unwrap();
+    adapter.set_created_at(&waiting, start - Duration::days(3));
+    let (stuck, _) = adapter.create_review("feature/stuck", "main", "Stuck", "Description").await.unwrap();
+    let (failing, _) = adapter.create_review("feature/failing", "main", "Failing", "Description").await.unwrap();
+    for review_id in [&failing, &stuck] {
+        adapter.approve_review(review_id, None).await.unwrap();
+        adapter.set_ci_status(review_id, CiStatus::Success);
+    }
+    
+    let mut queue = QueueManager::new(Box::new(adapter.clone()));
+    queue.add_to_queue(failing.clone()).await.unwrap();
+    queue.add_to_queue(stuck.clone()).await.unwrap();
+    adapter.set_ci_status(&failing, CiStatus::Failed(vec!["tests".to_string()]));
+    queue.process_queue().await.unwrap();
+    queue.process_queue().await.unwrap();
+    
+    // Without the queue, as from the CLI, only the forge sections are shown
+    let config = DigestConfig {
+        schedule: DigestSchedule::Daily,
+        hour: (start + Duration::hours(5)).hour(),
+        reports: vec![
+            DigestReportConfig { name: "Everyone".to_string(), members: vec![] },
+            DigestReportConfig { name: "Alice".to_string(), members: vec!["alice".to_string()] },
+            DigestReportConfig { name: "Bob".to_string(), members: vec!["bob".to_string()] },
+        ],
+        ..DigestConfig::default()
+    };
+    let digest = DigestCollector::new(&config).collect(&adapter, None, start).await.unwrap();
+    assert_eq!(digest.waiting_on["alice"][0].id, waiting);
+    assert_eq!(digest.unreviewed[0].id, waiting);
+    let markdown = digest.to_markdown("Review");
+    assert!(markdown.contains("**Oldest unreviewed**"));
+    assert!(markdown.contains("opened 3d ago"));
+    assert!(!markdown.contains("Stuck in the merge queue"));
+    
+    // Each report goes to the channels routed to it; empty reports aren't sent
+    let mut server = mockito::Server::new_async().await;
+    let everyone = server.mock("POST", "/everyone")
+        .match_body(Matcher::AllOf(vec![
+            Matcher::PartialJsonString(r#"{"event": "digest", "subject": "[org/repo] Everyone digest", "review_id": null}"#.to_string()),
+            Matcher::Regex("Stuck in the merge queue.*queued \\d+h ago: waiting".to_string()),
+            Matcher::Regex("Failed merges.*CI failed: tests".to_string()),
+        ]))
+        .with_status(200)
+        .expect(1)
+        .create_async()
+        .await;
+    let alice = server.mock("POST", "/alice")
+        .match_body(Matcher::Regex("@alice \\(1\\)".to_string()))
+        .with_status(200)
+        .expect(1)
+        .create_async()
+        .await;
+    let route = |report: &str| ChannelRoute { digests: vec![report.to_string()], ..ChannelRoute::default() };
+    let notifier = Notifier::new(Box::new(adapter.clone()))
+        .with_repository("org/repo".to_string())
+        .with_channel(route("Everyone"), Box::new(WebhookChannel::new(format!("{}/everyone", server.url()), &HashMap::new()).unwrap()))
+        .with_channel(route("Alice"), Box::new(WebhookChannel::new(format!("{}/alice", server.url()), &HashMap::new()).unwrap()));
+    
+    let mut scheduler = DigestScheduler::new(&config, start).unwrap();
+    assert!(!scheduler.is_due(start));
+    let sent_at = start + Duration::hours(5);
+    assert_eq!(scheduler.poll(&adapter, &queue.snapshot(), &notifier, sent_at).await.unwrap(), 2);
+    // Nothing more until the next day
+    assert_eq!(scheduler.poll(&adapter, &queue.snapshot(), &notifier, sent_at + Duration::hours(1)).await.unwrap(), 0);
+    
+    everyone.assert_async().await;
+    alice.assert_async().await;
+    assert!(DigestScheduler::new(&DigestConfig::default(), start).is_none());
+}
+
+#[tokio::test]
+async fn test_admin_api() {
+    use std::sync::{Arc, RwLock};
+    use chrono::{Duration, Utc};
+    use garry::bot::queue::{QueueManager, QueueStatus};
+    use garry::bot::server::{self, Health, ServerState};
+    use serde_json::{json, Value};
+    
+    let adapter = MockVcsAdapter::new();
+    let mut reviews = Vec::new();
+    for branch in ["feature/one", "feature/two"] {
+        let (review_id, _) = adapter.create_review(branch, "main", "Change", "Description").await.unwrap();
+        adapter.approve_review(&review_id, None).await.unwrap();
+        adapter.set_ci_status(&review_id, CiStatus::Success);
+        reviews.push(review_id);
+    }
+    let (one, two) = (reviews[0].as_str(), reviews[1].as_str());
+    
+    let queue = QueueManager::new(Box::new(adapter.clone()));
+    let snapshot = Arc::new(RwLock::new(queue.snapshot()));
+    let queue = Arc::new(tokio::sync::Mutex::new(queue));
+    let health = Arc::new(Health::new(Duration::minutes(5), Utc::now()));
+    let serve = |state: ServerState| {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.set_nonblocking(true).unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(server::serve(listener, state));
+        url
+    };
+    let url = serve(ServerState::new(queue.clone(), snapshot.clone(), health.clone()).with_token("secret".to_string()));
+    let client = reqwest::Client::new();
+    let api = |method: reqwest::Method, path: &str| {
+        client.request(method, format!("{}/api{}", url, path)).bearer_auth("secret")
+    };
+    
+    // Ready once the bot loop has run; health checks need no token
+    let ready = client.get(format!("{}/readyz", url)).send().await.unwrap();
+    assert_eq!(ready.status(), 503);
+    health.tick(Utc::now());
+    let ready = client.get(format!("{}/readyz", url)).send().await.unwrap();
+    assert_eq!(ready.status(), 200);
+    let healthy = client.get(format!("{}/healthz", url)).send().await.unwrap();
+    assert_eq!(healthy.status(), 200);
+    assert!(!health.is_live(Utc::now() + Duration::minutes(6)));
+    
+    // The API needs the token
+    let denied = client.get(format!("{}/api/queue", url)).bearer_auth("wrong").send().await.unwrap();
+    assert_eq!(denied.status(), 401);
+    
+    // Enqueue both, then move the second to the front
+    for review in [one, two] {
+        let queued = api(reqwest::Method::POST, "/queue").json(&json!({ "review": review })).send().await.unwrap();
+        assert_eq!(queued.status(), 200);
+    }
+    let moved: Value = api(reqwest::Method::POST, &format!("/queue/{}/move", two))
+        .json(&json!({ "position": 1 }))
+        .send().await.unwrap().json().await.unwrap();
+    assert_eq!((&moved["position"], &moved["queue_length"]), (&json!(1), &json!(2)));
+    let listed: Value = api(reqwest::Method::GET, "/queue").send().await.unwrap().json().await.unwrap();
+    assert_eq!(listed["entries"][0]["review_id"], two);
+    assert_eq!(listed["entries"][1]["review_id"], one);
+    
+    // Entries show their history
+    let entry: Value = api(reqwest::Method::GET, &format!("/queue/{}", two)).send().await.unwrap().json().await.unwrap();
+    assert_eq!((&entry["position"], &entry["queue_length"]), (&json!(1), &json!(2)));
+    let history: Vec<&str> = entry["entry"]["history"].as_array().unwrap()
+        .iter().map(|e| e["description"].as_str().unwrap()).collect();
+    assert_eq!(history, ["queued", "moved to position 1"]);
+    let missing = api(reqwest::Method::GET, "/queue/999").send().await.unwrap();
+    assert_eq!(missing.status(), 404);
+    
+    // A paused queue doesn't process entries
+    let paused: Value = api(reqwest::Method::POST, "/queue/pause").send().await.unwrap().json().await.unwrap();
+    assert_eq!(paused["paused"], true);
+    queue.lock().await.process_queue().await.unwrap();
+    assert_eq!(queue.lock().await.get_queue()[0].status, QueueStatus::Pending);
+    api(reqwest::Method::POST, "/queue/resume").send().await.unwrap();
+    queue.lock().await.process_queue().await.unwrap();
+    assert_eq!(queue.lock().await.get_queue()[0].status, QueueStatus::Merging);
+    
+    // Dequeued reviews are held, so discovery leaves them out
+    let dequeued = api(reqwest::Method::DELETE, &format!("/queue/{}", one)).send().await.unwrap();
+    assert_eq!(dequeued.status(), 200);
+    let listed: Value = api(reqwest::Method::GET, "/queue").send().await.unwrap().json().await.unwrap();
+    assert_eq!(listed["held"], json!([one]));
+    assert_eq!(listed["entries"].as_array().unwrap().len(), 1);
+    
+    // Without a token, only the health checks are served
+    let url = serve(ServerState::new(queue.clone(), snapshot.clone(), health.clone()));
+    let api_off = client.get(format!("{}/api/queue", url)).send().await.unwrap();
+    assert_eq!(api_off.status(), 404);
+}
+
+#[tokio::test]
+async fn test_metrics_endpoint() {
+    use std::sync::{Arc, RwLock};
+    use chrono::{Duration, Utc};
+    use garry::bot::queue::QueueManager;
+    use garry::bot::server::{self, Health, ServerState};
+    
+    let adapter = MockVcsAdapter::new();
+    let (review_id, _) = adapter.create_review("feature/metrics", "main", "Change", "Description").await.unwrap();
+    adapter.approve_review(&review_id, None).await.unwrap();
+    adapter.set_ci_status(&review_id, CiStatus::Success);
+    
+    let mut manager = QueueManager::new(Box::new(adapter.clone())).with_repository("acme/metrics".to_string());
+    manager.add_to_queue(review_id.clone()).await.unwrap();
+    manager.process_queue().await.unwrap();
+    manager.process_queue().await.unwrap();
+    assert!(manager.get_queue().is_empty());
+    
+    let snapshot = Arc::new(RwLock::new(manager.snapshot()));
+    let queue = Arc::new(tokio::sync::Mutex::new(manager));
+    let health = Arc::new(Health::new(Duration::minutes(5), Utc::now()));
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    listener.set_nonblocking(true).unwrap();
+    let url = format!("http://{}/metrics", listener.local_addr().unwrap());
+    tokio::spawn(server::serve(listener, ServerState::new(queue, snapshot, health)));
+    
+    // Metrics are public, like the health checks
+    let response = reqwest::get(&url).await.unwrap();
+    assert_eq!(response.status(), 200);
+    assert!(response.headers()["content-type"].to_str().unwrap().starts_with("text/plain"));
+    let body = response.text().await.unwrap();
+    
+    // Metrics are process-wide, so they're labelled with the repository
+    for series in [
+        "garry_queue_length{repository=\"acme/metrics\",state=\"pending\"} 0",
+        "garry_queue_time_seconds_count{repository=\"acme/metrics\",result=\"success\"} 1",
+        "garry_ci_wait_seconds_count{repository=\"acme/metrics\",result=\"success\"}",
+        "garry_merges_total{repository=\"acme/metrics\",result=\"success\"} 1",
+    ] {
+        assert!(body.contains(series), "missing {} in:\n{}", series, body);
+    }
+}
+
+#[tokio::test]
+async fn test_dashboard() {
+    use std::sync::{Arc, RwLock};
+    use chrono::{Duration, Utc};
+    use garry::bot::queue::QueueManager;
+    use garry::bot::server::{self, Health, ServerState};
+    
+    let adapter = MockVcsAdapter::new();
+    let mut reviews = Vec::new();
+    for branch in ["feature/flaky", "feature/waiting"] {
+        let (review_id, _) = adapter.create_review(branch, "main", "Change", "Description").await.unwrap();
+        adapter.approve_review(&review_id, None).await.unwrap();
+        adapter.set_ci_status(&review_id, CiStatus::Success);
+        reviews.push(review_id);
+    }
+    let (flaky, waiting) = (&reviews[0], &reviews[1]);
+    
+    let mut manager = QueueManager::new(Box::new(adapter.clone()));
+    manager.add_to_queue(flaky.clone()).await.unwrap();
+    manager.add_to_queue(waiting.clone()).await.unwrap();
+    adapter.set_ci_status(flaky, CiStatus::Failed(vec!["unit tests".to_string()]));
+    manager.process_queue().await.unwrap();
+    
+    let snapshot = Arc::new(RwLock::new(manager.snapshot()));
+    let queue = Arc::new(tokio::sync::Mutex::new(manager));
+    let health = Arc::new(Health::new(Duration::minutes(5), Utc::now()));
+    let serve = |state: ServerState| {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.set_nonblocking(true).unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(server::serve(listener, state));
+        url
+    };
+    let url = serve(ServerState::new(queue.clone(), snapshot.clone(), health.clone()).with_dashboard(15));
+    
+    // The front page lists the queue and the failure, and reloads itself
+    let response = reqwest::get(format!("{}/", url)).await.unwrap();
+    assert_eq!(response.status(), 200);
+    assert!(response.headers()["content-type"].to_str().unwrap().starts_with("text/html"));
+    let page = response.text().await.unwrap();
+    assert!(page.contains("<meta http-equiv=\"refresh\" content=\"15\">"));
+    assert!(page.contains(&format!("<a href=\"/reviews/{}\">", waiting)));
+    assert!(page.contains("<td>mock-user</td><td>feature/waiting</td><td>main</td>"));
+    assert!(page.contains("unit tests"));
+    assert!(!page.contains("<script") && !page.contains("src=\"http"));
+    
+    // Text from CI and the platform is escaped
+    assert!(page.contains("[&quot;unit tests&quot;]"));
+    
+    // Each review has a timeline
+    let page = reqwest::get(format!("{}/reviews/{}", url, flaky)).await.unwrap().text().await.unwrap();
+    assert!(page.contains("Failed "));
+    assert!(page.contains("queued") && page.contains("testing"));
+    let missing = reqwest::get(format!("{}/reviews/999", url)).await.unwrap();
+    assert_eq!(missing.status(), 404);
+    
+    // The dashboard shows the queue as the bot last published it
+    queue.lock().await.pause();
+    assert!(!reqwest::get(format!("{}/", url)).await.unwrap().text().await.unwrap().contains("The queue is paused"));
+    *snapshot.write().unwrap() = queue.lock().await.snapshot();
+    assert!(reqwest::get(format!("{}/", url)).await.unwrap().text().await.unwrap().contains("The queue is paused"));
+    
+    // The dashboard is only served when enabled
+    let url = serve(ServerState::new(queue, snapshot, health));
+    assert_eq!(reqwest::get(format!("{}/", url)).await.unwrap().status(), 404);
+}
+
+#[tokio::test]
+async fn test_multi_repository() {
+    use std::sync::{Arc, RwLock};
+    use chrono::{Duration, Utc};
+    use garry::bot::queue::QueueManager;
+    use garry::bot::server::{self, Health, ServerState};
+    use garry::utils::config::Config;
+    use serde_json::{json, Value};
+    
+    // Repositories override the top-level settings they set
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("config.toml");
+    let toml = r#"
+        [vcs]
+        platform = "github"
+        host = "github.com"
+        token = "shared-token"
+        repository = ""
+        
+        [bot]
+        webhook_port = 8080
+        queue_check_interval = 30
+        ci_timeout = 3600
+        main_branch = "main"
+        
+        [git]
+        default_remote = "origin"
+        squash_base = "main"
+        
+        [[repositories]]
+        repository = "acme/api"
+        
+        [[repositories]]
+        repository = "acme/legacy"
+        main_branch = "master"
+        token = "legacy-token"
+        
+        [repositories.policy]
+        required_approvals = 2
+    "#;
+    std::fs::write(&path, toml).unwrap();
+    let config = Config::load(&path).unwrap();
+    let configs = config.repository_configs();
+    assert_eq!(configs.len(), 2);
+    assert_eq!(configs[0].vcs.repository, "acme/api");
+    assert_eq!(configs[0].vcs.token, "shared-token");
+    assert_eq!(configs[0].bot.main_branch, "main");
+    assert_eq!(configs[0].policy.required_approvals, 1);
+    assert_eq!(configs[1].vcs.token, "legacy-token");
+    assert_eq!(configs[1].bot.main_branch, "master");
+    assert_eq!(configs[1].policy.required_approvals, 2);
+    
+    // Every repository must be valid, and listed once
+    std::fs::write(&path, toml.replace("acme/legacy", "acme/api")).unwrap();
+    assert!(Config::load(&path).is_err());
+    std::fs::write(&path, toml.replace("acme/legacy", "legacy")).unwrap();
+    assert!(Config::load(&path).is_err());
+    
+    // Each repository has its own queue
+    let mut bots = Vec::new();
+    for name in ["acme/api", "acme/legacy"] {
+        let adapter = MockVcsAdapter::new();
+        let (review_id, _) = adapter.create_review("feature/change", "main", "Change", "Description").await.unwrap();
+        adapter.approve_review(&review_id, None).await.unwrap();
+        adapter.set_ci_status(&review_id, CiStatus::Success);
+        let queue = QueueManager::new(Box::new(adapter)).with_repository(name.to_string());
+        let snapshot = Arc::new(RwLock::new(queue.snapshot()));
+        // Started long enough ago to be stalled until the first pass
+        let health = Arc::new(Health::new(Duration::minutes(5), Utc::now() - Duration::hours(1)));
+        bots.push((name, Arc::new(tokio::sync::Mutex::new(queue)), snapshot, health));
+    }
+    let mut state = ServerState::new(bots[0].1.clone(), bots[0].2.clone(), bots[0].3.clone())
+        .with_token("secret".to_string())
+        .with_dashboard(15);
+    for (name, queue, snapshot, health) in &bots {
+        state = state.with_repository(name.to_string(), queue.clone(), snapshot.clone(), health.clone());
+    }
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    listener.set_nonblocking(true).unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(server::serve(listener, state));
+    let client = reqwest::Client::new();
+    
+    // The API for each repository is served under its name
+    let queued = client.post(format!("{}/repos/acme/legacy/api/queue", url))
+        .bearer_auth("secret")
+        .json(&json!({ "review": "1" }))
+        .send().await.unwrap();
+    assert_eq!(queued.status(), 200);
+    assert_eq!(bots[1].1.lock().await.get_queue().len(), 1);
+    assert!(bots[0].1.lock().await.get_queue().is_empty());
+    // There's no root API to change the wrong repository's queue through
+    let root = client.get(format!("{}/api/queue", url)).bearer_auth("secret").send().await.unwrap();
+    assert_eq!(root.status(), 404);
+    
+    // So is the dashboard, with links to the other repositories
+    let page = client.get(format!("{}/repos/acme/legacy", url)).send().await.unwrap().text().await.unwrap();
+    assert!(page.contains("Merge queue: acme/legacy"));
+    assert!(page.contains("href=\"/repos/acme/legacy/reviews/1\""));
+    assert!(page.contains("href=\"/repos/acme/api\""));
+    
+    // The bot is only ready when every repository is
+    bots[0].3.tick(Utc::now());
+    let ready = client.get(format!("{}/readyz", url)).send().await.unwrap();
+    assert_eq!(ready.status(), 503);
+    let body: Value = ready.json().await.unwrap();
+    assert_eq!(body["starting"], json!(["acme/legacy"]));
+    let ready = client.get(format!("{}/repos/acme/api/readyz", url)).send().await.unwrap();
+    assert_eq!(ready.status(), 200);
+    // One stalled repository fails its own health check, not the bot's
+    let live = client.get(format!("{}/healthz", url)).send().await.unwrap();
+    assert_eq!(live.status(), 200);
+    let body: Value = live.json().await.unwrap();
+    assert_eq!(body["stalled"], json!(["acme/legacy"]));
+    let live = client.get(format!("{}/repos/acme/legacy/healthz", url)).send().await.unwrap();
+    assert_eq!(live.status(), 503);
+    bots[1].3.tick(Utc::now());
+    let ready = client.get(format!("{}/readyz", url)).send().await.unwrap();
+    assert_eq!(ready.status(), 200);
+}
+
+#[tokio::test]
+async fn test_target_branches() {
+    use garry::bot::notifier::Notifier;
+    use garry::bot::queue::{Priority, QueueManager, QueueStatus};
+    use garry::review::ReviewDecision;
+    use garry::review::policy::ApprovalPolicy;
+    use garry::utils::config::PolicyConfig;
+    
+    let adapter = MockVcsAdapter::new();
+    let mut reviews = Vec::new();
+    for target in ["main", "release/2.3", "feature/base"] {
+        let (review_id, _) = adapter.create_review("feature/change", target, "Change", "Description").await.unwrap();
+        adapter.approve_review(&review_id, None).await.unwrap();
+        adapter.set_ci_status(&review_id, CiStatus::Success);
+        reviews.push(review_id);
+    }
+    let (main, release, stacked) = (&reviews[0], &reviews[1], &reviews[2]);
+    assert_eq!(adapter.get_review_status(release).await.unwrap().target_branch, "release/2.3");
+    
+    // Release branches need two approvals
+    let release_policy = ApprovalPolicy::new(PolicyConfig { required_approvals: 2, ..PolicyConfig::default() });
+    let mut queue = QueueManager::new(Box::new(adapter.clone()))
+        .with_target("release/*".to_string(), release_policy)
+        .with_notifier(Notifier::new(Box::new(adapter.clone())));
+    assert!(queue.add_to_queue(release.clone()).await.is_err());
+    adapter.add_vote(release, "alice", ReviewDecision::Approved);
+    
+    // Reviews into branches the queue doesn't merge into are left alone
+    let refused = queue.add_to_queue(stacked.clone()).await.unwrap_err();
+    assert!(refused.to_string().contains("feature/base"));
+    queue.add_to_queue(main.clone()).await.unwrap();
+    queue.discover_and_queue_reviews().await.unwrap();
+    let queued: Vec<_> = queue.get_queue().iter().map(|e| (e.review_id.clone(), e.target.clone())).collect();
+    assert_eq!(queued, [(main.clone(), "main".to_string()), (release.clone(), "release/2.3".to_string())]);
+    assert_eq!(queue.targets(), ["main", "release/2.3"]);
+    assert_eq!(queue.target_position(release), Some((0, 1)));
+    
+    // Each target's queue advances on its own: main waiting on CI doesn't
+    // hold up the release branch
+    adapter.set_ci_status(main, CiStatus::Running);
+    queue.process_queue().await.unwrap();
+    assert_eq!(queue.get_queue()[0].status, QueueStatus::Testing);
+    assert_eq!(queue.get_queue()[1].status, QueueStatus::Merging);
+    assert!(adapter.comments(release)[0].contains("position: 1 of 1 for release/2.3"));
+    queue.process_queue().await.unwrap();
+    assert_eq!(queue.get_queue().len(), 1);
+    assert!(adapter.comments(release)[0].contains("merged to release/2.3"));
+    let outcome = queue.recent_outcomes().back().unwrap();
+    assert_eq!((&outcome.review_id, outcome.target.as_str()), (release, "release/2.3"));
+    
+    // Changes made while a pass runs unlocked are kept when it's applied
+    adapter.set_ci_status(main, CiStatus::Success);
+    let pass = queue.plan_pass();
+    queue.set_priority(main, Priority::High).unwrap();
+    let outcome = pass.run(&adapter).await;
+    queue.finish_pass(outcome).send().await.unwrap();
+    let entry = &queue.get_queue()[0];
+    assert_eq!((&entry.status, entry.priority), (&QueueStatus::Merging, Priority::High));
+    let history: Vec<_> = entry.history.iter().map(|e| e.description.as_str()).collect();
+    assert_eq!(history[history.len() - 2..], ["priority set to high", "CI passed, merging"]);
+    
+    // A review held while it merged still counts as merged
+    let pass = queue.plan_pass();
+    queue.hold(main);
+    let outcome = pass.run(&adapter).await;
+    queue.finish_pass(outcome).send().await.unwrap();
+    assert!(queue.get_queue().is_empty());
+    let outcome = queue.recent_outcomes().back().unwrap();
+    assert_eq!((&outcome.review_id, &outcome.failure), (main, &None));
+    assert!(adapter.comments(main).last().unwrap().contains("merged to main"));
+}
+
+#[tokio::test]
+async fn test_backports() {
+    use garry::bot::backport::{self, Backporter, BackportOutcome};
+    use garry::bot::queue::QueueManager;
+    use garry::git::GitRepository;
+    use garry::utils::config::BackportConfig;
+    
+    // A release branch that diverged from main in lib.txt
+    let origin_dir = tempfile::tempdir().unwrap();
+    git2::Repository::init_bare(origin_dir.path()).unwrap();
+    let origin_path = origin_dir.path().to_str().unwrap();
+    let (dir, local, _) = init_repo();
+    local.remote("origin", origin_path).unwrap();
+    commit_file(&local, "lib.txt", "a\n", "Add lib");
+    let base = local.head().unwrap().peel_to_commit().unwrap().id();
+    checkout(&local, "release/2.3", Some(base));
+    commit_file(&local, "lib.txt", "release\n", "Release tweak");
+    checkout(&local, "main", None);
+    let fix = commit_file(&local, "fix.txt", "fixed\n", "Fix crash (#1)");
+    let clash = commit_file(&local, "lib.txt", "main\n", "Rework lib (#2)");
+    let repo = GitRepository::open(dir.path()).unwrap();
+    repo.push_branch("main", "origin").unwrap();
+    repo.push_branch("release/2.3", "origin").unwrap();
+    
+    // The bot works in a bare clone of its own
+    let checkout_dir = tempfile::tempdir().unwrap();
+    git2::Repository::init_bare(checkout_dir.path()).unwrap().remote("origin", origin_path).unwrap();
+    
+    // A review labelled for backport is picked up once the queue merges it
+    let adapter = MockVcsAdapter::new();
+    let (review_id, _) = adapter.create_review("fix", "main", "Fix crash", "Description").await.unwrap();
+    adapter.approve_review(&review_id, None).await.unwrap();
+    adapter.set_ci_status(&review_id, CiStatus::Success);
+    adapter.set_labels(&review_id, &["backport/release-2.3", "bug"]);
+    adapter.set_merge_commit(&review_id, &fix.to_string());
+    let config = BackportConfig {
+        checkout: Some(checkout_dir.path().to_str().unwrap().to_string()),
+        ..BackportConfig::default()
+    };
+    let mut backporter = Backporter::new(&config, "origin", chrono::Utc::now()).unwrap();
+    let mut queue = QueueManager::new(Box::new(adapter.clone()));
+    queue.add_to_queue(review_id.clone()).await.unwrap();
+    queue.process_queue().await.unwrap();
+    queue.process_queue().await.unwrap();
+    assert!(queue.get_queue().is_empty());
+    assert_eq!(backporter.poll(&adapter, &queue.snapshot()).await.unwrap(), 1);
+    assert_eq!(backporter.poll(&adapter, &queue.snapshot()).await.unwrap(), 0);
+    
+    // The cherry-pick is pushed on top of the release branch and opened as a review into it
+    let origin = git2::Repository::open_bare(origin_dir.path()).unwrap();
+    let picked = origin.find_reference("refs/heads/backport/1-release-2.3").unwrap().peel_to_commit().unwrap();
+    let release = origin.find_reference("refs/heads/release/2.3").unwrap().peel_to_commit().unwrap();
+    assert_eq!(picked.parent_id(0).unwrap(), release.id());
+    assert!(picked.message().unwrap().contains(&format!("(cherry picked from commit {})", fix)));
+    assert!(picked.tree().unwrap().get_name("fix.txt").is_some());
+    let opened = adapter.list_open_reviews().await.unwrap();
+    assert_eq!(opened.len(), 1);
+    assert_eq!(opened[0].title, "[release/2.3] Fix crash (#1)");
+    assert_eq!(adapter.get_review_status(&opened[0].id).await.unwrap().target_branch, "release/2.3");
+    assert!(adapter.comments(&review_id).iter().any(|c| c.contains(&format!("Backported to release/2.3 in #{}", opened[0].id))));
+    
+    // Backporting again is refused rather than opening a duplicate
+    let checkout_path = checkout_dir.path();
+    let again = backport::backport(&adapter, checkout_path, "origin", &review_id, "release/2.3").await.unwrap_err();
+    assert!(again.to_string().contains("already backported"));
+    
+    // Conflicts are reported on the original review, as `garry backport` would
+    let (conflicting, _) = adapter.create_review("rework", "main", "Rework lib", "Description").await.unwrap();
+    assert!(backport::backport(&adapter, checkout_path, "origin", &conflicting, "release/2.3").await.is_err());
+    adapter.set_merge_commit(&conflicting, &clash.to_string());
+    adapter.merge_review(&conflicting).await.unwrap();
+    let outcome = backport::backport(&adapter, checkout_path, "origin", &conflicting, "release/2.3").await.unwrap();
+    assert_eq!(outcome, BackportOutcome::Conflicts(vec!["lib.txt".to_string()]));
+    let report = adapter.comments(&conflicting).pop().unwrap();
+    assert!(report.contains("conflicts in") && report.contains("- `lib.txt`"));
+    assert!(report.contains(&format!("git cherry-pick -x {}", clash)));
+    assert!(origin.find_reference("refs/heads/backport/3-release-2.3").is_err());
+    
+    // A conflicted backport isn't mistaken for a done one, so it can be tried again
+    let outcome = backport::backport(&adapter, checkout_path, "origin", &conflicting, "release/2.3").await.unwrap();
+    assert_eq!(outcome, BackportOutcome::Conflicts(vec!["lib.txt".to_string()]));
+}
+
+#[tokio::test]
+async fn test_github_pagination() {
+    use garry::bot::adapters::GithubAdapter;
+    use garry::review::{ReviewId, ReviewState};
+    use mockito::Matcher;
+    use serde_json::json;
+    
+    let mut server = mockito::Server::new_async().await;
+    let adapter = GithubAdapter::new(server.url(), "token".to_string(), "org/repo".to_string()).unwrap();
+    let pr = |number: u64| json!({
+        "number": number,
+        "title": format!("Change {}", number),
+        "html_url": format!("https://github.com/org/repo/pull/{}", number),
+        "created_at": "2026-10-01T00:00:00Z",
+        "updated_at": "2026-10-02T00:00:00Z",
+        "user": { "login": "alice" },
+        "head": { "ref": format!("change-{}", number) },
+    });
+    
+    // Open reviews past the first page are followed through the Link header
+    let next = format!("{}/api/v3/repos/org/repo/pulls?state=open&per_page=100&page=2", server.url());
+    server.mock("GET", "/api/v3/repos/org/repo/pulls")
+        .match_query(Matcher::Exact("state=open&per_page=100".into()))
+        .with_header("link", &format!("<{}>; rel=\"next\", <{}>; rel=\"last\"", next, next))
+        .with_body(json!([pr(1), pr(2)]).to_string())
+        .create_async()
+        .await;
+    server.mock("GET", "/api/v3/repos/org/repo/pulls")
+        .match_query(Matcher::UrlEncoded("page".into(), "2".into()))
+        .with_body(json!([pr(3)]).to_string())
+        .create_async()
+        .await;
+    let open = adapter.list_open_reviews().await.unwrap();
+    assert_eq!(open.iter().map(|r| r.id.as_str()).collect::<Vec<_>>(), ["1", "2", "3"]);
+    assert_eq!(adapter.list_pending_reviews().await.unwrap().len(), 3);
+    
+    // So are comments, only asking for the ones touched since the given time
+    let comment = |id: u64| json!({
+        "id": id, "user": { "login": "alice" }, "body": "/garry merge", "created_at": "2026-10-02T00:00:00Z",
+    });
+    let next = format!("{}/api/v3/repos/org/repo/issues/1/comments?per_page=100&since=2026-10-01T00:00:00Z&page=2", server.url());
+    server.mock("GET", "/api/v3/repos/org/repo/issues/1/comments")
+        .match_query(Matcher::Exact("per_page=100&since=2026-10-01T00:00:00Z".into()))
+        .with_header("link", &format!("<{}>; rel=\"next\"", next))
+        .with_body(json!([comment(10)]).to_string())
+        .create_async()
+        .await;
+    server.mock("GET", "/api/v3/repos/org/repo/issues/1/comments")
+        .match_query(Matcher::UrlEncoded("page".into(), "2".into()))
+        .with_body(json!([comment(11)]).to_string())
+        .create_async()
+        .await;
+    let since = "2026-10-01T00:00:00Z".parse().unwrap();
+    let comments = adapter.list_comments(&ReviewId::new("1".to_string()), Some(since)).await.unwrap();
+    assert_eq!(comments.iter().map(|c| c.id.as_str()).collect::<Vec<_>>(), ["10", "11"]);
+    
+    // And reviews, so a request for changes on a later page still blocks
+    server.mock("GET", "/api/v3/repos/org/repo/pulls/2")
+        .with_body(json!({
+            "state": "open",
+            "mergeable": true,
+            "user": { "login": "alice" },
+            "head": { "sha": "def456", "ref": "change-2" },
+            "base": { "ref": "main" },
+        }).to_string())
+        .create_async()
+        .await;
+    let next = format!("{}/api/v3/repos/org/repo/pulls/2/reviews?per_page=100&page=2", server.url());
+    server.mock("GET", "/api/v3/repos/org/repo/pulls/2/reviews")
+        .match_query(Matcher::Exact("per_page=100".into()))
+        .with_header("link", &format!("<{}>; rel=\"next\"", next))
+        .with_body(json!([{ "user": { "login": "carol" }, "state": "APPROVED" }]).to_string())
+        .create_async()
+        .await;
+    server.mock("GET", "/api/v3/repos/org/repo/pulls/2/reviews")
+        .match_query(Matcher::UrlEncoded("page".into(), "2".into()))
+        .with_body(json!([{ "user": { "login": "dave" }, "state": "CHANGES_REQUESTED" }]).to_string())
+        .create_async()
+        .await;
+    server.mock("GET", "/api/v3/repos/org/repo/commits/def456")
+        .with_body(json!({ "commit": { "committer": { "date": "2026-10-01T00:00:00Z" } } }).to_string())
+        .create_async()
+        .await;
+    server.mock("GET", "/api/v3/repos/org/repo/pulls/2/files")
+        .match_query(Matcher::Any)
+        .with_body(json!([]).to_string())
+        .create_async()
+        .await;
+    server.mock("GET", "/api/v3/repos/org/repo/commits/def456/check-runs")
+        .with_body(json!({ "check_runs": [] }).to_string())
+        .create_async()
+        .await;
+    let status = adapter.get_review_status(&ReviewId::new("2".to_string())).await.unwrap();
+    assert_eq!(status.state, ReviewState::ChangesRequested);
+    assert_eq!(status.votes.iter().map(|v| v.reviewer.as_str()).collect::<Vec<_>>(), ["carol", "dave"]);
+}
+
+#[tokio::test]
+async fn test_github_review_status() {
+    use garry::bot::adapters::GithubAdapter;
+    use garry::review::{ReviewDecision, ReviewId};
+    use mockito::Matcher;
+    use serde_json::json;
+    
+    let mut server = mockito::Server::new_async().await;
+    let adapter = GithubAdapter::new(server.url(), "token".to_string(), "org/repo".to_string()).unwrap();
+    server.mock("GET", "/api/v3/repos/org/repo/pulls/1")
+        .with_body(json!({
+            "state": "open",
+            "mergeable": true,
+            "user": { "login": "alice" },
+            "head": { "sha": "abc123", "ref": "feature" },
+            "base": { "ref": "main" },
+            "changed_files": 2,
+        }).to_string())
+        .create_async()
+        .await;
+    server.mock("GET", "/api/v3/repos/org/repo/pulls/1/reviews")
+        .match_query(Matcher::Any)
+        .with_body(json!([
+            { "user": { "login": "bob" }, "state": "COMMENTED", "body": "Label: Code-Review=+2" },
+            { "user": { "login": "carol" }, "state": "APPROVED", "body": "LGTM\n\nLabel: Code-Review=+2" },
+        ]).to_string())
+        .create_async()
+        .await;
+    server.mock("GET", "/api/v3/repos/org/repo/commits/abc123")
+        .with_body(json!({ "commit": { "committer": { "date": "2026-10-01T00:00:00Z" } } }).to_string())
+        .create_async()
+        .await;
+    let next = format!("{}/api/v3/repos/org/repo/pulls/1/files?per_page=100&page=2", server.url());
+    server.mock("GET", "/api/v3/repos/org/repo/pulls/1/files")
+        .match_query(Matcher::Exact("per_page=100".into()))
+        .with_header("link", &format!("<{}>; rel=\"next\"", next))
+        .with_body(json!([{ "filename": "src/lib.rs" }]).to_string())
+        .create_async()
+        .await;
+    server.mock("GET", "/api/v3/repos/org/repo/pulls/1/files")
+        .match_query(Matcher::UrlEncoded("page".into(), "2".into()))
+        .with_body(json!([{ "filename": "README.md" }]).to_string())
+        .create_async()
+        .await;
+    server.mock("GET", "/api/v3/repos/org/repo/commits/abc123/check-runs")
+        .with_body(json!({ "check_runs": [] }).to_string())
+        .create_async()
+        .await;
+    let thread = |id: &str, resolved: bool| json!({
+        "id": id, "path": "src/lib.rs", "originalLine": 1, "diffSide": "RIGHT",
+        "isResolved": resolved, "isOutdated": false, "comments": { "nodes": [] },
+    });
+    let threads = |nodes: serde_json::Value, end_cursor: Option<&str>| json!({ "data": { "repository": { "pullRequest": {
+        "reviewThreads": { "pageInfo": { "hasNextPage": end_cursor.is_some(), "endCursor": end_cursor }, "nodes": nodes },
+    } } } }).to_string();
+    let first_page = server.mock("POST", "/api/graphql")
+        .match_body(Matcher::PartialJson(json!({ "variables": { "after": null } })))
+        .with_body(threads(json!([thread("t1", true)]), Some("c1")))
+        .create_async()
+        .await;
+    let second_page = server.mock("POST", "/api/graphql")
+        .match_body(Matcher::PartialJson(json!({ "variables": { "after": "c1" } })))
+        .with_body(threads(json!([thread("t2", false)]), None))
+        .create_async()
+        .await;
+    
+    let review_id = ReviewId::new("1".to_string());
+    let status = adapter.get_review_status(&review_id).await.unwrap();
+    assert_eq!(status.changed_files, vec!["src/lib.rs".to_string(), "README.md".to_string()]);
+    assert!(!status.changed_files_truncated);
+    // Label trailers only count on reviews that approve or request changes
+    let vote = |reviewer: &str| status.votes.iter().find(|v| v.reviewer == reviewer).unwrap();
+    assert_eq!(vote("bob").decision, ReviewDecision::Commented);
+    assert!(vote("bob").labels.is_empty());
+    assert_eq!(vote("carol").labels.get("Code-Review"), Some(&2));
+    // Threads past the first page are followed by cursor
+    assert_eq!(status.threads.iter().map(|t| t.id.as_str()).collect::<Vec<_>>(), ["t1", "t2"]);
+    
+    // Without threads, the status only fails when they decide approval
+    first_page.remove_async().await;
+    second_page.remove_async().await;
+    server.mock("POST", "/api/graphql").with_status(502).create_async().await;
+    assert!(adapter.get_review_status(&review_id).await.unwrap().threads.is_empty());
+    let strict = GithubAdapter::new(server.url(), "token".to_string(), "org/repo".to_string()).unwrap()
+        .with_threads_required(true);
+    assert!(strict.get_review_status(&review_id).await.is_err());
+    
+    // Posting a comment gives back its ID, so it can be edited later
+    server.mock("POST", "/api/v3/repos/org/repo/issues/1/comments")
+        .with_status(201)
+        .with_body(json!({ "id": 42 }).to_string())
+        .create_async()
+        .await;
+    assert_eq!(adapter.post_comment(&review_id, "Hello").await.unwrap(), "42");
 }